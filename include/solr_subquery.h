@@ -0,0 +1,95 @@
+#ifndef SOLR_SUBQUERY_H
+#define SOLR_SUBQUERY_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Opaque handle of a query, `solr_subquery_t` in C
+ */
+typedef struct solr_subquery_t solr_subquery_t;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Parses a query from a NUL terminated URL, returns null on error
+ *
+ * # Safety
+ *
+ * `url` must be a valid NUL terminated string, or null.
+ */
+struct solr_subquery_t *solr_subquery_new(const char *url);
+
+/**
+ * Inner joins two queries into a new one, returns null on error
+ *
+ * # Safety
+ *
+ * Both queries must have been returned by this library and not freed, or be null.
+ */
+struct solr_subquery_t *solr_subquery_inner_join(const struct solr_subquery_t *query,
+                                                 const struct solr_subquery_t *other);
+
+/**
+ * Merges two queries with an operator (0: AND, 1: OR, 2: NOT), returns null on error
+ *
+ * # Safety
+ *
+ * Both queries must have been returned by this library and not freed, or be null.
+ */
+struct solr_subquery_t *solr_subquery_merge(const struct solr_subquery_t *query,
+                                            const struct solr_subquery_t *other,
+                                            int operator_);
+
+/**
+ * Gets the inverse of a query
+ *
+ * # Safety
+ *
+ * The query must have been returned by this library and not freed, or be null.
+ */
+struct solr_subquery_t *solr_subquery_inverse(const struct solr_subquery_t *query);
+
+/**
+ * Gets the URL of a query, to be freed with `solr_subquery_string_free`, returns null on error
+ *
+ * # Safety
+ *
+ * The query must have been returned by this library and not freed, or be null.
+ */
+char *solr_subquery_url(const struct solr_subquery_t *query);
+
+/**
+ * Gets the message of the last error of the calling thread, null if none
+ *
+ * The string is owned by the library and valid until the next failing call.
+ */
+const char *solr_subquery_last_error(void);
+
+/**
+ * Frees a query
+ *
+ * # Safety
+ *
+ * The query must have been returned by this library and not freed yet, or be null.
+ */
+void solr_subquery_free(struct solr_subquery_t *query);
+
+/**
+ * Frees a string returned by this library
+ *
+ * # Safety
+ *
+ * The string must have been returned by this library and not freed yet, or be null.
+ */
+void solr_subquery_string_free(char *string);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* SOLR_SUBQUERY_H */
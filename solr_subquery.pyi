@@ -0,0 +1,69 @@
+# Type stubs of the native module, keep in sync with src/bindings (checked by tests/python_stubs.rs)
+from typing import Any, ClassVar, Dict, Iterator, List, Optional, Sequence, Tuple, Union
+
+class SolrSubqueryError(ValueError): ...
+class InvalidUrl(SolrSubqueryError): ...
+class MissingQParameter(SolrSubqueryError): ...
+class MultipleQParameters(SolrSubqueryError): ...
+class DifferentHosts(SolrSubqueryError): ...
+class DifferentPorts(SolrSubqueryError): ...
+class DifferentPaths(SolrSubqueryError): ...
+class ConflictingEdismaxParams(SolrSubqueryError): ...
+class EmptyChain(SolrSubqueryError): ...
+class InvalidOperator(SolrSubqueryError): ...
+class HttpError(SolrSubqueryError): ...
+
+class Operator:
+    And: Operator
+    Or: Operator
+    Not: Operator
+
+class SolrQuery:
+    def __init__(self, url: str) -> None: ...
+    def inner_join(self, query: SolrQuery) -> SolrQuery: ...
+    def merge(self, query: SolrQuery, operator: Operator) -> SolrQuery: ...
+    def inverse(self) -> SolrQuery: ...
+    def url(self) -> str: ...
+    def inverse_url(self) -> str: ...
+    def get_param(self, name: str) -> Optional[str]: ...
+    def with_param(self, name: str, value: str) -> SolrQuery: ...
+    def params(self) -> List[Tuple[str, str]]: ...
+    def to_dict(self) -> Dict[str, Any]: ...
+    @staticmethod
+    def from_dict(dict: Dict[str, Any]) -> SolrQuery: ...
+    # Only available when built with the `client` feature
+    def execute(self) -> List[Dict[str, Any]]: ...
+    def iter_docs(self, batch_size: int = 500) -> DocIterator: ...
+    # Only available when built with the `dataframe` feature
+    def to_dataframe(self, fields: Optional[List[str]] = None) -> Any: ...
+    def __eq__(self, other: object) -> bool: ...
+    def __ne__(self, other: object) -> bool: ...
+    def __hash__(self) -> int: ...
+
+QueryTree = Union[SolrQuery, str, Dict[str, Any]]
+
+class SolrQueryChain:
+    def __init__(self, queries: Sequence[SolrQuery]) -> None: ...
+    @staticmethod
+    def from_tree(tree: QueryTree) -> SolrQueryChain: ...
+    def add_subquery(self, url: str) -> None: ...
+    def append(self, query: SolrQuery) -> None: ...
+    def __len__(self) -> int: ...
+    def __getitem__(self, index: int) -> SolrQuery: ...
+    # Only available when built with the `client` feature
+    def execute_all(self) -> List[List[Dict[str, Any]]]: ...
+    def __iter__(self) -> SolrQueryChain: ...
+    def __next__(self) -> SolrQuery: ...
+    def __eq__(self, other: object) -> bool: ...
+    def __ne__(self, other: object) -> bool: ...
+    # Unhashable, the chain is mutable
+    __hash__: ClassVar[None]  # type: ignore[assignment]
+
+class SolrReplicas:
+    def __init__(self, replicas: Sequence[Tuple[str, int]]) -> None: ...
+    def next_base(self) -> str: ...
+    def route(self, query: SolrQuery) -> SolrQuery: ...
+
+class DocIterator(Iterator[Dict[str, Any]]):
+    def __iter__(self) -> DocIterator: ...
+    def __next__(self) -> Dict[str, Any]: ...
@@ -0,0 +1,346 @@
+use crate::errors::SolrSubqueryError;
+use crate::query_ast::QueryAst;
+use crate::solr_query::SolrQuery;
+
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+/// How a routed alias distributes documents among its collections
+pub enum AliasRouter {
+    /// Collections named `<alias>__TRA__<start date>`
+    Time,
+    /// Collections named `<alias>__CRA__<category>`
+    Category,
+    /// A plain alias
+    None,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A constraint on the routing field of an alias
+pub enum RouteConstraint {
+    /// `field:[from TO to]`, `*` and date math bounds are open
+    Range(String, String),
+    /// `field:value`
+    Value(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A Solr alias and the concrete collections behind it
+pub struct RoutedAlias {
+    pub name: String,
+    pub router: AliasRouter,
+    pub field: Option<String>,
+    pub collections: Vec<String>,
+}
+
+impl RoutedAlias {
+    /// Reads an alias from a Collections API `LISTALIASES` response
+    pub fn from_list_aliases(
+        response: &Value,
+        name: &str,
+    ) -> Result<RoutedAlias, SolrSubqueryError> {
+        let collections = response["aliases"][name]
+            .as_str()
+            .ok_or_else(|| SolrSubqueryError::InvalidResponse(format!("unknown alias {}", name)))?
+            .split(',')
+            .map(|c| c.trim().to_string())
+            .collect();
+
+        let properties = &response["properties"][name];
+        let router = match properties["router.name"].as_str() {
+            Some("time") => AliasRouter::Time,
+            Some("category") => AliasRouter::Category,
+            _ => AliasRouter::None,
+        };
+
+        Ok(RoutedAlias {
+            name: name.to_string(),
+            router,
+            field: properties["router.field"].as_str().map(|f| f.to_string()),
+            collections,
+        })
+    }
+
+    /// Fetches an alias through the Collections API of the Solr instance serving a query
+    #[cfg(feature = "client")]
+    pub fn fetch(
+        client: &crate::client::SolrClient,
+        query: &SolrQuery,
+        name: &str,
+    ) -> Result<RoutedAlias, SolrSubqueryError> {
+        let mut url = crate::client::admin_url(query, "admin/collections");
+        url.query_pairs_mut()
+            .append_pair("action", "LISTALIASES")
+            .append_pair("wt", "json");
+
+        RoutedAlias::from_list_aliases(&client.get_json_uncached(&url)?, name)
+    }
+
+    /// Gets the collections that may hold documents matching a constraint on the routing field
+    pub fn collections_for(&self, constraint: &RouteConstraint) -> Vec<String> {
+        match (&self.router, constraint) {
+            (AliasRouter::Time, RouteConstraint::Range(from, to)) => {
+                self.time_collections(from, to)
+            }
+            (AliasRouter::Time, RouteConstraint::Value(value)) => {
+                self.time_collections(value, value)
+            }
+            (AliasRouter::Category, RouteConstraint::Value(value)) => self
+                .collections
+                .iter()
+                .filter(|c| self.suffix(c, "__CRA__") == Some(value.as_str()))
+                .cloned()
+                .collect(),
+            _ => self.collections.clone(),
+        }
+    }
+
+    /// Restricts a query to the collections involved by its constraint on the routing field
+    ///
+    /// The query is left untouched if it has no such constraint, or if no collection matches it.
+    pub fn restrict(&self, query: &SolrQuery) -> Result<SolrQuery, SolrSubqueryError> {
+        let field = match &self.field {
+            Some(field) => field,
+            None => return Ok(query.clone()),
+        };
+
+        match find_constraint(&query.q_param()?, field) {
+            Some(constraint) => Ok(query.with_collections(&self.collections_for(&constraint))),
+            None => Ok(query.clone()),
+        }
+    }
+
+    fn suffix<'a>(&self, collection: &'a str, separator: &str) -> Option<&'a str> {
+        collection
+            .strip_prefix(&self.name)
+            .and_then(|c| c.strip_prefix(separator))
+    }
+
+    /// Collections cover the time span from their start date to the start date of the next one
+    ///
+    /// Bounds that aren't absolute dates, such as `NOW-30DAYS`, can't be resolved here and
+    /// are treated as unbounded.
+    fn time_collections(&self, from: &str, to: &str) -> Vec<String> {
+        let from = absolute_date(from).unwrap_or("*");
+        let to = absolute_date(to).unwrap_or("*");
+
+        // Suffixes are formatted as `yyyy-MM-dd_HH_mm_ss`, with the time parts being optional
+        let mut starts = self
+            .collections
+            .iter()
+            .filter_map(|c| {
+                self.suffix(c, "__TRA__")
+                    .map(|s| (s.replacen('_', "T", 1).replace('_', ":"), c))
+            })
+            .collect::<Vec<_>>();
+        starts.sort();
+
+        starts
+            .iter()
+            .enumerate()
+            .filter(|(i, (start, _))| {
+                let starts_before_end = to == "*" || start.as_str() <= to;
+                let ends_after_start = from == "*"
+                    || starts
+                        .get(i + 1)
+                        .is_none_or(|(next, _)| next.as_str() > from);
+                starts_before_end && ends_after_start
+            })
+            .map(|(_, (_, c))| c.to_string())
+            .collect()
+    }
+}
+
+/// Gets a date bound if it is an absolute date such as `2021-01-01T00:00:00Z`, without date math
+fn absolute_date(bound: &str) -> Option<&str> {
+    let absolute = bound.starts_with(|c: char| c.is_ascii_digit())
+        && bound
+            .chars()
+            .all(|c| c.is_ascii_digit() || "-:.TZ".contains(c));
+
+    absolute.then_some(bound)
+}
+
+/// Finds a `field:value` or `field:[from TO to]` clause every match of a q value satisfies
+///
+/// Only a single clause or the operands of a top-level `AND` qualify, constraints under `OR`,
+/// `NOT` or implicit operators don't bound the routing field of the matches.
+fn find_constraint(q: &str, field: &str) -> Option<RouteConstraint> {
+    let clauses = match QueryAst::parse(q) {
+        QueryAst::Clause(clause) => vec![clause],
+        QueryAst::And(operands) => operands
+            .into_iter()
+            .filter_map(|operand| match operand {
+                QueryAst::Clause(clause) => Some(clause),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    clauses.iter().find_map(|clause| {
+        let value = clause
+            .strip_prefix('+')
+            .unwrap_or(clause)
+            .strip_prefix(field)?
+            .strip_prefix(':')?;
+
+        if value.starts_with('[') || value.starts_with('{') {
+            let (from, to) = value.get(1..value.len() - 1)?.split_once(" TO ")?;
+            return Some(RouteConstraint::Range(
+                from.trim().trim_matches('"').to_string(),
+                to.trim().trim_matches('"').to_string(),
+            ));
+        }
+
+        match value.starts_with('(') {
+            true => None,
+            false => Some(RouteConstraint::Value(value.trim_matches('"').to_string())),
+        }
+    })
+}
+
+#[cfg(test)]
+mod aliases_tests {
+    use super::*;
+    use crate::solr_query::QueryParam;
+    use serde_json::json;
+    use std::error::Error;
+    use urlencoding::decode;
+
+    fn time_alias() -> Result<RoutedAlias, SolrSubqueryError> {
+        RoutedAlias::from_list_aliases(
+            &json!({
+                "aliases": { "logs": "logs__TRA__2021-01-01,logs__TRA__2020-01-01,logs__TRA__2022-01-01" },
+                "properties": { "logs": { "router.name": "time", "router.field": "timestamp" } },
+            }),
+            "logs",
+        )
+    }
+
+    #[test]
+    fn should_resolve_time_routed_collections() -> Result<(), Box<dyn Error>> {
+        let alias = time_alias()?;
+
+        assert_eq!(
+            alias.collections_for(&RouteConstraint::Range(
+                "2020-06-01T00:00:00Z".into(),
+                "2021-03-01T00:00:00Z".into()
+            )),
+            vec!["logs__TRA__2020-01-01", "logs__TRA__2021-01-01"]
+        );
+        assert_eq!(
+            alias.collections_for(&RouteConstraint::Range(
+                "2021-06-01T00:00:00Z".into(),
+                "*".into()
+            )),
+            vec!["logs__TRA__2021-01-01", "logs__TRA__2022-01-01"]
+        );
+        assert_eq!(
+            alias.collections_for(&RouteConstraint::Range(
+                "2021-06-01T00:00:00Z".into(),
+                "2021-06-01T00:00:00Z+1YEAR".into()
+            )),
+            vec!["logs__TRA__2021-01-01", "logs__TRA__2022-01-01"]
+        );
+
+        let query = SolrQuery::new(
+            "http://localhost:8983/solr/logs/select?q=level:error AND timestamp:[2022-02-01T00:00:00Z TO *]",
+        )?;
+        let url_string = alias.restrict(&query)?.url.to_string();
+        let result = decode(&url_string)?;
+
+        assert_eq!(
+            result,
+            "http://localhost:8983/solr/logs/select?q=level:error+AND+timestamp:[2022-02-01T00:00:00Z+TO+*]&collection=logs__TRA__2022-01-01"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_leave_date_math_bounds_open() -> Result<(), Box<dyn Error>> {
+        let alias = time_alias()?;
+
+        let query = SolrQuery::new(
+            format!(
+                "http://localhost:8983/solr/logs/select?q={}",
+                crate::last_n_days("timestamp", 30)
+            )
+            .as_str(),
+        )?;
+        assert_eq!(
+            alias.restrict(&query)?.url.params("collection"),
+            vec!["logs__TRA__2020-01-01,logs__TRA__2021-01-01,logs__TRA__2022-01-01"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_sort_sub_day_time_routed_collections() -> Result<(), Box<dyn Error>> {
+        let alias = RoutedAlias::from_list_aliases(
+            &json!({
+                "aliases": { "logs": "logs__TRA__2021-01-01_12_30,logs__TRA__2021-01-01_06,logs__TRA__2021-01-01_12" },
+                "properties": { "logs": { "router.name": "time", "router.field": "timestamp" } },
+            }),
+            "logs",
+        )?;
+
+        assert_eq!(
+            alias.collections_for(&RouteConstraint::Range(
+                "2021-01-01T12:10:00Z".into(),
+                "2021-01-01T12:20:00Z".into()
+            )),
+            vec!["logs__TRA__2021-01-01_12"]
+        );
+        assert_eq!(
+            alias.collections_for(&RouteConstraint::Range(
+                "2021-01-01T12:40:00Z".into(),
+                "2021-01-01T12:50:00Z".into()
+            )),
+            vec!["logs__TRA__2021-01-01_12_30"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_resolve_category_routed_collections() -> Result<(), Box<dyn Error>> {
+        let alias = RoutedAlias::from_list_aliases(
+            &json!({
+                "aliases": { "docs": "docs__CRA__en,docs__CRA__fr" },
+                "properties": { "docs": { "router.name": "category", "router.field": "lang" } },
+            }),
+            "docs",
+        )?;
+
+        let query = SolrQuery::new("http://localhost:8983/solr/docs/select?q=(lang:fr)")?;
+        assert_eq!(
+            alias.restrict(&query)?.url.params("collection"),
+            vec!["docs__CRA__fr"]
+        );
+
+        let unconstrained = SolrQuery::new("http://localhost:8983/solr/docs/select?q=*:*")?;
+        assert_eq!(alias.restrict(&unconstrained)?, unconstrained);
+
+        for q in [
+            "lang:fr OR lang:en",
+            "*:* AND NOT lang:fr",
+            "lang:fr title:a",
+            "lang:de",
+        ] {
+            let query =
+                SolrQuery::new(format!("http://localhost:8983/solr/docs/select?q={}", q).as_str())?;
+            assert_eq!(alias.restrict(&query)?, query);
+        }
+
+        let query =
+            SolrQuery::new("http://localhost:8983/solr/docs/select?q=title:a AND +lang:en")?;
+        let restricted = alias.restrict(&query)?;
+        assert_eq!(restricted.url.params("collection"), vec!["docs__CRA__en"]);
+        assert_eq!(restricted.negation_url(), query.negation_url());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+use clap::{Parser, Subcommand};
+use solr_subquery::{
+    ExecutionBudget, Operator, QueryChain, SolrClient, SolrQuery, SolrSubqueryError, SubQuery,
+};
+use std::path::PathBuf;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "solr-subquery",
+    about = "Composes and runs chains of Solr queries"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Merges two queries and prints the merged URL
+    Join {
+        first: String,
+        second: String,
+        /// Operator merging the queries: and, or, not
+        #[arg(long, default_value = "and")]
+        op: String,
+    },
+    /// Prints the URL of the inverse of a query
+    Inverse { url: String },
+    /// Prints the URL of every step of a chain definition file (YAML or TOML)
+    Chain {
+        file: PathBuf,
+        /// Prints every document of the final query as JSON lines instead
+        #[arg(long)]
+        execute: bool,
+    },
+    /// Counts the documents matched by every step of a chain definition file
+    Counts { file: PathBuf },
+}
+
+fn main() {
+    if let Err(err) = run(Cli::parse()) {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+fn run(cli: Cli) -> Result<(), SolrSubqueryError> {
+    match cli.command {
+        Command::Join { first, second, op } => {
+            let operator = op.to_uppercase().parse::<Operator>()?;
+            let merged = match operator {
+                Operator::And => {
+                    SolrQuery::new(first.as_str())?.inner_join(&SolrQuery::new(second.as_str())?)?
+                }
+                operator => SolrQuery::new(first.as_str())?
+                    .merge_queries(&SolrQuery::new(second.as_str())?, operator)?,
+            };
+            println!("{}", merged.url);
+        }
+        Command::Inverse { url } => println!("{}", SolrQuery::new(url.as_str())?.inverse().url),
+        Command::Chain { file, execute } => {
+            let chain = QueryChain::from_file(file)?;
+
+            if execute {
+                let client = SolrClient::new();
+                let query = chain.collapse()?;
+                let key_field = client.schema(&query)?.unique_key;
+                client.harvest(&query, &key_field, |doc| {
+                    println!("{}", doc);
+                    Ok(())
+                })?;
+            } else {
+                for step in chain.labeled() {
+                    let step = step?;
+                    match step.label {
+                        Some(label) => println!("{}\t{}\t{}", step.step, label, step.query.url),
+                        None => println!("{}\t\t{}", step.step, step.query.url),
+                    }
+                }
+            }
+        }
+        Command::Counts { file } => {
+            let mut chain = QueryChain::from_file(file)?;
+            let counts = chain.count_steps(&SolrClient::new(), &ExecutionBudget::default())?;
+
+            println!(
+                "{:<6}{:<24}{:>12}{:>12}",
+                "STEP", "LABEL", "NUM FOUND", "DELTA"
+            );
+            let mut previous = None;
+            for (step, num_found) in counts.into_iter().enumerate() {
+                let delta = match previous {
+                    Some(previous) => format!("{:+}", num_found as i64 - previous as i64),
+                    None => "-".to_string(),
+                };
+                println!(
+                    "{:<6}{:<24}{:>12}{:>12}",
+                    step,
+                    chain.label(step).unwrap_or("-"),
+                    num_found,
+                    delta
+                );
+                previous = Some(num_found);
+            }
+        }
+    }
+
+    Ok(())
+}
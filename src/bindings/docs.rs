@@ -0,0 +1,120 @@
+use crate::bindings::to_py;
+use crate::client::{Harvest, SolrClient};
+use crate::SolrQuery as Query;
+use pyo3::prelude::*;
+#[cfg(feature = "dataframe")]
+use pyo3::types::PyDict;
+#[cfg(feature = "dataframe")]
+use serde_json::Value;
+
+/// Lazily harvests the documents matched by a query
+///
+/// Pages are fetched with a cursor sorted on the unique key of the collection, read from the
+/// Schema API on the first call to `__next__`.
+#[pyclass]
+pub struct DocIterator {
+    client: SolrClient,
+    query: Query,
+    batch_size: usize,
+    docs: Option<Harvest>,
+}
+
+impl DocIterator {
+    pub(crate) fn new(query: Query, batch_size: usize) -> DocIterator {
+        DocIterator {
+            client: SolrClient::new(),
+            query,
+            batch_size,
+            docs: None,
+        }
+    }
+}
+
+/// Harvests the documents matched by a query on the unique key of its collection
+pub(crate) fn harvest(
+    client: &SolrClient,
+    query: &Query,
+    batch_size: usize,
+) -> Result<Harvest, crate::SolrSubqueryError> {
+    let key_field = client.schema(query)?.unique_key;
+    Ok(client.harvest_iter(query, &key_field, batch_size))
+}
+
+/// Builds a pandas DataFrame with the given columns, or the fields of the documents
+///
+/// The columns go through a pyarrow Table when pyarrow is installed, the documents are
+/// given to pandas as records otherwise.
+#[cfg(feature = "dataframe")]
+pub(crate) fn to_dataframe(
+    py: Python<'_>,
+    docs: &[Value],
+    fields: Option<Vec<String>>,
+) -> PyResult<PyObject> {
+    let columns = fields.unwrap_or_else(|| {
+        let mut columns = Vec::new();
+        for name in docs
+            .iter()
+            .filter_map(Value::as_object)
+            .flat_map(|doc| doc.keys())
+        {
+            if !columns.contains(name) {
+                columns.push(name.clone());
+            }
+        }
+        columns
+    });
+
+    let pyarrow = match py.import("pyarrow") {
+        Ok(pyarrow) => pyarrow,
+        Err(_) => {
+            let kwargs = PyDict::new(py);
+            kwargs.set_item("columns", columns)?;
+            return Ok(py
+                .import("pandas")?
+                .getattr("DataFrame")?
+                .call_method(
+                    "from_records",
+                    (to_py(py, &Value::Array(docs.to_vec()))?,),
+                    Some(kwargs),
+                )?
+                .into_py(py));
+        }
+    };
+
+    let table = PyDict::new(py);
+    for column in &columns {
+        let values = docs
+            .iter()
+            .map(|doc| doc.get(column).cloned().unwrap_or(Value::Null))
+            .collect();
+        table.set_item(column, to_py(py, &Value::Array(values))?)?;
+    }
+
+    Ok(pyarrow
+        .getattr("Table")?
+        .call_method1("from_pydict", (table,))?
+        .call_method0("to_pandas")?
+        .into_py(py))
+}
+
+#[pymethods]
+impl DocIterator {
+    fn __iter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    fn __next__(mut slf: PyRefMut<Self>, py: Python<'_>) -> PyResult<Option<PyObject>> {
+        let mut docs = match slf.docs.take() {
+            Some(docs) => docs,
+            None => {
+                let (client, query) = (slf.client.clone(), slf.query.clone());
+                let batch_size = slf.batch_size;
+                py.allow_threads(|| harvest(&client, &query, batch_size))?
+            }
+        };
+
+        let doc = py.allow_threads(|| docs.next()).transpose()?;
+        slf.docs = Some(docs);
+        doc.map(|doc| to_py(py, &doc)).transpose()
+    }
+}
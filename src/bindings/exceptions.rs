@@ -0,0 +1,57 @@
+use crate::errors::SolrSubqueryError as Error;
+use pyo3::create_exception;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+create_exception!(solr_subquery, SolrSubqueryError, PyValueError);
+create_exception!(solr_subquery, InvalidUrl, SolrSubqueryError);
+create_exception!(solr_subquery, MissingQParameter, SolrSubqueryError);
+create_exception!(solr_subquery, MultipleQParameters, SolrSubqueryError);
+create_exception!(solr_subquery, DifferentHosts, SolrSubqueryError);
+create_exception!(solr_subquery, DifferentPorts, SolrSubqueryError);
+create_exception!(solr_subquery, DifferentPaths, SolrSubqueryError);
+create_exception!(solr_subquery, ConflictingEdismaxParams, SolrSubqueryError);
+create_exception!(solr_subquery, EmptyChain, SolrSubqueryError);
+create_exception!(solr_subquery, InvalidOperator, SolrSubqueryError);
+create_exception!(solr_subquery, HttpError, SolrSubqueryError);
+
+impl From<Error> for PyErr {
+    fn from(err: Error) -> PyErr {
+        let message = err.to_string();
+
+        match err {
+            Error::InvalidUrl { .. } => InvalidUrl::new_err(message),
+            Error::MissingQQueryParameter { .. } => MissingQParameter::new_err(message),
+            Error::MultipleQQueryParameters { .. } => MultipleQParameters::new_err(message),
+            Error::DifferentsHosts { .. } => DifferentHosts::new_err(message),
+            Error::DifferentsPorts { .. } => DifferentPorts::new_err(message),
+            Error::DifferentsPaths { .. } | Error::DifferentHandlers { .. } => {
+                DifferentPaths::new_err(message)
+            }
+            Error::ConflictingEdismaxParams { .. } => ConflictingEdismaxParams::new_err(message),
+            Error::EmptyChain => EmptyChain::new_err(message),
+            Error::InvalidOperator(_) => InvalidOperator::new_err(message),
+            Error::Http(_) => HttpError::new_err(message),
+            _ => SolrSubqueryError::new_err(message),
+        }
+    }
+}
+
+/// Registers the exception classes on the Python module
+pub(crate) fn register(py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add("SolrSubqueryError", py.get_type::<SolrSubqueryError>())?;
+    m.add("InvalidUrl", py.get_type::<InvalidUrl>())?;
+    m.add("MissingQParameter", py.get_type::<MissingQParameter>())?;
+    m.add("MultipleQParameters", py.get_type::<MultipleQParameters>())?;
+    m.add("DifferentHosts", py.get_type::<DifferentHosts>())?;
+    m.add("DifferentPorts", py.get_type::<DifferentPorts>())?;
+    m.add("DifferentPaths", py.get_type::<DifferentPaths>())?;
+    m.add(
+        "ConflictingEdismaxParams",
+        py.get_type::<ConflictingEdismaxParams>(),
+    )?;
+    m.add("EmptyChain", py.get_type::<EmptyChain>())?;
+    m.add("InvalidOperator", py.get_type::<InvalidOperator>())?;
+    m.add("HttpError", py.get_type::<HttpError>())?;
+    Ok(())
+}
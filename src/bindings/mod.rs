@@ -1,14 +1,32 @@
+#[cfg(feature = "client")]
+mod docs;
+mod exceptions;
+
+#[cfg(feature = "client")]
+use crate::client::SolrClient;
 use crate::errors::SolrSubqueryError;
 use crate::query_chain::QueryChain as Chain;
-use crate::solr_query::SubQuery;
+use crate::query_tree::QueryTree;
+use crate::replicas::Replicas;
+use crate::solr_query::{Operator as Op, QueryParam, SubQuery};
 use crate::SolrQuery as Query;
-use pyo3::exceptions::PyValueError;
+use pyo3::basic::CompareOp;
+use pyo3::exceptions::{PyIndexError, PyValueError};
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
+#[cfg(feature = "client")]
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use url::Url;
 
-impl From<SolrSubqueryError> for PyErr {
-    fn from(err: SolrSubqueryError) -> PyErr {
-        PyValueError::new_err(err.to_string())
-    }
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[pyclass]
+pub enum Operator {
+    And,
+    Or,
+    Not,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -32,6 +50,48 @@ impl SolrQuery {
         })
     }
 
+    /// Merges the queries with the given operator, see the `SubQuery` trait
+    fn merge(&self, query: SolrQuery, operator: Operator) -> Result<SolrQuery, SolrSubqueryError> {
+        Ok(SolrQuery {
+            query: self.query.merge_queries(&query.into(), operator.into())?,
+        })
+    }
+
+    /// Fetches the documents matched by the query as a list of dicts
+    ///
+    /// The GIL is released during the request.
+    #[cfg(feature = "client")]
+    fn execute(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let docs = py.allow_threads(|| SolrClient::new().documents(&self.query))?;
+        to_py(py, &Value::Array(docs))
+    }
+
+    /// Iterates over the documents matched by the query, fetching `batch_size` documents per request
+    #[cfg(feature = "client")]
+    #[args(batch_size = "500")]
+    fn iter_docs(&self, batch_size: usize) -> docs::DocIterator {
+        docs::DocIterator::new(self.query.clone(), batch_size)
+    }
+
+    /// Fetches every document matched by the query into a pandas DataFrame
+    ///
+    /// `fields` selects the columns (and the `fl` of the query), all the returned
+    /// fields are kept otherwise. Documents are harvested like `iter_docs`, then
+    /// converted through pyarrow if installed. pandas must be installed.
+    #[cfg(feature = "dataframe")]
+    #[args(fields = "None")]
+    fn to_dataframe(&self, py: Python<'_>, fields: Option<Vec<String>>) -> PyResult<PyObject> {
+        let query = match &fields {
+            Some(fields) => self.query.clone().with_param("fl", &fields.join(",")),
+            None => self.query.clone(),
+        };
+        let docs = py.allow_threads(|| {
+            docs::harvest(&SolrClient::new(), &query, 500)?.collect::<Result<Vec<_>, _>>()
+        })?;
+
+        docs::to_dataframe(py, &docs, fields)
+    }
+
     fn inverse(&self) -> SolrQuery {
         SolrQuery {
             query: self.query.inverse(),
@@ -41,27 +101,237 @@ impl SolrQuery {
     fn url(&self) -> String {
         self.query.url.to_string()
     }
+
+    fn inverse_url(&self) -> String {
+        self.query.negation_url().to_string()
+    }
+
+    /// Gets the first value of a parameter
+    fn get_param(&self, name: &str) -> Option<String> {
+        self.query.url.params(name).into_iter().next()
+    }
+
+    /// Returns a copy of the query with a parameter set on it and on its inverse, replacing its values
+    ///
+    /// The query itself is left untouched, as it is hashable.
+    fn with_param(&self, name: &str, value: &str) -> PyResult<SolrQuery> {
+        if name == "q" {
+            return Err(PyValueError::new_err(
+                "`q` can't be set, create a new SolrQuery instead",
+            ));
+        }
+
+        Ok(self.query.clone().with_param(name, value).into())
+    }
+
+    /// Gets the parameters as a list of `(name, value)` pairs
+    fn params(&self) -> Vec<(String, String)> {
+        self.query
+            .url
+            .query_pairs()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    /// Converts the query to `{"base_url", "params", "q", "negation_q"}`, params mapping names to lists of values
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<&'py PyDict> {
+        let mut base_url = self.query.url.clone();
+        base_url.set_query(None);
+
+        let mut params: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (name, value) in self.query.url.query_pairs().filter(|(k, _)| k != "q") {
+            params
+                .entry(name.to_string())
+                .or_default()
+                .push(value.to_string());
+        }
+
+        let dict = PyDict::new(py);
+        dict.set_item("base_url", base_url.as_str())?;
+        dict.set_item("params", params)?;
+        dict.set_item("q", self.query.url.params("q").pop())?;
+        dict.set_item("negation_q", self.query.negation_url().params("q").pop())?;
+        Ok(dict)
+    }
+
+    /// Builds a query from a dict returned by `to_dict`, `params` and `negation_q` being optional
+    #[staticmethod]
+    fn from_dict(dict: &PyDict) -> PyResult<SolrQuery> {
+        let item = |key: &str| {
+            dict.get_item(key)
+                .ok_or_else(|| PyValueError::new_err(format!("Missing `{}` key", key)))
+        };
+
+        let base_url: &str = item("base_url")?.extract()?;
+        let q: &str = item("q")?.extract()?;
+        let params: BTreeMap<String, Vec<String>> = match dict.get_item("params") {
+            Some(params) => params.extract()?,
+            None => BTreeMap::new(),
+        };
+        let negation_q: Option<&str> = match dict.get_item("negation_q") {
+            Some(negation_q) => negation_q.extract()?,
+            None => None,
+        };
+
+        let with_q = |q: &str| -> Result<Url, SolrSubqueryError> {
+            let mut url = Url::parse(base_url)?;
+            url.query_pairs_mut().append_pair("q", q).extend_pairs(
+                params
+                    .iter()
+                    .flat_map(|(name, values)| values.iter().map(move |v| (name, v))),
+            );
+            Ok(url)
+        };
+
+        let query = match negation_q {
+            Some(negation_q) => Query::with_negation(with_q(q)?, with_q(negation_q)?)?,
+            None => Query::new(with_q(q)?)?,
+        };
+        Ok(query.into())
+    }
+
+    fn __str__(&self) -> String {
+        decoded(&self.query.url)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("SolrQuery({:?})", decoded(&self.query.url))
+    }
+
+    fn __richcmp__(&self, other: PyRef<SolrQuery>, op: CompareOp, py: Python<'_>) -> PyObject {
+        match op {
+            CompareOp::Eq => (self == &*other).into_py(py),
+            CompareOp::Ne => (self != &*other).into_py(py),
+            _ => py.NotImplemented(),
+        }
+    }
+
+    fn __hash__(&self) -> isize {
+        hash(self.query.url.as_str())
+    }
+
+    fn __getnewargs__(&self) -> (String,) {
+        (self.query.url.to_string(),)
+    }
+
+    fn __getstate__(&self) -> String {
+        self.query.negation_url().to_string()
+    }
+
+    fn __setstate__(&mut self, negation: &str) -> Result<(), SolrSubqueryError> {
+        let negation = Url::parse(negation)?;
+        self.query = Query::with_negation(self.query.url.clone(), negation)?;
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 #[pyclass]
 pub struct SolrQueryChain {
     chain: Chain,
+    /// Every subquery added to the chain, unaffected by the iteration
+    subqueries: Vec<Query>,
 }
 
 #[pymethods]
 impl SolrQueryChain {
     #[new]
     fn new(queries: Vec<SolrQuery>) -> SolrQueryChain {
-        let queries = queries.into_iter().map(|q| q.query).collect();
+        let queries: Vec<Query> = queries.into_iter().map(|q| q.query).collect();
 
         SolrQueryChain {
-            chain: Chain::new(queries),
+            chain: Chain::new(queries.clone()),
+            subqueries: queries,
         }
     }
 
+    /// Builds a chain from a nested structure such as `{"and": [q1, {"or": [q2, q3]}]}`
+    ///
+    /// Leaves are `SolrQuery` objects or URLs, nodes are dicts with a single
+    /// `and`, `or` (list of children) or `not` (single child) key.
+    #[staticmethod]
+    fn from_tree(tree: &PyAny) -> PyResult<SolrQueryChain> {
+        let query = to_tree(tree)?.to_query()?;
+        Ok(Chain::new(vec![query]).into())
+    }
+
+    /// Fetches the documents matched by every step of the chain, as a list of lists of dicts
+    ///
+    /// The GIL is released during the requests.
+    #[cfg(feature = "client")]
+    fn execute_all(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let steps = py.allow_threads(|| {
+            let client = SolrClient::new();
+            self.chain
+                .iter()
+                .map(|step| Ok(Value::Array(client.documents(&step?)?)))
+                .collect::<Result<Vec<_>, SolrSubqueryError>>()
+        })?;
+        to_py(py, &Value::Array(steps))
+    }
+
+    fn append(&mut self, query: SolrQuery) {
+        self.chain.extend(std::iter::once(query.clone().into()));
+        self.subqueries.push(query.into());
+    }
+
+    fn __len__(&self) -> usize {
+        self.subqueries.len()
+    }
+
+    /// Gets a subquery of the chain, negative indexes counting from the end
+    ///
+    /// Indexes refer to the subqueries as added, iterating over the chain doesn't shift them.
+    fn __getitem__(&self, index: isize) -> PyResult<SolrQuery> {
+        let len = self.__len__() as isize;
+        let position = if index < 0 { index + len } else { index };
+
+        usize::try_from(position)
+            .ok()
+            .and_then(|position| self.subqueries.get(position))
+            .map(|query| query.clone().into())
+            .ok_or_else(|| PyIndexError::new_err("SolrQueryChain index out of range"))
+    }
+
     fn add_subquery(&mut self, url: &str) -> Result<(), SolrSubqueryError> {
         self.chain.add_subquery(url)?;
+        self.subqueries.push(Query::new(url)?);
+        Ok(())
+    }
+
+    fn __str__(&self) -> String {
+        self.__repr__()
+    }
+
+    fn __repr__(&self) -> String {
+        let queries = self
+            .chain
+            .pending()
+            .map(|q| format!("{:?}", decoded(&q.url)))
+            .collect::<Vec<_>>();
+        format!("SolrQueryChain([{}])", queries.join(", "))
+    }
+
+    fn __richcmp__(&self, other: PyRef<SolrQueryChain>, op: CompareOp, py: Python<'_>) -> PyObject {
+        match op {
+            CompareOp::Eq => (self == &*other).into_py(py),
+            CompareOp::Ne => (self != &*other).into_py(py),
+            _ => py.NotImplemented(),
+        }
+    }
+
+    fn __getnewargs__(&self) -> (Vec<SolrQuery>,) {
+        (Vec::new(),)
+    }
+
+    fn __getstate__(&self) -> (String, String) {
+        let subqueries = Chain::new(self.subqueries.clone());
+        (self.chain.to_json(), subqueries.to_json())
+    }
+
+    fn __setstate__(&mut self, state: (&str, &str)) -> Result<(), SolrSubqueryError> {
+        self.chain = Chain::from_json(state.0)?;
+        self.subqueries = Chain::from_json(state.1)?.pending().cloned().collect();
         Ok(())
     }
 
@@ -69,8 +339,131 @@ impl SolrQueryChain {
         slf
     }
 
-    fn __next__(mut slf: PyRefMut<Self>) -> Option<SolrQuery> {
-        slf.chain.next().map(|q| q.into())
+    fn __next__(mut slf: PyRefMut<Self>) -> Result<Option<SolrQuery>, SolrSubqueryError> {
+        match slf.chain.next() {
+            Some(query) => Ok(Some(query.into())),
+            None => slf.chain.take_error().map_or(Ok(None), Err),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[pyclass]
+pub struct SolrReplicas {
+    replicas: Replicas,
+}
+
+#[pymethods]
+impl SolrReplicas {
+    #[new]
+    fn new(replicas: Vec<(String, u32)>) -> Result<SolrReplicas, SolrSubqueryError> {
+        let replicas = replicas
+            .iter()
+            .map(|(base, weight)| (base.as_str(), *weight))
+            .collect();
+
+        Ok(SolrReplicas {
+            replicas: Replicas::new(replicas)?,
+        })
+    }
+
+    fn next_base(&mut self) -> String {
+        self.replicas.next_base().to_string()
+    }
+
+    fn route(&mut self, query: SolrQuery) -> Result<SolrQuery, SolrSubqueryError> {
+        Ok(self.replicas.route(&query.into())?.into())
+    }
+}
+
+/// Renders an URL with its parameters percent-decoded
+fn decoded(url: &Url) -> String {
+    let parameters = url
+        .query_pairs()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>();
+
+    let mut base = url.clone();
+    base.set_query(None);
+    format!("{}?{}", base, parameters.join("&"))
+}
+
+/// Converts a JSON value to the equivalent Python object, keeping the order of the keys
+#[cfg(feature = "client")]
+pub(crate) fn to_py(py: Python<'_>, value: &Value) -> PyResult<PyObject> {
+    Ok(match value {
+        Value::Null => py.None(),
+        Value::Bool(b) => b.into_py(py),
+        Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(i), _) => i.into_py(py),
+            (_, Some(u)) => u.into_py(py),
+            _ => n.as_f64().unwrap_or(f64::NAN).into_py(py),
+        },
+        Value::String(s) => s.into_py(py),
+        Value::Array(values) => values
+            .iter()
+            .map(|v| to_py(py, v))
+            .collect::<PyResult<Vec<_>>>()?
+            .into_py(py),
+        Value::Object(map) => {
+            let dict = PyDict::new(py);
+            for (k, v) in map {
+                dict.set_item(k, to_py(py, v)?)?;
+            }
+            dict.into_py(py)
+        }
+    })
+}
+
+fn hash(value: &str) -> isize {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish() as isize
+}
+
+fn to_tree(node: &PyAny) -> PyResult<QueryTree> {
+    if let Ok(query) = node.extract::<SolrQuery>() {
+        return Ok(QueryTree::leaf(query.into()));
+    }
+
+    if let Ok(url) = node.extract::<&str>() {
+        return Ok(QueryTree::leaf(Query::new(url)?));
+    }
+
+    let dict = node
+        .downcast::<PyDict>()
+        .map_err(|_| PyValueError::new_err("Tree node must be a SolrQuery, an URL or a dict"))?;
+
+    match dict.items().extract::<Vec<(String, &PyAny)>>()?.as_slice() {
+        [(operator, child)] if operator.eq_ignore_ascii_case("not") => {
+            Ok(QueryTree::not(to_tree(child)?))
+        }
+        [(operator, children)] => {
+            let children = children
+                .extract::<Vec<&PyAny>>()?
+                .into_iter()
+                .map(to_tree)
+                .collect::<PyResult<Vec<_>>>()?;
+
+            match operator.to_lowercase().as_str() {
+                "and" => Ok(QueryTree::and(children)),
+                "or" => Ok(QueryTree::or(children)),
+                _ => Err(SolrSubqueryError::InvalidOperator(operator.to_string()).into()),
+            }
+        }
+        _ => Err(PyValueError::new_err(
+            "Tree node dict must have a single `and`, `or` or `not` key",
+        )),
+    }
+}
+
+impl From<Operator> for Op {
+    fn from(operator: Operator) -> Op {
+        match operator {
+            Operator::And => Op::And,
+            Operator::Or => Op::Or,
+            Operator::Not => Op::Not,
+        }
     }
 }
 
@@ -88,13 +481,19 @@ impl From<SolrQuery> for Query {
 
 impl From<Chain> for SolrQueryChain {
     fn from(chain: Chain) -> SolrQueryChain {
-        SolrQueryChain { chain }
+        SolrQueryChain {
+            subqueries: chain.pending().cloned().collect(),
+            chain,
+        }
     }
 }
 
 #[pymodule]
-fn solr_subquery(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+fn solr_subquery(py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    exceptions::register(py, m)?;
+    m.add_class::<Operator>()?;
     m.add_class::<SolrQuery>()?;
     m.add_class::<SolrQueryChain>()?;
+    m.add_class::<SolrReplicas>()?;
     Ok(())
 }
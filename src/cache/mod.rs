@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
+#[cfg(feature = "redis-cache")]
+mod redis;
+#[cfg(feature = "redis-cache")]
+pub use self::redis::*;
+
+use crate::errors::SolrSubqueryError;
+use crate::solr_query::{fnv1a, QueryParam, SolrQuery};
+
+use serde_json::Value;
+use url::Url;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// Identifies a page of results of a query
+pub struct CacheKey {
+    /// Hash of the endpoint and of the sorted decoded parameters of the request
+    pub query_hash: u64,
+    /// Cursor mark of the page, `*` for the first one
+    pub cursor: String,
+    /// Endpoint and sorted decoded parameters the hash is computed from, checked on a hit
+    pub request: String,
+    /// Hash of the credentials the request is sent with, `0` for anonymous requests
+    pub identity: u64,
+}
+
+impl CacheKey {
+    pub fn new(query: &SolrQuery, cursor: &str) -> CacheKey {
+        CacheKey {
+            cursor: cursor.to_string(),
+            ..CacheKey::for_url(&query.url)
+        }
+    }
+
+    /// Identifies the response to a request, the `cursorMark` parameter (if any) being the cursor
+    ///
+    /// Parameters may be in any order, but their values must be the same code points: unlike
+    /// `SolrQuery::canonical_hash`, q values aren't normalized as the index may not be.
+    /// Credentials embedded in the URL are left out of the request, see `CacheKey::with_identity`.
+    pub fn for_url(url: &Url) -> CacheKey {
+        let mut endpoint = url.clone();
+        endpoint.set_query(None);
+        endpoint.set_fragment(None);
+        let _ = endpoint.set_username("");
+        let _ = endpoint.set_password(None);
+
+        let mut params = url
+            .query_pairs()
+            .filter(|(k, _)| k != "cursorMark")
+            .collect::<Vec<_>>();
+        params.sort();
+
+        let mut request = endpoint.to_string();
+        for (key, value) in params {
+            request.push_str(&format!("\n{}={}", key, value));
+        }
+
+        CacheKey {
+            query_hash: fnv1a(request.as_bytes()),
+            cursor: url.params("cursorMark").pop().unwrap_or_default(),
+            request,
+            identity: 0,
+        }
+    }
+
+    /// Keeps the responses fetched with different credentials apart
+    pub fn with_identity(mut self, identity: u64) -> CacheKey {
+        self.identity = identity;
+        self
+    }
+
+    /// Gets a name made of safe characters, cursor marks may contain `/` or `+`
+    pub fn file_name(&self) -> String {
+        format!(
+            "{:016x}-{:016x}-{:016x}.json",
+            self.identity,
+            self.query_hash,
+            fnv1a(self.cursor.as_bytes())
+        )
+    }
+
+    /// Wraps a response with the request it answers, for the stores keyed by hash only
+    pub(crate) fn entry(&self, response: &Value) -> Value {
+        serde_json::json!({"request": self.request, "response": response})
+    }
+
+    /// Unwraps the response of an entry, unless it answers another request with the same hash
+    pub(crate) fn response(&self, mut entry: Value) -> Option<Value> {
+        match entry.get("request").and_then(Value::as_str) == Some(self.request.as_str()) {
+            true => entry.get_mut("response").map(Value::take),
+            false => None,
+        }
+    }
+}
+
+/// Stores Solr responses to skip the requests of unchanged queries
+pub trait CacheStore {
+    fn get(&mut self, key: &CacheKey) -> Result<Option<Value>, SolrSubqueryError>;
+    fn put(&mut self, key: &CacheKey, response: &Value) -> Result<(), SolrSubqueryError>;
+}
+
+#[derive(Debug, Clone, Default)]
+/// Keeps responses in memory for the lifetime of the store
+pub struct MemoryCache {
+    entries: HashMap<CacheKey, (Instant, Value)>,
+    /// Time to live of the entries, `None` keeps them forever
+    ttl: Option<Duration>,
+}
+
+impl MemoryCache {
+    pub fn new() -> MemoryCache {
+        MemoryCache::default()
+    }
+
+    /// Forgets the responses stored for longer than `ttl`
+    pub fn with_ttl(mut self, ttl: Duration) -> MemoryCache {
+        self.ttl = Some(ttl);
+        self
+    }
+}
+
+impl CacheStore for MemoryCache {
+    fn get(&mut self, key: &CacheKey) -> Result<Option<Value>, SolrSubqueryError> {
+        match (self.entries.get(key), self.ttl) {
+            (Some((stored, _)), Some(ttl)) if stored.elapsed() > ttl => {
+                self.entries.remove(key);
+                Ok(None)
+            }
+            (entry, _) => Ok(entry.map(|(_, response)| response.clone())),
+        }
+    }
+
+    fn put(&mut self, key: &CacheKey, response: &Value) -> Result<(), SolrSubqueryError> {
+        self.entries
+            .insert(key.clone(), (Instant::now(), response.clone()));
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Keeps responses as JSON files of a directory, across runs
+pub struct FileCache {
+    directory: PathBuf,
+    /// Time to live of the entries, from the modification time of their file
+    ttl: Option<Duration>,
+}
+
+impl FileCache {
+    /// Uses the given directory, creating it if needed
+    pub fn new<P: Into<PathBuf>>(directory: P) -> Result<FileCache, SolrSubqueryError> {
+        let directory = directory.into();
+        std::fs::create_dir_all(&directory)
+            .map_err(|e| SolrSubqueryError::Cache(format!("{}: {}", directory.display(), e)))?;
+        Ok(FileCache {
+            directory,
+            ttl: None,
+        })
+    }
+
+    /// Ignores the responses stored for longer than `ttl`
+    pub fn with_ttl(mut self, ttl: Duration) -> FileCache {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Checks if the file of an entry is older than the time to live
+    fn is_expired(&self, path: &PathBuf) -> bool {
+        let age = std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+
+        matches!((self.ttl, age), (Some(ttl), Some(age)) if age > ttl)
+    }
+}
+
+impl CacheStore for FileCache {
+    fn get(&mut self, key: &CacheKey) -> Result<Option<Value>, SolrSubqueryError> {
+        let path = self.directory.join(key.file_name());
+        if self.is_expired(&path) {
+            return Ok(None);
+        }
+
+        match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map(|entry| key.response(entry))
+                .map_err(|e| SolrSubqueryError::Cache(format!("{}: {}", path.display(), e))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(SolrSubqueryError::Cache(format!(
+                "{}: {}",
+                path.display(),
+                e
+            ))),
+        }
+    }
+
+    fn put(&mut self, key: &CacheKey, response: &Value) -> Result<(), SolrSubqueryError> {
+        let path = self.directory.join(key.file_name());
+        std::fs::write(&path, key.entry(response).to_string())
+            .map_err(|e| SolrSubqueryError::Cache(format!("{}: {}", path.display(), e)))
+    }
+}
+
+#[cfg(test)]
+mod cache_tests {
+    use super::*;
+    use serde_json::json;
+    use std::error::Error;
+
+    #[test]
+    fn should_store_responses_by_request() -> Result<(), Box<dyn Error>> {
+        let query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=a:1&rows=10")?;
+        let reordered =
+            SolrQuery::new("http://localhost:8983/solr/collection/select?rows=10&q=a:1")?;
+        let response = json!({"response": {"numFound": 1}});
+
+        let directory = std::env::temp_dir().join(format!("solr-subquery-{}", std::process::id()));
+        let mut stores: Vec<Box<dyn CacheStore>> = vec![
+            Box::new(MemoryCache::new()),
+            Box::new(FileCache::new(&directory)?),
+        ];
+
+        for store in stores.iter_mut() {
+            store.put(&CacheKey::new(&query, "*"), &response)?;
+
+            assert_eq!(
+                store.get(&CacheKey::new(&reordered, "*"))?,
+                Some(response.clone())
+            );
+            assert_eq!(store.get(&CacheKey::new(&query, "AoE/+="))?, None);
+        }
+
+        std::fs::remove_dir_all(directory)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_not_normalize_q_values() -> Result<(), Box<dyn Error>> {
+        let key = |q: &str| -> Result<CacheKey, Box<dyn Error>> {
+            let mut url = Url::parse("http://localhost:8983/solr/collection/select")?;
+            url.query_pairs_mut().append_pair("q", q);
+            Ok(CacheKey::for_url(&url))
+        };
+
+        assert_ne!(key("title:caf\u{e9}")?, key("title:cafe\u{301}")?);
+        assert_ne!(
+            key("title:caf\u{e9}")?.query_hash,
+            key("title:cafe\u{301}")?.query_hash
+        );
+        assert_ne!(key("a:1")?, key("(a:1)")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_check_the_request_on_a_hit() -> Result<(), Box<dyn Error>> {
+        let key = CacheKey::for_url(&Url::parse(
+            "http://localhost:8983/solr/collection/select?q=a:1",
+        )?);
+        let colliding = CacheKey {
+            request: "http://localhost:8983/solr/collection/select\nq=b:1".to_string(),
+            ..key.clone()
+        };
+        let response = json!({"response": {"numFound": 1}});
+
+        let directory =
+            std::env::temp_dir().join(format!("solr-subquery-collision-{}", std::process::id()));
+        let mut store = FileCache::new(&directory)?;
+        store.put(&key, &response)?;
+
+        assert_eq!(store.get(&key)?, Some(response));
+        assert_eq!(store.get(&colliding)?, None);
+        assert_eq!(store.get(&key.clone().with_identity(1))?, None);
+
+        std::fs::remove_dir_all(directory)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_expire_entries() -> Result<(), Box<dyn Error>> {
+        let key = CacheKey::for_url(&Url::parse(
+            "http://localhost:8983/solr/collection/select?q=a:1&cursorMark=AoE",
+        )?);
+        let response = json!({"response": {"numFound": 1}});
+
+        let mut store = MemoryCache::new().with_ttl(Duration::from_millis(20));
+        store.put(&key, &response)?;
+        assert_eq!(store.get(&key)?, Some(response));
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(store.get(&key)?, None);
+
+        assert_eq!(key.cursor, "AoE");
+        assert_eq!(
+            key,
+            CacheKey::new(
+                &SolrQuery::new("http://localhost:8983/solr/collection/select?q=a:1")?,
+                "AoE"
+            )
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,58 @@
+use crate::cache::{CacheKey, CacheStore};
+use crate::errors::SolrSubqueryError;
+
+use redis::Commands;
+use serde_json::Value;
+
+/// Keeps responses in Redis, shared between processes
+pub struct RedisCache {
+    connection: redis::Connection,
+    /// Time to live of the entries in seconds, `None` keeps them forever
+    ttl: Option<usize>,
+}
+
+impl RedisCache {
+    pub fn new(url: &str, ttl: Option<usize>) -> Result<RedisCache, SolrSubqueryError> {
+        let connection = redis::Client::open(url)
+            .and_then(|client| client.get_connection())
+            .map_err(|e| SolrSubqueryError::Cache(e.to_string()))?;
+
+        Ok(RedisCache { connection, ttl })
+    }
+
+    fn redis_key(key: &CacheKey) -> String {
+        format!(
+            "solr-subquery:{:016x}:{:016x}:{}",
+            key.identity, key.query_hash, key.cursor
+        )
+    }
+}
+
+impl CacheStore for RedisCache {
+    fn get(&mut self, key: &CacheKey) -> Result<Option<Value>, SolrSubqueryError> {
+        let response: Option<String> = self
+            .connection
+            .get(RedisCache::redis_key(key))
+            .map_err(|e| SolrSubqueryError::Cache(e.to_string()))?;
+
+        let entry = response
+            .map(|r| serde_json::from_str(&r).map_err(|e| SolrSubqueryError::Cache(e.to_string())))
+            .transpose()?;
+
+        Ok(entry.and_then(|entry| key.response(entry)))
+    }
+
+    fn put(&mut self, key: &CacheKey, response: &Value) -> Result<(), SolrSubqueryError> {
+        let redis_key = RedisCache::redis_key(key);
+        let result = match self.ttl {
+            Some(ttl) => self
+                .connection
+                .set_ex(redis_key, key.entry(response).to_string(), ttl),
+            None => self
+                .connection
+                .set(redis_key, key.entry(response).to_string()),
+        };
+
+        result.map_err(|e: redis::RedisError| SolrSubqueryError::Cache(e.to_string()))
+    }
+}
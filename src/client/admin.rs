@@ -0,0 +1,208 @@
+use crate::client::{admin_url, SolrClient};
+use crate::errors::SolrSubqueryError;
+use crate::query_chain::QueryChain;
+use crate::solr_query::SolrQuery;
+
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use url::Url;
+
+#[derive(Debug, Clone, PartialEq)]
+/// Status of a core of a Solr instance
+pub struct CoreStatus {
+    pub name: String,
+    pub num_docs: Option<u64>,
+    pub size_in_bytes: Option<u64>,
+    /// Why the core failed to load, if it did
+    pub init_failure: Option<String>,
+}
+
+impl CoreStatus {
+    /// Reads the cores from a CoreAdmin API `STATUS` response
+    pub fn from_status(response: &Value) -> Result<Vec<CoreStatus>, SolrSubqueryError> {
+        let cores = response["status"]
+            .as_object()
+            .ok_or_else(|| SolrSubqueryError::InvalidResponse("missing status".to_string()))?;
+
+        let loaded = cores.iter().map(|(name, core)| CoreStatus {
+            name: name.to_string(),
+            num_docs: core["index"]["numDocs"].as_u64(),
+            size_in_bytes: core["index"]["sizeInBytes"].as_u64(),
+            init_failure: None,
+        });
+
+        let failed = response["initFailures"]
+            .as_object()
+            .into_iter()
+            .flatten()
+            .map(|(name, failure)| CoreStatus {
+                name: name.to_string(),
+                num_docs: None,
+                size_in_bytes: None,
+                init_failure: Some(
+                    failure
+                        .as_str()
+                        .map_or(failure.to_string(), |f| f.to_string()),
+                ),
+            });
+
+        Ok(loaded.chain(failed).collect())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Status of a collection of a SolrCloud cluster
+pub struct CollectionStatus {
+    pub name: String,
+    /// `GREEN`, `YELLOW`, `ORANGE` or `RED`, reported by Solr 8.1+
+    pub health: Option<String>,
+    pub shards: usize,
+    /// Aliases pointing to the collection
+    pub aliases: Vec<String>,
+}
+
+impl CollectionStatus {
+    /// Reads the collections from a Collections API `CLUSTERSTATUS` response
+    pub fn from_cluster_status(
+        response: &Value,
+    ) -> Result<Vec<CollectionStatus>, SolrSubqueryError> {
+        let cluster = &response["cluster"];
+        let collections = cluster["collections"]
+            .as_object()
+            .ok_or_else(|| SolrSubqueryError::InvalidResponse("missing collections".to_string()))?;
+
+        let mut aliases: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+        for (alias, targets) in cluster["aliases"].as_object().into_iter().flatten() {
+            for target in targets.as_str().unwrap_or_default().split(',') {
+                aliases
+                    .entry(target.trim())
+                    .or_default()
+                    .push(alias.to_string());
+            }
+        }
+
+        Ok(collections
+            .iter()
+            .map(|(name, collection)| CollectionStatus {
+                name: name.to_string(),
+                health: collection["health"].as_str().map(|h| h.to_string()),
+                shards: collection["shards"].as_object().map_or(0, |s| s.len()),
+                aliases: aliases.remove(name.as_str()).unwrap_or_default(),
+            })
+            .collect())
+    }
+}
+
+impl SolrClient {
+    /// Lists the cores of the Solr instance serving a query with the CoreAdmin API
+    pub fn cores(&self, frame: &SolrQuery) -> Result<Vec<CoreStatus>, SolrSubqueryError> {
+        let mut url = admin_url(frame, "admin/cores");
+        url.query_pairs_mut()
+            .append_pair("action", "STATUS")
+            .append_pair("wt", "json");
+
+        CoreStatus::from_status(&self.get_json_uncached(&url)?)
+    }
+
+    /// Lists the collections of the cluster serving a query with the Collections API
+    pub fn collections(
+        &self,
+        frame: &SolrQuery,
+    ) -> Result<Vec<CollectionStatus>, SolrSubqueryError> {
+        let mut url = admin_url(frame, "admin/collections");
+        url.query_pairs_mut()
+            .append_pair("action", "CLUSTERSTATUS")
+            .append_pair("wt", "json");
+
+        CollectionStatus::from_cluster_status(&self.get_json_uncached(&url)?)
+    }
+
+    /// Checks that every remaining step of a chain targets an existing collection
+    ///
+    /// Collections and their aliases are listed with the Collections API, or the
+    /// cores are listed if Solr doesn't run in SolrCloud mode.
+    pub fn check_collections(&self, chain: &QueryChain) -> Result<(), SolrSubqueryError> {
+        let mut known: BTreeMap<Url, BTreeSet<String>> = BTreeMap::new();
+
+        for query in chain.pending() {
+            let instance = admin_url(query, "");
+            let name = query.collection()?.to_string();
+
+            if !known.contains_key(&instance) {
+                known.insert(instance.clone(), self.collection_names(query)?);
+            }
+            if !known[&instance].contains(&name) {
+                return Err(SolrSubqueryError::UnknownCollection(name));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Gets the names of the collections and aliases, or of the cores, of the instance serving a query
+    fn collection_names(&self, frame: &SolrQuery) -> Result<BTreeSet<String>, SolrSubqueryError> {
+        match self.collections(frame) {
+            Ok(collections) => Ok(collections
+                .into_iter()
+                .flat_map(|c| std::iter::once(c.name).chain(c.aliases))
+                .collect()),
+            Err(SolrSubqueryError::Http(_)) => {
+                Ok(self.cores(frame)?.into_iter().map(|c| c.name).collect())
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod admin_tests {
+    use super::*;
+    use serde_json::json;
+    use std::error::Error;
+
+    #[test]
+    fn should_read_cores_and_collections() -> Result<(), Box<dyn Error>> {
+        let cores = CoreStatus::from_status(&json!({
+            "status": {
+                "books": {"name": "books", "index": {"numDocs": 12, "sizeInBytes": 4096}},
+            },
+            "initFailures": {"broken": "org.apache.solr.common.SolrException: no schema"},
+        }))?;
+
+        assert_eq!(cores[0].num_docs, Some(12));
+        assert_eq!(cores[1].name, "broken");
+        assert_eq!(
+            cores[1].init_failure.as_deref(),
+            Some("org.apache.solr.common.SolrException: no schema")
+        );
+
+        let collections = CollectionStatus::from_cluster_status(&json!({
+            "cluster": {
+                "collections": {
+                    "books_2020": {"health": "GREEN", "shards": {"shard1": {}, "shard2": {}}},
+                    "books_2021": {"health": "YELLOW", "shards": {"shard1": {}}},
+                },
+                "aliases": {"books": "books_2020,books_2021", "recent": "books_2021"},
+                "live_nodes": [],
+            },
+        }))?;
+
+        assert_eq!(
+            collections[0],
+            CollectionStatus {
+                name: "books_2020".to_string(),
+                health: Some("GREEN".to_string()),
+                shards: 2,
+                aliases: vec!["books".to_string()],
+            }
+        );
+        assert_eq!(collections[1].aliases, vec!["books", "recent"]);
+
+        assert_eq!(
+            SolrQuery::new("http://localhost:8983/solr/books/select?q=*:*")?.collection(),
+            Ok("books")
+        );
+
+        Ok(())
+    }
+}
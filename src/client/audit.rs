@@ -0,0 +1,137 @@
+use crate::client::SolrClient;
+use crate::errors::SolrSubqueryError;
+use crate::query_chain::QueryChain;
+use crate::solr_query::Operator;
+
+use serde_json::{json, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+/// The number of documents matched by a step of a chain
+pub struct StepAudit {
+    pub step: usize,
+    pub label: Option<String>,
+    /// Operator the subquery of the step was merged with
+    pub operator: Operator,
+    /// Decoded q of the merged query
+    pub q: String,
+    pub num_found: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// The number of documents matched by every step of a chain
+pub struct ChainAudit {
+    pub steps: Vec<StepAudit>,
+}
+
+impl ChainAudit {
+    /// Gets the steps merged with `AND` or `NOT` that match more documents than the previous one
+    ///
+    /// Such steps are expected to narrow the results, so they usually point to a
+    /// subquery Solr doesn't interpret as intended.
+    pub fn widening_steps(&self) -> Vec<&StepAudit> {
+        self.steps
+            .windows(2)
+            .filter(|w| w[1].operator != Operator::Or && w[1].num_found > w[0].num_found)
+            .map(|w| &w[1])
+            .collect()
+    }
+
+    /// Renders the audit as JSON
+    pub fn to_json(&self) -> Value {
+        Value::Array(
+            self.steps
+                .iter()
+                .map(|s| {
+                    json!({
+                        "step": s.step,
+                        "label": s.label,
+                        "operator": s.operator.to_string(),
+                        "q": s.q,
+                        "num_found": s.num_found,
+                    })
+                })
+                .collect(),
+        )
+    }
+}
+
+impl QueryChain {
+    /// Counts the documents matched by every remaining step
+    ///
+    /// Every step is sent with `rows=0`, memoized counts are ignored. The collection
+    /// is pinged first if the client has a pre-flight ping.
+    pub fn audit(&self, client: &SolrClient) -> Result<ChainAudit, SolrSubqueryError> {
+        client.preflight(self)?;
+        let first_step = self.step_offset();
+
+        let steps = self
+            .iter()
+            .enumerate()
+            .map(|(i, query)| {
+                let query = query?;
+                let step = first_step + i;
+
+                Ok(StepAudit {
+                    step,
+                    label: self.label(step).map(|l| l.to_string()),
+                    operator: self.operator(step),
+                    q: query.q_param()?,
+                    num_found: client.count(&query)?,
+                })
+            })
+            .collect::<Result<Vec<_>, SolrSubqueryError>>()?;
+
+        Ok(ChainAudit { steps })
+    }
+}
+
+#[cfg(test)]
+mod audit_tests {
+    use super::*;
+    use crate::solr_query::SolrQuery;
+    use crate::testing::MockSolr;
+    use std::error::Error;
+
+    #[test]
+    fn should_audit_every_step() -> Result<(), Box<dyn Error>> {
+        let solr = MockSolr::start()?;
+        solr.respond(
+            "type:book",
+            json!({"response": {"numFound": 100, "docs": []}}),
+        );
+        solr.respond(
+            "(type:book) AND (lang:en)",
+            json!({"response": {"numFound": 40, "docs": []}}),
+        );
+        solr.respond(
+            "((type:book) AND (lang:en)) AND (-year:2020)",
+            json!({"response": {"numFound": 60, "docs": []}}),
+        );
+
+        let mut query_chain = QueryChain::new(vec![SolrQuery::new(
+            solr.select_url("books", "type:book").as_str(),
+        )?]);
+        query_chain.add_labeled_subquery("english", solr.select_url("books", "lang:en"))?;
+        query_chain.add_subquery(solr.select_url("books", "-year:2020"))?;
+
+        let audit = query_chain.audit(&SolrClient::new())?;
+
+        assert_eq!(
+            audit.steps.iter().map(|s| s.num_found).collect::<Vec<_>>(),
+            vec![100, 40, 60]
+        );
+        assert_eq!(audit.steps[1].label.as_deref(), Some("english"));
+        assert_eq!(
+            audit.steps[2].q,
+            "((type:book) AND (lang:en)) AND (-year:2020)"
+        );
+        assert_eq!(audit.widening_steps(), vec![&audit.steps[2]]);
+        assert_eq!(audit.to_json()[1]["num_found"], json!(40));
+        assert!(solr
+            .requests()
+            .iter()
+            .all(|url| url.query_pairs().any(|(k, v)| k == "rows" && v == "0")));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,164 @@
+use crate::client::SolrClient;
+use crate::errors::SolrSubqueryError;
+use crate::solr_query::{QueryParam, SolrQuery};
+
+use serde_json::{json, Map, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use url::Url;
+
+impl SolrClient {
+    /// Counts the documents matched by several queries in a single request
+    ///
+    /// The queries are sent as query facets of one JSON Request API body, which
+    /// is much cheaper than one round trip per query when filling overlap matrices.
+    /// Only `q` and `fq` are taken into account and all the queries must target
+    /// the same collection.
+    pub fn count_all(&self, queries: &[SolrQuery]) -> Result<Vec<u64>, SolrSubqueryError> {
+        let (url, body) = count_request(queries)?;
+        let response = self.post_json(&url, &body)?;
+
+        (0..queries.len())
+            .map(|i| {
+                response["facets"][format!("q{}", i)]["count"]
+                    .as_u64()
+                    .ok_or_else(|| {
+                        SolrSubqueryError::InvalidResponse(format!("missing count of q{}", i))
+                    })
+            })
+            .collect()
+    }
+
+    /// Fetches the documents of several independent queries in parallel
+    ///
+    /// At most `concurrency` requests are in flight at once. The documents are
+    /// returned in the order of the queries, the first failing query fails the batch.
+    pub fn execute_all(
+        &self,
+        queries: &[SolrQuery],
+        concurrency: usize,
+    ) -> Result<Vec<Vec<Value>>, SolrSubqueryError> {
+        let next = AtomicUsize::new(0);
+        let workers = concurrency.clamp(1, queries.len().max(1));
+
+        let mut results = thread::scope(|scope| {
+            let handles = (0..workers)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut results = Vec::new();
+                        loop {
+                            let i = next.fetch_add(1, Ordering::Relaxed);
+                            match queries.get(i) {
+                                Some(query) => results.push((i, self.documents(query))),
+                                None => return results,
+                            }
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("query worker panicked"))
+                .collect::<Vec<_>>()
+        });
+
+        results.sort_by_key(|(i, _)| *i);
+        results.into_iter().map(|(_, docs)| docs).collect()
+    }
+}
+
+/// Builds the endpoint and the JSON Request API body counting each query as a query facet
+fn count_request(queries: &[SolrQuery]) -> Result<(Url, Value), SolrSubqueryError> {
+    let first = queries.first().ok_or(SolrSubqueryError::EmptyChain)?;
+    let mut facets = Map::new();
+
+    for (i, query) in queries.iter().enumerate() {
+        first.check_can_join(query)?;
+
+        facets.insert(
+            format!("q{}", i),
+            json!({
+                "type": "query",
+                "q": query.q_param()?,
+                "domain": { "filter": query.url.params("fq") },
+            }),
+        );
+    }
+
+    let mut url = first.url.clone();
+    url.set_query(None);
+
+    Ok((
+        url,
+        json!({
+            "query": "*:*",
+            "limit": 0,
+            "facet": facets,
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod batch_tests {
+    use super::*;
+    use crate::testing::MockSolr;
+    use std::error::Error;
+
+    #[test]
+    fn should_execute_queries_in_parallel() -> Result<(), Box<dyn Error>> {
+        let solr = MockSolr::start()?;
+        let queries = (0..5)
+            .map(|i| {
+                let q = format!("id:{}", i);
+                solr.respond_docs(&q, vec![json!({ "id": i })]);
+                SolrQuery::new(solr.select_url("books", &q).as_str())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let docs = SolrClient::new().execute_all(&queries, 3)?;
+        assert_eq!(
+            docs,
+            (0..5).map(|i| vec![json!({ "id": i })]).collect::<Vec<_>>()
+        );
+        assert_eq!(solr.requests().len(), 5);
+
+        let missing = SolrQuery::new(solr.select_url("books", "id:9").as_str())?;
+        assert!(SolrClient::new()
+            .execute_all(&[queries[0].clone(), missing], 2)
+            .is_err());
+        assert!(SolrClient::new().execute_all(&[], 4)?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_build_count_request() -> Result<(), Box<dyn Error>> {
+        let first_query =
+            SolrQuery::new("http://localhost:8983/solr/collection/select?q=1:*&fq=lang:en")?;
+        let second_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=2:*")?;
+
+        let (url, body) = count_request(&[first_query, second_query.clone()])?;
+
+        assert_eq!(url.as_str(), "http://localhost:8983/solr/collection/select");
+        assert_eq!(
+            body,
+            json!({
+                "query": "*:*",
+                "limit": 0,
+                "facet": {
+                    "q0": { "type": "query", "q": "1:*", "domain": { "filter": ["lang:en"] } },
+                    "q1": { "type": "query", "q": "2:*", "domain": { "filter": [] } },
+                },
+            })
+        );
+
+        let other_host = SolrQuery::new("http://other:8983/solr/collection/select?q=3:*")?;
+        assert!(matches!(
+            count_request(&[second_query, other_host]),
+            Err(SolrSubqueryError::DifferentsHosts { .. })
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+use std::time::{Duration, Instant};
+
+use crate::client::SolrClient;
+use crate::errors::SolrSubqueryError;
+use crate::query_chain::QueryChain;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+/// Limits of a whole chain execution, `None` means unlimited
+pub struct ExecutionBudget {
+    pub max_duration: Option<Duration>,
+    pub max_documents: Option<u64>,
+    pub max_requests: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// The limit of an execution budget that was exceeded
+pub enum BudgetExceeded {
+    /// The execution ran for longer than the given duration
+    Duration(Duration),
+    /// More documents than the given number were matched
+    Documents(u64),
+    /// More requests than the given number were sent
+    Requests(usize),
+}
+
+impl std::fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            BudgetExceeded::Duration(duration) => write!(f, "ran for more than {:?}", duration),
+            BudgetExceeded::Documents(documents) => {
+                write!(f, "matched more than {} documents", documents)
+            }
+            BudgetExceeded::Requests(requests) => write!(f, "sent more than {} requests", requests),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Accounts the resources used by an execution against its budget
+pub struct BudgetTracker {
+    budget: ExecutionBudget,
+    started: Instant,
+    documents: u64,
+    requests: usize,
+}
+
+impl ExecutionBudget {
+    /// Starts tracking an execution
+    pub fn start(&self) -> BudgetTracker {
+        BudgetTracker {
+            budget: *self,
+            started: Instant::now(),
+            documents: 0,
+            requests: 0,
+        }
+    }
+}
+
+impl BudgetTracker {
+    /// Accounts a request about to be sent, failing if it would exceed the budget
+    pub fn record_request(&mut self) -> Result<(), SolrSubqueryError> {
+        self.requests += 1;
+        self.check()
+    }
+
+    /// Accounts matched documents
+    pub fn record_documents(&mut self, documents: u64) -> Result<(), SolrSubqueryError> {
+        self.documents += documents;
+        self.check()
+    }
+
+    /// Checks every limit of the budget
+    pub fn check(&self) -> Result<(), SolrSubqueryError> {
+        let exceeded = |limit| Err(SolrSubqueryError::BudgetExceeded(limit));
+
+        match self.budget {
+            ExecutionBudget {
+                max_requests: Some(max),
+                ..
+            } if self.requests > max => exceeded(BudgetExceeded::Requests(max)),
+            ExecutionBudget {
+                max_documents: Some(max),
+                ..
+            } if self.documents > max => exceeded(BudgetExceeded::Documents(max)),
+            ExecutionBudget {
+                max_duration: Some(max),
+                ..
+            } if self.started.elapsed() > max => exceeded(BudgetExceeded::Duration(max)),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl QueryChain {
+    /// Counts the documents matched by every remaining step within an execution budget
+    ///
+    /// Memoized counts don't send requests. The documents matched by each step
+    /// are accounted against `max_documents`. The collection is pinged first if
+    /// the client has a pre-flight ping.
+    pub fn count_steps(
+        &mut self,
+        client: &SolrClient,
+        budget: &ExecutionBudget,
+    ) -> Result<Vec<u64>, SolrSubqueryError> {
+        client.preflight(self)?;
+        let mut tracker = budget.start();
+        let steps = self.iter().collect::<Result<Vec<_>, _>>()?;
+        let first_step = self.step_offset();
+
+        steps
+            .iter()
+            .enumerate()
+            .map(|(i, query)| {
+                #[cfg(feature = "tracing")]
+                let (_span, started) = (
+                    tracing::info_span!(
+                        "count_step",
+                        step = first_step + i,
+                        label = self.label(first_step + i),
+                        q = %query.q_param()?
+                    )
+                    .entered(),
+                    std::time::Instant::now(),
+                );
+
+                let num_found = self.count_or_insert_with(first_step + i, || {
+                    tracker.record_request()?;
+                    client.count(query)
+                })?;
+                tracker.record_documents(num_found)?;
+
+                #[cfg(feature = "tracing")]
+                tracing::info!(
+                    num_found,
+                    elapsed_ms = started.elapsed().as_millis() as u64,
+                    "counted step"
+                );
+
+                Ok(num_found)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod budget_tests {
+    use super::*;
+    use crate::solr_query::SolrQuery;
+    use std::error::Error;
+
+    #[test]
+    fn should_stop_when_budget_is_exceeded() -> Result<(), Box<dyn Error>> {
+        let budget = ExecutionBudget {
+            max_requests: Some(1),
+            max_documents: Some(100),
+            ..ExecutionBudget::default()
+        };
+
+        let mut tracker = budget.start();
+        tracker.record_request()?;
+        tracker.record_documents(60)?;
+        assert_eq!(
+            tracker.record_documents(60),
+            Err(SolrSubqueryError::BudgetExceeded(
+                BudgetExceeded::Documents(100)
+            ))
+        );
+
+        let first_query = SolrQuery::new("http://localhost:1/solr/collection/select?q=1:*")?;
+        let mut query_chain = QueryChain::new(vec![first_query]);
+        query_chain.cache_count(0, 10);
+
+        let budget = ExecutionBudget {
+            max_requests: Some(0),
+            ..ExecutionBudget::default()
+        };
+        assert_eq!(
+            query_chain.count_steps(&SolrClient::new(), &budget)?,
+            vec![10]
+        );
+
+        query_chain.add_subquery("http://localhost:1/solr/collection/select?q=2:*")?;
+        assert_eq!(
+            query_chain.count_steps(&SolrClient::new(), &budget),
+            Err(SolrSubqueryError::BudgetExceeded(BudgetExceeded::Requests(
+                0
+            )))
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+use crate::client::{harvest_keys, SolrClient};
+use crate::errors::SolrSubqueryError;
+use crate::solr_query::SolrQuery;
+
+use std::collections::BTreeSet;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+/// Unique keys of the documents matched by two queries, sorted
+pub struct ResultDiff {
+    pub only_a: Vec<String>,
+    pub only_b: Vec<String>,
+    pub both: Vec<String>,
+}
+
+/// Compares the documents matched by two queries, e.g. a query and its inverse
+///
+/// Keys are streamed with cursor pagination sorted on `key_field`, which must
+/// be the unique key field of the collection.
+pub fn diff(
+    client: &SolrClient,
+    a: &SolrQuery,
+    b: &SolrQuery,
+    key_field: &str,
+) -> Result<ResultDiff, SolrSubqueryError> {
+    let keys = |query| {
+        let mut keys = BTreeSet::new();
+        harvest_keys(client, query, key_field, |key| {
+            keys.insert(key.to_string());
+            Ok(())
+        })?;
+        Ok::<_, SolrSubqueryError>(keys)
+    };
+    let (a_keys, b_keys) = (keys(a)?, keys(b)?);
+
+    Ok(ResultDiff {
+        only_a: a_keys.difference(&b_keys).cloned().collect(),
+        only_b: b_keys.difference(&a_keys).cloned().collect(),
+        both: a_keys.intersection(&b_keys).cloned().collect(),
+    })
+}
+
+#[cfg(test)]
+mod diff_tests {
+    use super::*;
+    use crate::testing::MockSolr;
+    use serde_json::json;
+    use std::error::Error;
+
+    #[test]
+    fn should_diff_matched_keys() -> Result<(), Box<dyn Error>> {
+        let solr = MockSolr::start()?;
+        let respond_ids = |q: &str, ids: &[serde_json::Value]| {
+            let docs = ids.iter().map(|id| json!({ "id": id })).collect::<Vec<_>>();
+            solr.respond(
+                q,
+                json!({
+                    "response": {"numFound": docs.len(), "docs": docs},
+                    "nextCursorMark": "*",
+                }),
+            );
+        };
+        respond_ids("lang:en", &[json!("doc1"), json!("doc2"), json!(3)]);
+        respond_ids("year:2020", &[json!("doc2"), json!("doc4")]);
+
+        let a = SolrQuery::new(solr.select_url("books", "lang:en").as_str())?;
+        let b = SolrQuery::new(solr.select_url("books", "year:2020").as_str())?;
+
+        assert_eq!(
+            diff(&SolrClient::new(), &a, &b, "id")?,
+            ResultDiff {
+                only_a: vec!["3".to_string(), "doc1".to_string()],
+                only_b: vec!["doc4".to_string()],
+                both: vec!["doc2".to_string()],
+            }
+        );
+        assert!(solr
+            .requests()
+            .iter()
+            .all(|url| url.query_pairs().any(|(k, v)| k == "fl" && v == "id")));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+use crate::client::SolrClient;
+use crate::errors::SolrSubqueryError;
+use crate::solr_query::{fnv1a, SolrQuery};
+
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Order insensitive digest of a set of unique keys
+///
+/// Each key is hashed with FNV-1a, which is stable across runs and platforms,
+/// and the hashes are summed so the order the keys are received in doesn't matter.
+pub struct ResultDigest {
+    pub count: u64,
+    pub hash: u64,
+}
+
+impl ResultDigest {
+    pub fn add(&mut self, key: &str) {
+        self.count += 1;
+        self.hash = self.hash.wrapping_add(fnv1a(key.as_bytes()));
+    }
+}
+
+impl std::fmt::Display for ResultDigest {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}:{:016x}", self.count, self.hash)
+    }
+}
+
+impl<'a> FromIterator<&'a str> for ResultDigest {
+    fn from_iter<I: IntoIterator<Item = &'a str>>(keys: I) -> ResultDigest {
+        let mut digest = ResultDigest::default();
+        keys.into_iter().for_each(|key| digest.add(key));
+        digest
+    }
+}
+
+impl SolrQuery {
+    /// Computes a digest of the unique keys matched by the query
+    ///
+    /// Keys are streamed with cursor pagination sorted on `key_field`, which must
+    /// be the unique key field of the collection.
+    pub fn result_digest(
+        &self,
+        client: &SolrClient,
+        key_field: &str,
+    ) -> Result<ResultDigest, SolrSubqueryError> {
+        let mut digest = ResultDigest::default();
+        harvest_keys(client, self, key_field, |key| {
+            digest.add(key);
+            Ok(())
+        })?;
+
+        Ok(digest)
+    }
+}
+
+/// Streams the unique keys of the documents matched by a query, see `SolrClient::harvest`
+pub(crate) fn harvest_keys<F>(
+    client: &SolrClient,
+    query: &SolrQuery,
+    key_field: &str,
+    mut on_key: F,
+) -> Result<(), SolrSubqueryError>
+where
+    F: FnMut(&str) -> Result<(), SolrSubqueryError>,
+{
+    let mut url = query.url.clone();
+    let parameters = query
+        .url
+        .query_pairs()
+        .filter(|(k, _)| k != "fl")
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect::<Vec<_>>();
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(parameters)
+        .append_pair("fl", key_field);
+
+    client.harvest(&SolrQuery::new(url)?, key_field, |doc| {
+        match &doc[key_field] {
+            Value::String(key) => on_key(key),
+            Value::Null => Err(SolrSubqueryError::InvalidResponse(format!(
+                "document without `{}`",
+                key_field
+            ))),
+            key => on_key(&key.to_string()),
+        }
+    })
+}
+
+#[cfg(test)]
+mod digest_tests {
+    use super::*;
+
+    #[test]
+    fn should_digest_keys_in_any_order() {
+        let digest = ["doc1", "doc2", "doc3"]
+            .into_iter()
+            .collect::<ResultDigest>();
+
+        assert_eq!(
+            digest,
+            ["doc3", "doc1", "doc2"]
+                .into_iter()
+                .collect::<ResultDigest>()
+        );
+        assert_ne!(
+            digest,
+            ["doc1", "doc2"].into_iter().collect::<ResultDigest>()
+        );
+        assert_eq!(digest.count, 3);
+    }
+}
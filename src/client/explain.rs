@@ -0,0 +1,256 @@
+use crate::client::{num_found, SolrClient};
+use crate::errors::SolrSubqueryError;
+use crate::solr_query::{closing_paren, QueryParam, SolrQuery};
+
+use url::Url;
+
+#[derive(Debug, Clone, PartialEq)]
+/// How Solr interprets the q value of a query
+pub struct QueryExplanation {
+    /// The `parsedquery` debug output, the query as built by the query parser
+    pub parsed_query: String,
+    /// The `parsedquery_toString` debug output, the query as rewritten for Lucene
+    pub rewritten_query: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Whether a document matches one of the subqueries a query was merged from
+pub struct ClauseMatch {
+    pub q: String,
+    pub matched: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Why a document is or isn't matched by a composed query
+pub struct DocExplanation {
+    pub doc_id: String,
+    pub matched: bool,
+    /// Scoring explanation returned by Solr's `explainOther`
+    pub explanation: Option<String>,
+    pub clauses: Vec<ClauseMatch>,
+}
+
+impl SolrQuery {
+    /// Asks Solr how it parses the query, without fetching any document
+    ///
+    /// Useful to check that merged or negated queries are interpreted as intended.
+    pub fn explain(&self, client: &SolrClient) -> Result<QueryExplanation, SolrSubqueryError> {
+        let mut url = without_params(&self.url, &["rows", "wt", "debug", "debugQuery"]);
+        url.query_pairs_mut()
+            .append_pair("rows", "0")
+            .append_pair("debug", "query")
+            .append_pair("wt", "json");
+        let response = client.get_json(&url)?;
+
+        let debug_string = |key: &str| {
+            response["debug"][key]
+                .as_str()
+                .map(|s| s.to_string())
+                .ok_or_else(|| SolrSubqueryError::InvalidResponse(format!("missing {}", key)))
+        };
+
+        Ok(QueryExplanation {
+            parsed_query: debug_string("parsedquery")?,
+            rewritten_query: debug_string("parsedquery_toString")?,
+        })
+    }
+
+    /// Explains why a document is or isn't matched by the query
+    ///
+    /// The document is looked up by the unique key of the collection. Besides the
+    /// `explainOther` debug output, every subquery the query was merged from is
+    /// run against the document alone to report which ones it matches.
+    pub fn explain_doc(
+        &self,
+        client: &SolrClient,
+        doc_id: &str,
+    ) -> Result<DocExplanation, SolrSubqueryError> {
+        let unique_key = client.schema(self)?.unique_key;
+        let doc_filter = format!("{{!term f={}}}{}", unique_key, doc_id);
+
+        let mut url = without_params(
+            &self.url,
+            &["rows", "wt", "debug", "debugQuery", "explainOther"],
+        );
+        url.query_pairs_mut()
+            .append_pair("fq", &doc_filter)
+            .append_pair("rows", "0")
+            .append_pair("debugQuery", "true")
+            .append_pair("explainOther", &doc_filter)
+            .append_pair("wt", "json");
+        let response = client.get_json(&url)?;
+
+        let clauses = subquery_clauses(&self.q_param()?)
+            .into_iter()
+            .map(|q| {
+                let mut url = without_params(&self.url, &["rows", "wt"]);
+                url.set_param(("q", &q));
+                url.query_pairs_mut()
+                    .append_pair("fq", &doc_filter)
+                    .append_pair("rows", "0")
+                    .append_pair("wt", "json");
+
+                Ok(ClauseMatch {
+                    matched: num_found(&client.get_json(&url)?)? > 0,
+                    q,
+                })
+            })
+            .collect::<Result<Vec<_>, SolrSubqueryError>>()?;
+
+        Ok(DocExplanation {
+            doc_id: doc_id.to_string(),
+            matched: num_found(&response)? > 0,
+            explanation: response["debug"]["explainOther"][doc_id]
+                .as_str()
+                .map(|e| e.to_string()),
+            clauses,
+        })
+    }
+}
+
+/// Copies an URL without the given parameters, which Solr would read before the appended ones
+fn without_params(url: &Url, names: &[&str]) -> Url {
+    let mut stripped = url.clone();
+    let parameters = url
+        .query_pairs()
+        .filter(|(k, _)| !names.contains(&k.as_ref()))
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect::<Vec<_>>();
+
+    stripped.query_pairs_mut().clear().extend_pairs(parameters);
+    stripped
+}
+
+/// Splits a q value merged as `(A) OP (B)` back into the subqueries it was built from
+fn subquery_clauses(q: &str) -> Vec<String> {
+    match split_merge(q) {
+        Some((left, right)) => {
+            let mut clauses = subquery_clauses(left);
+            clauses.extend(subquery_clauses(right));
+            clauses
+        }
+        None => vec![q.to_string()],
+    }
+}
+
+/// Splits `(A) OP (B)` into `A` and `B` if both groups are balanced
+fn split_merge(q: &str) -> Option<(&str, &str)> {
+    let q = q.trim();
+    if !q.starts_with('(') || !q.ends_with(')') {
+        return None;
+    }
+
+    let left_end = closing_paren(q)?;
+    let rest = &q[left_end + 1..];
+    let right_start = left_end + 1 + rest.find('(')?;
+    let operator = rest[..right_start - left_end - 1].trim();
+
+    if !matches!(operator, "AND" | "OR" | "NOT")
+        || closing_paren(&q[right_start..])? != q.len() - right_start - 1
+    {
+        return None;
+    }
+
+    Some((&q[1..left_end], &q[right_start + 1..q.len() - 1]))
+}
+
+#[cfg(test)]
+mod explain_tests {
+    use super::*;
+    use crate::testing::MockSolr;
+    use serde_json::json;
+    use std::error::Error;
+
+    #[test]
+    fn should_explain_parsed_query() -> Result<(), Box<dyn Error>> {
+        let solr = MockSolr::start()?;
+        solr.respond(
+            "-(a:1)",
+            json!({
+                "response": {"numFound": 0, "docs": []},
+                "debug": {
+                    "parsedquery": "-a:1 +MatchAllDocsQuery(*:*)",
+                    "parsedquery_toString": "-a:1 +*:*",
+                },
+            }),
+        );
+
+        let query = SolrQuery::new(solr.select_url("books", "-(a:1)").as_str())?;
+        let explanation = query.explain(&SolrClient::new())?;
+
+        assert_eq!(explanation.parsed_query, "-a:1 +MatchAllDocsQuery(*:*)");
+        assert_eq!(explanation.rewritten_query, "-a:1 +*:*");
+
+        let request = solr.requests().pop().ok_or("no request")?;
+        assert_eq!(request.params("rows"), vec!["0"]);
+        assert_eq!(request.params("debug"), vec!["query"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_explain_doc_with_json_responses() -> Result<(), Box<dyn Error>> {
+        let solr = MockSolr::start()?;
+        solr.respond_schema(json!({
+            "uniqueKey": "id",
+            "fields": [],
+            "dynamicFields": [],
+            "fieldTypes": [],
+        }));
+        solr.respond(
+            "(a:1) AND (b:1)",
+            json!({
+                "response": {"numFound": 1, "docs": []},
+                "debug": {"explainOther": {"doc1": "1.0 = sum of"}},
+            }),
+        );
+        solr.respond("a:1", json!({"response": {"numFound": 1, "docs": []}}));
+        solr.respond("b:1", json!({"response": {"numFound": 0, "docs": []}}));
+
+        let mut url = solr.select_url("books", "(a:1) AND (b:1)");
+        url.query_pairs_mut()
+            .append_pair("rows", "10")
+            .append_pair("wt", "xml");
+        let explanation = SolrQuery::new(url)?.explain_doc(&SolrClient::new(), "doc1")?;
+
+        assert!(explanation.matched);
+        assert_eq!(explanation.explanation.as_deref(), Some("1.0 = sum of"));
+        assert_eq!(
+            explanation.clauses,
+            vec![
+                ClauseMatch {
+                    q: "a:1".into(),
+                    matched: true
+                },
+                ClauseMatch {
+                    q: "b:1".into(),
+                    matched: false
+                },
+            ]
+        );
+
+        for request in solr
+            .requests()
+            .iter()
+            .filter(|r| r.path().ends_with("/select"))
+        {
+            assert_eq!(request.params("rows"), vec!["0"]);
+            assert_eq!(request.params("wt"), vec!["json"]);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_split_merged_subqueries() {
+        assert_eq!(
+            subquery_clauses("((title:\"a (b\") AND (lang:en)) OR (year:[2020 TO *])"),
+            vec!["title:\"a (b\"", "lang:en", "year:[2020 TO *]"]
+        );
+        assert_eq!(subquery_clauses("(a) AND b"), vec!["(a) AND b"]);
+        assert_eq!(
+            subquery_clauses("(a) AND (b) OR (c)"),
+            vec!["(a) AND (b) OR (c)"]
+        );
+    }
+}
@@ -0,0 +1,329 @@
+use crate::client::SolrClient;
+use crate::errors::SolrSubqueryError;
+use crate::solr_query::SolrQuery;
+
+use serde::de::{DeserializeSeed, Error as _, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde_json::Value;
+use std::io::{BufReader, Write};
+
+/// Separator of the values of multivalued fields in CSV exports
+///
+/// Separators and backslashes inside the values are escaped with a backslash.
+pub const CSV_MULTIVALUE_SEPARATOR: &str = "|";
+
+impl SolrClient {
+    /// Streams every document matched by a query through the `/export` handler
+    ///
+    /// The query must have `sort` and `fl` parameters, see [`SolrQuery::to_export`].
+    /// Documents are handed over as they are read from the response, which is never cached.
+    pub fn export<F>(&self, query: &SolrQuery, mut on_doc: F) -> Result<(), SolrSubqueryError>
+    where
+        F: FnMut(&Value) -> Result<(), SolrSubqueryError>,
+    {
+        let read = |response: ureq::Response| {
+            let mut state = ExportState {
+                on_doc: &mut on_doc,
+                error: None,
+                found: false,
+            };
+            let mut deserializer =
+                serde_json::Deserializer::from_reader(BufReader::new(response.into_reader()));
+
+            let read = ExportPath {
+                state: &mut state,
+                path: &["response", "docs"],
+            }
+            .deserialize(&mut deserializer)
+            .and_then(|_| deserializer.end());
+
+            match (read, state.error, state.found) {
+                (_, Some(e), _) => Err(e),
+                (Err(e), None, _) => Err(SolrSubqueryError::InvalidResponse(e.to_string())),
+                (Ok(_), None, false) => Err(SolrSubqueryError::InvalidResponse(
+                    "missing docs".to_string(),
+                )),
+                (Ok(_), None, true) => Ok(()),
+            }
+        };
+
+        self.send("GET", &query.to_export()?.url, None, read, |_| None)
+    }
+}
+
+/// What is left of an `/export` response being read
+struct ExportState<'a, F> {
+    on_doc: &'a mut F,
+    /// The error of `on_doc` or of an exception reported by Solr, stopping the reading
+    error: Option<SolrSubqueryError>,
+    found: bool,
+}
+
+/// Follows a path of keys to the documents of an `/export` response, skipping everything else
+struct ExportPath<'a, 'b, F> {
+    state: &'a mut ExportState<'b, F>,
+    path: &'static [&'static str],
+}
+
+impl<'de, F> DeserializeSeed<'de> for ExportPath<'_, '_, F>
+where
+    F: FnMut(&Value) -> Result<(), SolrSubqueryError>,
+{
+    type Value = ();
+
+    fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        match self.path.is_empty() {
+            true => deserializer.deserialize_seq(self),
+            false => deserializer.deserialize_map(self),
+        }
+    }
+}
+
+impl<'de, F> Visitor<'de> for ExportPath<'_, '_, F>
+where
+    F: FnMut(&Value) -> Result<(), SolrSubqueryError>,
+{
+    type Value = ();
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "an export response with {}", self.path.join("."))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(key) = map.next_key::<String>()? {
+            match self.path.first() {
+                Some(next) if key == *next => map.next_value_seed(ExportPath {
+                    state: &mut *self.state,
+                    path: &self.path[1..],
+                })?,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        self.state.found = true;
+
+        while let Some(doc) = seq.next_element::<Value>()? {
+            // Failures while exporting are reported as a last document
+            let result = match doc["EXCEPTION"].as_str() {
+                Some(exception) => Err(SolrSubqueryError::InvalidResponse(exception.to_string())),
+                None => (self.state.on_doc)(&doc),
+            };
+
+            if let Err(e) = result {
+                self.state.error = Some(e);
+                return Err(A::Error::custom("export interrupted"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl SolrQuery {
+    /// Writes every document matched by the query as CSV, with a header row of the fields
+    ///
+    /// Documents are fetched with cursor pagination sorted on the unique key of the
+    /// collection. Values of multivalued fields are joined with `CSV_MULTIVALUE_SEPARATOR`.
+    pub fn export_csv<W: Write>(
+        &self,
+        client: &SolrClient,
+        writer: &mut W,
+        fields: &[&str],
+    ) -> Result<(), SolrSubqueryError> {
+        write_line(
+            writer,
+            &fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>(),
+        )?;
+
+        self.stream_pages(client, fields, |doc| {
+            let row = fields
+                .iter()
+                .map(|field| csv_field(&csv_value(&doc[field])))
+                .collect::<Vec<_>>();
+            write_line(writer, &row)
+        })
+    }
+
+    /// Writes every document matched by the query as JSON Lines
+    ///
+    /// Only the given fields are exported, or every stored field if none is given.
+    /// Documents are fetched like [`SolrQuery::export_csv`].
+    pub fn export_jsonl<W: Write>(
+        &self,
+        client: &SolrClient,
+        writer: &mut W,
+        fields: &[&str],
+    ) -> Result<(), SolrSubqueryError> {
+        self.stream_pages(client, fields, |doc| {
+            writeln!(writer, "{}", doc).map_err(|e| SolrSubqueryError::Write(e.to_string()))
+        })
+    }
+
+    /// Harvests the given fields of the documents matched by the query
+    fn stream_pages<F>(
+        &self,
+        client: &SolrClient,
+        fields: &[&str],
+        on_doc: F,
+    ) -> Result<(), SolrSubqueryError>
+    where
+        F: FnMut(&Value) -> Result<(), SolrSubqueryError>,
+    {
+        let key_field = client.schema(self)?.unique_key;
+        let query = match fields.is_empty() {
+            true => self.clone(),
+            false => self.clone().with_param("fl", &fields.join(",")),
+        };
+
+        client.harvest(&query, &key_field, on_doc)
+    }
+}
+
+/// Formats a value of a document as a CSV field, before escaping
+fn csv_value(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.to_string(),
+        Value::Array(values) => values
+            .iter()
+            .map(|v| {
+                csv_value(v).replace('\\', "\\\\").replace(
+                    CSV_MULTIVALUE_SEPARATOR,
+                    &format!("\\{}", CSV_MULTIVALUE_SEPARATOR),
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(CSV_MULTIVALUE_SEPARATOR),
+        value => value.to_string(),
+    }
+}
+
+/// Quotes a CSV field if it contains a delimiter, a quote or a line break
+fn csv_field(value: &str) -> String {
+    match value.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", value.replace('"', "\"\"")),
+        false => value.to_string(),
+    }
+}
+
+fn write_line<W: Write>(writer: &mut W, fields: &[String]) -> Result<(), SolrSubqueryError> {
+    write!(writer, "{}\r\n", fields.join(",")).map_err(|e| SolrSubqueryError::Write(e.to_string()))
+}
+
+#[cfg(test)]
+mod export_tests {
+    use super::*;
+    use crate::cache::MemoryCache;
+    use crate::solr_query::QueryParam;
+    use crate::testing::MockSolr;
+    use serde_json::json;
+    use std::error::Error;
+
+    #[test]
+    fn should_stream_exported_documents() -> Result<(), Box<dyn Error>> {
+        let solr = MockSolr::start()?;
+        solr.respond_docs("lang:en", vec![json!({"id": "1"}), json!({"id": "2"})]);
+        solr.respond_docs("lang:fr", vec![json!({"EXCEPTION": "sort field missing"})]);
+
+        let client = SolrClient::new().with_cache(MemoryCache::new());
+        let mut url = solr.select_url("books", "lang:en");
+        url.query_pairs_mut()
+            .append_pair("fl", "id")
+            .append_pair("sort", "id asc");
+        let query = SolrQuery::new(url)?;
+
+        let mut ids = Vec::new();
+        client.export(&query, |doc| {
+            ids.push(doc["id"].clone());
+            Ok(())
+        })?;
+        assert_eq!(ids, vec![json!("1"), json!("2")]);
+
+        let mut seen = 0;
+        let interrupted = client.export(&query, |_| {
+            seen += 1;
+            Err(SolrSubqueryError::Write("disk full".to_string()))
+        });
+        assert_eq!(
+            interrupted,
+            Err(SolrSubqueryError::Write("disk full".to_string()))
+        );
+        assert_eq!(seen, 1);
+
+        let requests = solr.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].path(), "/solr/books/export");
+
+        let mut url = solr.select_url("books", "lang:fr");
+        url.query_pairs_mut()
+            .append_pair("fl", "id")
+            .append_pair("sort", "id asc");
+        assert_eq!(
+            client.export(&SolrQuery::new(url)?, |_| Ok(())),
+            Err(SolrSubqueryError::InvalidResponse(
+                "sort field missing".to_string()
+            ))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_export_csv() -> Result<(), Box<dyn Error>> {
+        let solr = MockSolr::start()?;
+        solr.respond_schema(json!({
+            "uniqueKey": "id",
+            "fields": [],
+            "dynamicFields": [],
+            "fieldTypes": [],
+        }));
+        solr.respond(
+            "lang:en",
+            json!({
+                "response": {"numFound": 2, "docs": [
+                    {"id": "1", "title": "Rust, in action", "tags": ["sys|low", "web"]},
+                    {"id": "2", "title": "The \"Book\""},
+                ]},
+                "nextCursorMark": "*",
+            }),
+        );
+
+        let query = SolrQuery::new(solr.select_url("books", "lang:en"))?;
+        let mut csv = Vec::new();
+        query.export_csv(&SolrClient::new(), &mut csv, &["id", "title", "tags"])?;
+
+        assert_eq!(
+            String::from_utf8(csv)?,
+            "id,title,tags\r\n1,\"Rust, in action\",sys\\|low|web\r\n2,\"The \"\"Book\"\"\",\r\n"
+        );
+
+        let requests = solr.requests();
+        assert_eq!(requests[0].path(), "/solr/books/schema");
+        assert_eq!(requests[1].params("fl"), vec!["id,title,tags"]);
+        assert_eq!(requests[1].params("sort"), vec!["id asc"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_escape_csv_values() {
+        assert_eq!(csv_field(&csv_value(&json!("plain"))), "plain");
+        assert_eq!(
+            csv_field(&csv_value(&json!("say \"hi\", then\nleave"))),
+            "\"say \"\"hi\"\", then\nleave\""
+        );
+        assert_eq!(csv_field(&csv_value(&json!(["en", "fr"]))), "en|fr");
+        assert_eq!(
+            csv_field(&csv_value(&json!(["a|b", "c\\d"]))),
+            "a\\|b|c\\\\d"
+        );
+        assert_eq!(csv_field(&csv_value(&json!("a|b"))), "a|b");
+        assert_eq!(csv_field(&csv_value(&json!([1.5, true]))), "1.5|true");
+        assert_eq!(csv_field(&csv_value(&json!(null))), "");
+    }
+}
@@ -0,0 +1,152 @@
+use crate::client::SolrClient;
+use crate::errors::SolrSubqueryError;
+use crate::solr_query::SolrQuery;
+
+use serde_json::Value;
+use std::collections::VecDeque;
+
+/// Number of documents fetched per request while harvesting
+pub const HARVEST_PAGE_SIZE: usize = 1000;
+
+/// Parameters replaced by the cursor pagination
+const PAGINATION_PARAMS: [&str; 5] = ["sort", "rows", "start", "cursorMark", "wt"];
+
+impl SolrClient {
+    /// Streams every document matched by a query
+    ///
+    /// Documents are fetched with cursor pagination sorted on `key_field`, which
+    /// must be the unique key field of the collection.
+    pub fn harvest<F>(
+        &self,
+        query: &SolrQuery,
+        key_field: &str,
+        mut on_doc: F,
+    ) -> Result<(), SolrSubqueryError>
+    where
+        F: FnMut(&Value) -> Result<(), SolrSubqueryError>,
+    {
+        for doc in self.harvest_iter(query, key_field, HARVEST_PAGE_SIZE) {
+            on_doc(&doc?)?;
+        }
+
+        Ok(())
+    }
+
+    /// Iterates over every document matched by a query, fetching `rows` documents per request
+    ///
+    /// Pages are fetched as the documents are consumed, see `SolrClient::harvest`.
+    pub fn harvest_iter(&self, query: &SolrQuery, key_field: &str, rows: usize) -> Harvest {
+        Harvest {
+            client: self.clone(),
+            query: query.clone(),
+            key_field: key_field.to_string(),
+            rows: rows.max(1),
+            cursor: Some("*".to_string()),
+            docs: VecDeque::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// The documents matched by a query, fetched page by page with a cursor
+pub struct Harvest {
+    client: SolrClient,
+    query: SolrQuery,
+    key_field: String,
+    rows: usize,
+    /// Cursor mark of the next page, `None` once the last one was fetched
+    cursor: Option<String>,
+    docs: VecDeque<Value>,
+}
+
+impl Harvest {
+    fn fetch_page(&mut self, cursor: String) -> Result<(), SolrSubqueryError> {
+        let mut url = self.query.url.clone();
+        let parameters = self
+            .query
+            .url
+            .query_pairs()
+            .filter(|(k, _)| !PAGINATION_PARAMS.contains(&k.as_ref()))
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<Vec<_>>();
+
+        url.query_pairs_mut()
+            .clear()
+            .extend_pairs(parameters)
+            .append_pair("sort", &format!("{} asc", self.key_field))
+            .append_pair("rows", &self.rows.to_string())
+            .append_pair("cursorMark", &cursor)
+            .append_pair("wt", "json");
+
+        let response = self.client.get_json(&url)?;
+        let docs = response["response"]["docs"]
+            .as_array()
+            .ok_or_else(|| SolrSubqueryError::InvalidResponse("missing docs".to_string()))?;
+        let next_cursor = response["nextCursorMark"].as_str().ok_or_else(|| {
+            SolrSubqueryError::InvalidResponse("missing nextCursorMark".to_string())
+        })?;
+
+        if next_cursor != cursor {
+            self.cursor = Some(next_cursor.to_string());
+        }
+        self.docs.extend(docs.iter().cloned());
+        Ok(())
+    }
+}
+
+impl Iterator for Harvest {
+    type Item = Result<Value, SolrSubqueryError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.docs.is_empty() {
+            let cursor = self.cursor.take()?;
+            if let Err(e) = self.fetch_page(cursor) {
+                return Some(Err(e));
+            }
+        }
+
+        self.docs.pop_front().map(Ok)
+    }
+}
+
+#[cfg(test)]
+mod harvest_tests {
+    use super::*;
+    use crate::solr_query::QueryParam;
+    use crate::testing::MockSolr;
+    use serde_json::json;
+    use std::error::Error;
+
+    #[test]
+    fn should_iterate_over_harvested_documents() -> Result<(), Box<dyn Error>> {
+        let solr = MockSolr::start()?;
+        solr.respond(
+            "a:1",
+            json!({
+                "response": {"numFound": 2, "docs": [{"id": "1"}, {"id": "2"}]},
+                "nextCursorMark": "*",
+            }),
+        );
+
+        let client = SolrClient::new();
+        let mut url = solr.select_url("books", "a:1");
+        url.query_pairs_mut()
+            .append_pair("start", "10")
+            .append_pair("rows", "5");
+        let query = SolrQuery::new(url)?;
+
+        let docs = client
+            .harvest_iter(&query, "id", 2)
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(docs, vec![json!({"id": "1"}), json!({"id": "2"})]);
+
+        let requests = solr.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].params("start"), Vec::<String>::new());
+        assert_eq!(requests[0].params("rows"), vec!["2"]);
+        assert_eq!(requests[0].params("sort"), vec!["id asc"]);
+        assert_eq!(requests[0].params("cursorMark"), vec!["*"]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+use crate::client::SolrClient;
+use crate::solr_query::{count_clauses, QueryParam};
+
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
+
+#[derive(Debug, Clone, PartialEq)]
+/// Measures of a request sent to Solr
+pub struct RequestMetrics {
+    pub method: String,
+    pub path: String,
+    /// Time until the response was read, or the request failed
+    pub latency: Duration,
+    /// HTTP status, `None` when no response was received
+    pub status: Option<u16>,
+    /// `numFound` of search responses
+    pub num_found: Option<u64>,
+    /// Estimated number of clauses of the q parameter, if any
+    pub clause_count: Option<usize>,
+}
+
+/// Receives the measures of every request sent by a client, retries included
+pub trait MetricsSink: Send + Sync {
+    fn record(&self, metrics: &RequestMetrics);
+}
+
+#[derive(Clone)]
+/// A metrics sink shared by the clones of a client
+pub(crate) struct SharedSink(pub(crate) Arc<dyn MetricsSink>);
+
+impl std::fmt::Debug for SharedSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_tuple("SharedSink").finish()
+    }
+}
+
+impl SolrClient {
+    /// Reports the measures of every request to a sink
+    pub fn with_metrics(mut self, sink: Arc<dyn MetricsSink>) -> SolrClient {
+        self.metrics = Some(SharedSink(sink));
+        self
+    }
+
+    pub(crate) fn record_metrics(
+        &self,
+        method: &str,
+        url: &Url,
+        latency: Duration,
+        status: Option<u16>,
+        response: Option<&Value>,
+    ) {
+        if let Some(SharedSink(sink)) = &self.metrics {
+            sink.record(&RequestMetrics {
+                method: method.to_string(),
+                path: url.path().to_string(),
+                latency,
+                status,
+                num_found: response.and_then(|r| r["response"]["numFound"].as_u64()),
+                clause_count: url.params("q").first().map(|q| count_clauses(q)),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod metrics_tests {
+    use super::*;
+    use crate::solr_query::SolrQuery;
+    use crate::testing::MockSolr;
+    use serde_json::json;
+    use std::error::Error;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<RequestMetrics>>);
+
+    impl MetricsSink for Recorder {
+        fn record(&self, metrics: &RequestMetrics) {
+            self.0.lock().expect("metrics lock").push(metrics.clone());
+        }
+    }
+
+    #[test]
+    fn should_report_request_metrics() -> Result<(), Box<dyn Error>> {
+        let solr = MockSolr::start()?;
+        solr.respond_docs("a:1 OR b:1", vec![json!({"id": "1"}), json!({"id": "2"})]);
+
+        let recorder = Arc::new(Recorder::default());
+        let client = SolrClient::new().with_metrics(recorder.clone());
+
+        client.count(&SolrQuery::new(solr.select_url("books", "a:1 OR b:1"))?)?;
+        assert!(client
+            .count(&SolrQuery::new(solr.select_url("books", "c:1"))?)
+            .is_err());
+
+        let metrics = recorder.0.lock().expect("metrics lock");
+        assert_eq!(metrics.len(), 2);
+        assert_eq!(metrics[0].path, "/solr/books/select");
+        assert_eq!(metrics[0].status, Some(200));
+        assert_eq!(metrics[0].num_found, Some(2));
+        assert_eq!(metrics[0].clause_count, Some(2));
+        assert_eq!(metrics[1].status, Some(404));
+        assert_eq!(metrics[1].num_found, None);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,557 @@
+mod admin;
+pub use admin::*;
+mod audit;
+pub use audit::*;
+mod auth;
+pub use auth::*;
+mod batch;
+mod budget;
+pub use budget::*;
+mod diff;
+pub use diff::*;
+mod digest;
+pub use digest::*;
+mod explain;
+pub use explain::*;
+mod export;
+pub use export::*;
+mod harvest;
+pub use harvest::*;
+mod metrics;
+pub use metrics::*;
+mod partition;
+pub use partition::*;
+mod ping;
+mod retry;
+pub use retry::*;
+mod schema;
+pub use schema::*;
+mod tls;
+pub use tls::*;
+
+use crate::cache::{CacheKey, CacheStore};
+use crate::errors::SolrSubqueryError;
+use crate::replicas::Replicas;
+use crate::solr_query::timeout::time_allowed;
+use crate::solr_query::{fnv1a, with_origin, SolrQuery};
+
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use url::Url;
+
+/// Time given to Solr to send back partial results once the `timeAllowed` of a query is over
+const TIME_ALLOWED_GRACE: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone)]
+/// Sends requests to Solr
+pub struct SolrClient {
+    agent: ureq::Agent,
+    retry: RetryPolicy,
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    credentials: Option<Credentials>,
+    tls: Option<Arc<rustls::ClientConfig>>,
+    replicas: Option<Arc<Mutex<Replicas>>>,
+    cache: Option<SharedCache>,
+    metrics: Option<metrics::SharedSink>,
+    preflight_ping: bool,
+}
+
+#[derive(Clone)]
+/// A response store shared by the clones of a client
+struct SharedCache(Arc<Mutex<Box<dyn CacheStore + Send>>>);
+
+impl std::fmt::Debug for SharedCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_tuple("SharedCache").finish()
+    }
+}
+
+impl SharedCache {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Box<dyn CacheStore + Send>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for SolrClient {
+    fn default() -> SolrClient {
+        SolrClient::new()
+    }
+}
+
+impl SolrClient {
+    pub fn new() -> SolrClient {
+        SolrClient {
+            agent: ureq::AgentBuilder::new().build(),
+            retry: RetryPolicy::default(),
+            connect_timeout: None,
+            read_timeout: None,
+            credentials: None,
+            tls: None,
+            replicas: None,
+            cache: None,
+            metrics: None,
+            preflight_ping: false,
+        }
+    }
+
+    /// Reuses the stored responses of GET requests, keyed by the canonical form of their URL
+    pub fn with_cache<S: CacheStore + Send + 'static>(mut self, store: S) -> SolrClient {
+        self.cache = Some(SharedCache(Arc::new(Mutex::new(Box::new(store)))));
+        self
+    }
+
+    /// Balances the requests over replicas of the collection, failing over to the next replica
+    /// when one can't be reached
+    ///
+    /// Requests keep their path and parameters, only their origin is replaced.
+    pub fn with_replicas(mut self, replicas: Replicas) -> SolrClient {
+        self.replicas = Some(Arc::new(Mutex::new(replicas)));
+        self
+    }
+
+    /// Gets the URL of the request sent to the next replica, if any
+    ///
+    /// Replicas whose base is in `failed` are skipped. Gets the routed URL and the base of the
+    /// replica, if any.
+    fn route(&self, url: &Url, failed: &[Url]) -> Result<(Url, Option<Url>), SolrSubqueryError> {
+        match &self.replicas {
+            Some(replicas) => {
+                let mut replicas = replicas.lock().unwrap_or_else(|e| e.into_inner());
+                let base = replicas
+                    .next_base_excluding(failed)
+                    .ok_or(SolrSubqueryError::NoReplica)?;
+                Ok((with_origin(url, base)?, Some(base.clone())))
+            }
+            None => Ok((url.clone(), None)),
+        }
+    }
+
+    /// Gets the number of replicas a request can be sent to
+    fn replica_count(&self) -> usize {
+        self.replicas.as_ref().map_or(1, |replicas| {
+            replicas.lock().unwrap_or_else(|e| e.into_inner()).len()
+        })
+    }
+
+    /// Uses custom TLS settings, such as a private root CA or a client certificate
+    pub fn with_tls(mut self, options: TlsOptions) -> Result<SolrClient, SolrSubqueryError> {
+        self.tls = Some(Arc::new(options.client_config()?));
+        self.agent = self.build_agent();
+        Ok(self)
+    }
+
+    /// Authenticates every request, unless its URL embeds other credentials
+    pub fn with_credentials(mut self, credentials: Credentials) -> SolrClient {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    /// Sets the timeouts of the connection to Solr and of the reading of its responses
+    ///
+    /// Queries with a `timeAllowed` parameter use it as their timeout instead.
+    pub fn with_timeouts(mut self, connect: Duration, read: Duration) -> SolrClient {
+        self.connect_timeout = Some(connect);
+        self.read_timeout = Some(read);
+        self.agent = self.build_agent();
+        self
+    }
+
+    fn build_agent(&self) -> ureq::Agent {
+        let mut builder = ureq::AgentBuilder::new();
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.timeout_connect(timeout);
+        }
+        if let Some(timeout) = self.read_timeout {
+            builder = builder.timeout_read(timeout);
+        }
+        if let Some(tls) = &self.tls {
+            builder = builder.tls_config(tls.clone());
+        }
+        builder.build()
+    }
+
+    /// Prepares an authenticated request, timing out with the `timeAllowed` of the URL if any
+    ///
+    /// Credentials embedded in the URL are moved to the `Authorization` header.
+    fn request(&self, method: &str, url: &Url) -> ureq::Request {
+        let credentials = Credentials::from_url(url).or_else(|| self.credentials.clone());
+        let mut url = url.clone();
+        let _ = url.set_username("");
+        let _ = url.set_password(None);
+
+        let mut request = self.agent.request_url(method, &url);
+        if let Some(credentials) = credentials {
+            request = request.set("Authorization", &credentials.header());
+        }
+        match time_allowed(&url) {
+            Some(timeout) => request.timeout(timeout + TIME_ALLOWED_GRACE),
+            None => request,
+        }
+    }
+
+    /// Hashes the credentials a request is sent with, so each identity gets its own cached responses
+    fn identity(&self, url: &Url) -> u64 {
+        Credentials::from_url(url)
+            .or_else(|| self.credentials.clone())
+            .map_or(0, |credentials| fnv1a(credentials.header().as_bytes()))
+    }
+
+    /// Retries the requests failing because of transient errors
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> SolrClient {
+        self.retry = retry;
+        self
+    }
+
+    /// Sends a request and parses the JSON response
+    fn call(
+        &self,
+        method: &str,
+        url: &Url,
+        body: Option<&Value>,
+    ) -> Result<Value, SolrSubqueryError> {
+        self.send(
+            method,
+            url,
+            body,
+            |response| {
+                response
+                    .into_json()
+                    .map_err(|e| SolrSubqueryError::InvalidResponse(e.to_string()))
+            },
+            |json| Some(json),
+        )
+    }
+
+    /// Sends a request and reads the response with `read`
+    ///
+    /// Unreachable replicas are failed over, then the request is retried according to the retry
+    /// policy. `measured` gets the JSON response read, if any, for the metrics.
+    pub(crate) fn send<T, F>(
+        &self,
+        method: &str,
+        url: &Url,
+        body: Option<&Value>,
+        read: F,
+        measured: fn(&T) -> Option<&Value>,
+    ) -> Result<T, SolrSubqueryError>
+    where
+        F: FnOnce(ureq::Response) -> Result<T, SolrSubqueryError>,
+    {
+        // Replicas found unreachable since the last retry
+        let (mut retry, mut failed) = (0, Vec::new());
+
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "solr_request",
+            method,
+            path = url.path(),
+            q = crate::solr_query::QueryParam::params(url, "q")
+                .first()
+                .map(|q| q.as_str())
+        )
+        .entered();
+
+        loop {
+            let (routed, base) = self.route(url, &failed)?;
+            let request = self.request(method, &routed);
+            let started = Instant::now();
+            let response = match body {
+                Some(body) => request.send_json(body),
+                None => request.call(),
+            };
+
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                status = response.as_ref().ok().map(|r| r.status()),
+                error = response.as_ref().err().map(|e| e.to_string()),
+                elapsed_ms = started.elapsed().as_millis() as u64,
+                retry,
+                failovers = failed.len(),
+                "sent request"
+            );
+
+            if let Err(e) = &response {
+                let status = match e {
+                    ureq::Error::Status(status, _) => Some(*status),
+                    ureq::Error::Transport(_) => None,
+                };
+                self.record_metrics(method, url, started.elapsed(), status, None);
+            }
+
+            match response {
+                Ok(response) => {
+                    let status = response.status();
+                    let read = read(response);
+                    self.record_metrics(
+                        method,
+                        url,
+                        started.elapsed(),
+                        Some(status),
+                        read.as_ref().ok().and_then(measured),
+                    );
+                    return read;
+                }
+                Err(e) if failed.len() + 1 < self.replica_count() && is_unreachable(&e) => {
+                    failed.extend(base);
+                }
+                Err(e) if retry + 1 < self.retry.max_attempts && self.retry.should_retry(&e) => {
+                    failed.clear();
+                    retry += 1;
+                    std::thread::sleep(self.retry.backoff(retry));
+                }
+                Err(e) => return Err(SolrSubqueryError::Http(e.to_string())),
+            }
+        }
+    }
+
+    /// Sends a GET request and parses the JSON response, bypassing the cache
+    pub(crate) fn get_json_uncached(&self, url: &Url) -> Result<Value, SolrSubqueryError> {
+        self.call("GET", url, None)
+    }
+
+    /// Sends a GET request and parses the JSON response, unless the response is cached
+    pub fn get_json(&self, url: &Url) -> Result<Value, SolrSubqueryError> {
+        let cache = match &self.cache {
+            Some(cache) => cache,
+            None => return self.call("GET", url, None),
+        };
+
+        let key = CacheKey::for_url(url).with_identity(self.identity(url));
+        if let Some(response) = cache.lock().get(&key)? {
+            return Ok(response);
+        }
+
+        let response = self.call("GET", url, None)?;
+        cache.lock().put(&key, &response)?;
+        Ok(response)
+    }
+
+    /// Fetches a page of results of a query, reusing the stored response if any
+    ///
+    /// `cursor` is the `cursorMark` of the page, `*` for the first one.
+    pub fn get_page_cached(
+        &self,
+        query: &SolrQuery,
+        cursor: &str,
+        store: &mut dyn CacheStore,
+    ) -> Result<Value, SolrSubqueryError> {
+        let key = CacheKey::new(query, cursor).with_identity(self.identity(&query.url));
+        if let Some(response) = store.get(&key)? {
+            return Ok(response);
+        }
+
+        let mut url = query.url.clone();
+        let parameters = query
+            .url
+            .query_pairs()
+            .filter(|(k, _)| k != "cursorMark" && k != "wt")
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<Vec<_>>();
+
+        url.query_pairs_mut()
+            .clear()
+            .extend_pairs(parameters)
+            .append_pair("cursorMark", cursor)
+            .append_pair("wt", "json");
+
+        let response = self.get_json(&url)?;
+        store.put(&key, &response)?;
+        Ok(response)
+    }
+
+    /// Fetches the documents of the page of results requested by a query
+    pub fn documents(&self, query: &SolrQuery) -> Result<Vec<Value>, SolrSubqueryError> {
+        let mut url = query.url.clone();
+        let parameters = query
+            .url
+            .query_pairs()
+            .filter(|(k, _)| k != "wt")
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<Vec<_>>();
+
+        url.query_pairs_mut()
+            .clear()
+            .extend_pairs(parameters)
+            .append_pair("wt", "json");
+
+        match self.get_json(&url)?["response"]["docs"].take() {
+            Value::Array(docs) => Ok(docs),
+            _ => Err(SolrSubqueryError::InvalidResponse(
+                "missing docs".to_string(),
+            )),
+        }
+    }
+
+    /// Counts the documents matched by a query without fetching them
+    pub fn count(&self, query: &SolrQuery) -> Result<u64, SolrSubqueryError> {
+        let mut url = query.url.clone();
+        let parameters = query
+            .url
+            .query_pairs()
+            .filter(|(k, _)| k != "rows" && k != "wt")
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<Vec<_>>();
+
+        url.query_pairs_mut()
+            .clear()
+            .extend_pairs(parameters)
+            .append_pair("rows", "0")
+            .append_pair("wt", "json");
+
+        num_found(&self.get_json(&url)?)
+    }
+
+    /// Sends a POST request with a JSON body and parses the JSON response
+    pub fn post_json(&self, url: &Url, body: &Value) -> Result<Value, SolrSubqueryError> {
+        self.call("POST", url, Some(body))
+    }
+}
+
+/// Checks if a request failed because its target couldn't be reached
+fn is_unreachable(error: &ureq::Error) -> bool {
+    match error {
+        ureq::Error::Transport(transport) => matches!(
+            transport.kind(),
+            ureq::ErrorKind::ConnectionFailed | ureq::ErrorKind::Dns
+        ),
+        ureq::Error::Status(_, _) => false,
+    }
+}
+
+/// Reads `numFound` from a search response
+pub(crate) fn num_found(response: &Value) -> Result<u64, SolrSubqueryError> {
+    response["response"]["numFound"]
+        .as_u64()
+        .ok_or_else(|| SolrSubqueryError::InvalidResponse("missing numFound".to_string()))
+}
+
+/// Gets the URL of an admin handler (e.g. `admin/collections`) of the Solr instance serving a query
+///
+/// The core and request handler of the query's endpoint are replaced.
+pub fn admin_url(query: &SolrQuery, handler: &str) -> Url {
+    query.with_path(&format!("{}/{}", query.endpoint().base_path, handler))
+}
+
+/// Gets the URL of a handler (e.g. `schema/fields`) of the collection a query is sent to
+///
+/// The request handler of the query's endpoint is replaced, whatever its number of segments.
+pub fn collection_url(query: &SolrQuery, handler: &str) -> Url {
+    query.core_handler_url(handler)
+}
+
+#[cfg(test)]
+mod client_tests {
+    use super::*;
+    use crate::cache::MemoryCache;
+    use crate::testing::MockSolr;
+    use serde_json::json;
+    use std::error::Error;
+
+    #[test]
+    fn should_fail_over_unreachable_replicas() -> Result<(), Box<dyn Error>> {
+        let solr = MockSolr::start()?;
+        solr.respond("*:*", json!({"response": {"numFound": 3, "docs": []}}));
+
+        let replicas = Replicas::new(vec![
+            (Url::parse("http://127.0.0.1:1")?, 1),
+            (solr.collection_url("collection"), 1),
+        ])?;
+        let client = SolrClient::new().with_replicas(replicas);
+        let query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=*:*")?;
+
+        assert_eq!(client.count(&query)?, 3);
+        assert_eq!(client.count(&query)?, 3);
+        assert_eq!(solr.requests().len(), 2);
+
+        // The unreachable replica would be picked twice in a row without being excluded
+        let weighted_replicas = Replicas::new(vec![
+            (Url::parse("http://127.0.0.1:1")?, 3),
+            (solr.collection_url("collection"), 1),
+        ])?;
+        let client = SolrClient::new().with_replicas(weighted_replicas);
+
+        for _ in 0..4 {
+            assert_eq!(client.count(&query)?, 3);
+        }
+        assert_eq!(solr.requests().len(), 6);
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_reuse_cached_responses() -> Result<(), Box<dyn Error>> {
+        let solr = MockSolr::start()?;
+        solr.respond_docs("a:1", vec![json!({"id": "1"})]);
+
+        let client = SolrClient::new().with_cache(MemoryCache::new());
+        let mut url = solr.select_url("collection", "a:1");
+        url.query_pairs_mut().append_pair("fl", "id");
+        let query = SolrQuery::new(url)?;
+        let mut url = solr.collection_url("collection").join("select?fl=id")?;
+        url.query_pairs_mut().append_pair("q", "a:1");
+        let equivalent = SolrQuery::new(url)?;
+
+        assert_eq!(client.count(&query)?, 1);
+        assert_eq!(client.clone().count(&equivalent)?, 1);
+        assert_eq!(solr.requests().len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_not_share_cached_responses_between_identities() -> Result<(), Box<dyn Error>> {
+        let solr = MockSolr::start()?;
+        solr.respond_docs("a:1", vec![json!({"id": "1"})]);
+
+        let client = SolrClient::new().with_cache(MemoryCache::new());
+        let query = SolrQuery::new(solr.select_url("collection", "a:1"))?;
+
+        client.count(&query)?;
+        client
+            .clone()
+            .with_credentials(Credentials::basic("admin", "secret"))
+            .count(&query)?;
+        client
+            .clone()
+            .with_credentials(Credentials::bearer("eyJhbGciOi"))
+            .count(&query)?;
+        client.count(&query)?;
+
+        assert_eq!(solr.requests().len(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_derive_admin_url() -> Result<(), Box<dyn Error>> {
+        let query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=*:*")?;
+
+        assert_eq!(
+            admin_url(&query, "admin/collections").as_str(),
+            "http://localhost:8983/solr/admin/collections"
+        );
+        assert_eq!(
+            collection_url(&query, "schema/fields").as_str(),
+            "http://localhost:8983/solr/collection/schema/fields"
+        );
+
+        let ping = SolrQuery::from_parts(
+            "http://localhost:8983/solr",
+            "collection",
+            "admin/ping",
+            "*:*",
+        )?;
+        assert_eq!(
+            admin_url(&ping, "admin/collections").as_str(),
+            "http://localhost:8983/solr/admin/collections"
+        );
+        assert_eq!(
+            collection_url(&ping, "schema/fields").as_str(),
+            "http://localhost:8983/solr/collection/schema/fields"
+        );
+
+        Ok(())
+    }
+}
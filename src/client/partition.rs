@@ -0,0 +1,124 @@
+use crate::client::SolrClient;
+use crate::errors::SolrSubqueryError;
+use crate::solr_query::{InverseMode, QueryParam, SolrQuery};
+
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Side of a partition a document belongs to
+pub enum PartitionSide {
+    Matched,
+    Unmatched,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Number of documents on each side of a partition
+pub struct PartitionCounts {
+    pub matched: u64,
+    pub unmatched: u64,
+    /// Documents of the collection, filters included
+    pub total: u64,
+}
+
+impl PartitionCounts {
+    /// Checks that every document is on exactly one side
+    pub fn is_consistent(&self) -> bool {
+        self.matched + self.unmatched == self.total
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Splits a corpus into the documents matched by a query and all the other ones
+pub struct QueryPartition {
+    query: SolrQuery,
+    inverse: SolrQuery,
+}
+
+impl QueryPartition {
+    /// Partitions the corpus with a query and its complement
+    pub fn new(query: SolrQuery) -> QueryPartition {
+        QueryPartition {
+            inverse: query.inverse_with(InverseMode::Complement),
+            query,
+        }
+    }
+
+    pub fn query(&self) -> &SolrQuery {
+        &self.query
+    }
+
+    pub fn inverse(&self) -> &SolrQuery {
+        &self.inverse
+    }
+
+    /// Counts the documents on each side
+    pub fn counts(&self, client: &SolrClient) -> Result<(u64, u64), SolrSubqueryError> {
+        Ok((client.count(&self.query)?, client.count(&self.inverse)?))
+    }
+
+    /// Counts the documents on each side and in the whole collection
+    ///
+    /// Filter queries apply to both sides and to the total.
+    pub fn check(&self, client: &SolrClient) -> Result<PartitionCounts, SolrSubqueryError> {
+        let (matched, unmatched) = self.counts(client)?;
+
+        let mut all = self.query.url.clone();
+        all.set_param(("q", "*:*"));
+
+        Ok(PartitionCounts {
+            matched,
+            unmatched,
+            total: client.count(&SolrQuery::new(all)?)?,
+        })
+    }
+
+    /// Streams the documents of both sides, see `SolrClient::harvest`
+    pub fn harvest_both<F>(
+        &self,
+        client: &SolrClient,
+        key_field: &str,
+        mut on_doc: F,
+    ) -> Result<(), SolrSubqueryError>
+    where
+        F: FnMut(PartitionSide, &Value) -> Result<(), SolrSubqueryError>,
+    {
+        client.harvest(&self.query, key_field, |doc| {
+            on_doc(PartitionSide::Matched, doc)
+        })?;
+        client.harvest(&self.inverse, key_field, |doc| {
+            on_doc(PartitionSide::Unmatched, doc)
+        })
+    }
+}
+
+#[cfg(test)]
+mod partition_tests {
+    use super::*;
+    use crate::solr_query::SubQuery;
+    use std::error::Error;
+    use urlencoding::decode;
+
+    #[test]
+    fn should_partition_with_complement() -> Result<(), Box<dyn Error>> {
+        let first_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=a:1")?;
+        let second_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=b:1")?;
+
+        let partition = QueryPartition::new(first_query.inner_join(&second_query)?);
+
+        let url_string = partition.inverse().url.to_string();
+        let result = decode(&url_string)?;
+        assert_eq!(
+            result,
+            "http://localhost:8983/solr/collection/select?q=*:*+NOT+((a:1)+AND+(b:1))"
+        );
+
+        assert!(PartitionCounts {
+            matched: 3,
+            unmatched: 7,
+            total: 10
+        }
+        .is_consistent());
+
+        Ok(())
+    }
+}
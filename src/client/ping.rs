@@ -0,0 +1,78 @@
+use crate::client::{collection_url, SolrClient};
+use crate::errors::{redact, SolrSubqueryError};
+use crate::query_chain::QueryChain;
+use crate::solr_query::SolrQuery;
+
+impl SolrClient {
+    /// Checks that the collection a query is sent to is up with its `/admin/ping` handler
+    ///
+    /// Responses are never cached.
+    pub fn ping(&self, frame: &SolrQuery) -> Result<(), SolrSubqueryError> {
+        let mut url = collection_url(frame, "admin/ping");
+        url.query_pairs_mut().append_pair("wt", "json");
+
+        let unavailable = |reason: String| SolrSubqueryError::CollectionUnavailable {
+            url: redact(&url),
+            reason,
+        };
+
+        let response = self.get_json_uncached(&url).map_err(|e| match e {
+            SolrSubqueryError::Http(reason) => unavailable(reason),
+            e => e,
+        })?;
+
+        match response["status"].as_str() {
+            Some("OK") => Ok(()),
+            Some(status) => Err(unavailable(format!("status {}", status))),
+            None => Err(unavailable("missing status".to_string())),
+        }
+    }
+
+    /// Pings the collection of a chain before counting or auditing its steps
+    ///
+    /// Long pipelines then fail right away when the collection is down.
+    pub fn with_preflight_ping(mut self) -> SolrClient {
+        self.preflight_ping = true;
+        self
+    }
+
+    /// Pings the collection of the first remaining step of a chain, if enabled
+    pub(crate) fn preflight(&self, chain: &QueryChain) -> Result<(), SolrSubqueryError> {
+        match (self.preflight_ping, chain.pending().next()) {
+            (true, Some(query)) => self.ping(query),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod ping_tests {
+    use super::*;
+    use crate::testing::MockSolr;
+    use std::error::Error;
+
+    #[test]
+    fn should_ping_collection() -> Result<(), Box<dyn Error>> {
+        let solr = MockSolr::start()?;
+        let query = SolrQuery::new(solr.select_url("books", "a:1").as_str())?;
+
+        SolrClient::new().ping(&query)?;
+        assert_eq!(solr.requests()[0].path(), "/solr/books/admin/ping");
+
+        let down = SolrQuery::new("http://127.0.0.1:1/solr/books/select?q=a:1")?;
+        assert!(matches!(
+            SolrClient::new().ping(&down),
+            Err(SolrSubqueryError::CollectionUnavailable { url, .. })
+                if url == "http://127.0.0.1:1/solr/books/admin/ping?wt=json"
+        ));
+
+        let chain = QueryChain::new(vec![down]);
+        assert!(chain.audit(&SolrClient::new()).is_err());
+        assert!(matches!(
+            chain.audit(&SolrClient::new().with_preflight_ping()),
+            Err(SolrSubqueryError::CollectionUnavailable { .. })
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// How requests failing because of transient errors are retried
+pub struct RetryPolicy {
+    /// Number of attempts of a request, the first one included
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled after each retry
+    pub initial_backoff: Duration,
+    /// Upper bound of the delay between two attempts
+    pub max_backoff: Duration,
+    /// Retries requests answered with a 503 status
+    pub retry_on_503: bool,
+    /// Retries requests that timed out or couldn't connect
+    pub retry_on_timeout: bool,
+}
+
+impl Default for RetryPolicy {
+    /// Sends each request once
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 1,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            retry_on_503: true,
+            retry_on_timeout: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Retries transient errors until `max_attempts` requests were sent
+    pub fn exponential(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            ..RetryPolicy::default()
+        }
+    }
+
+    /// Gets the delay before the given retry, starting at 1
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    /// Checks if a failed request should be sent again
+    pub(crate) fn should_retry(&self, error: &ureq::Error) -> bool {
+        match error {
+            ureq::Error::Status(status, _) => self.retry_on_503 && *status == 503,
+            ureq::Error::Transport(transport) => {
+                self.retry_on_timeout
+                    && matches!(
+                        transport.kind(),
+                        ureq::ErrorKind::Io | ureq::ErrorKind::ConnectionFailed
+                    )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod retry_tests {
+    use super::*;
+    use crate::client::SolrClient;
+    use crate::errors::SolrSubqueryError;
+    use std::error::Error;
+    use std::time::Instant;
+    use url::Url;
+
+    #[test]
+    fn should_back_off_exponentially() {
+        let policy = RetryPolicy {
+            max_backoff: Duration::from_secs(3),
+            ..RetryPolicy::exponential(5)
+        };
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(500));
+        assert_eq!(policy.backoff(2), Duration::from_secs(1));
+        assert_eq!(policy.backoff(3), Duration::from_secs(2));
+        assert_eq!(policy.backoff(4), Duration::from_secs(3));
+    }
+
+    #[test]
+    fn should_only_retry_transient_errors() {
+        let policy = RetryPolicy::exponential(3);
+        let status = |code| {
+            let response = ureq::Response::new(code, "", "").expect("valid response");
+            ureq::Error::Status(code, response)
+        };
+
+        assert!(policy.should_retry(&status(503)));
+        assert!(!policy.should_retry(&status(400)));
+        assert!(!RetryPolicy {
+            retry_on_503: false,
+            ..policy
+        }
+        .should_retry(&status(503)));
+    }
+
+    #[test]
+    fn should_retry_refused_connections() -> Result<(), Box<dyn Error>> {
+        let client = SolrClient::new().with_retry_policy(RetryPolicy {
+            initial_backoff: Duration::from_millis(20),
+            ..RetryPolicy::exponential(3)
+        });
+
+        let started = Instant::now();
+        let response = client.get_json(&Url::parse("http://127.0.0.1:1/solr/collection/select")?);
+
+        assert!(matches!(response, Err(SolrSubqueryError::Http(_))));
+        assert!(started.elapsed() >= Duration::from_millis(60));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,174 @@
+use crate::client::{collection_url, SolrClient};
+use crate::errors::SolrSubqueryError;
+use crate::solr_query::SolrQuery;
+
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+/// A field, or dynamic field pattern, of a schema
+pub struct SchemaField {
+    pub name: String,
+    pub field_type: String,
+    pub multi_valued: bool,
+    pub indexed: bool,
+    pub stored: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A field type of a schema
+pub struct FieldType {
+    pub name: String,
+    /// Implementing class, e.g. `solr.TextField`
+    pub class: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Fields, field types and unique key of a collection, as returned by the Schema API
+pub struct Schema {
+    pub unique_key: String,
+    pub fields: Vec<SchemaField>,
+    pub dynamic_fields: Vec<SchemaField>,
+    pub field_types: Vec<FieldType>,
+}
+
+impl Schema {
+    /// Reads a schema from a Schema API `/schema` response
+    pub fn from_response(response: &Value) -> Result<Schema, SolrSubqueryError> {
+        let schema = &response["schema"];
+        let unique_key = schema["uniqueKey"]
+            .as_str()
+            .ok_or_else(|| SolrSubqueryError::InvalidResponse("missing uniqueKey".to_string()))?;
+
+        Ok(Schema {
+            unique_key: unique_key.to_string(),
+            fields: entries(schema, "fields")?.map(schema_field).collect(),
+            dynamic_fields: entries(schema, "dynamicFields")?
+                .map(schema_field)
+                .collect(),
+            field_types: entries(schema, "fieldTypes")?
+                .map(|t| FieldType {
+                    name: string(t, "name"),
+                    class: string(t, "class"),
+                })
+                .collect(),
+        })
+    }
+
+    /// Gets a field, or the first dynamic field whose pattern matches its name
+    pub fn field(&self, name: &str) -> Option<&SchemaField> {
+        self.fields.iter().find(|f| f.name == name).or_else(|| {
+            self.dynamic_fields
+                .iter()
+                .find(|f| matches_pattern(&f.name, name))
+        })
+    }
+
+    /// Gets the type of a field
+    pub fn field_type(&self, field: &str) -> Option<&FieldType> {
+        let field = self.field(field)?;
+        self.field_types.iter().find(|t| t.name == field.field_type)
+    }
+}
+
+impl SolrClient {
+    /// Fetches the schema of the collection a query is sent to
+    pub fn schema(&self, collection: &SolrQuery) -> Result<Schema, SolrSubqueryError> {
+        Schema::from_response(&self.get_json(&collection_url(collection, "schema"))?)
+    }
+}
+
+impl SolrQuery {
+    /// Checks that every field used by the query exists in the target collection
+    ///
+    /// Fields are looked up with the Schema API, dynamic field patterns included.
+    /// A misspelled field otherwise silently matches nothing.
+    pub fn validate_fields(&self, client: &SolrClient) -> Result<(), SolrSubqueryError> {
+        let schema = client.schema(self)?;
+
+        let unknown = self
+            .fields()?
+            .into_iter()
+            .filter(|field| schema.field(field).is_none())
+            .collect::<Vec<_>>();
+
+        match unknown.is_empty() {
+            true => Ok(()),
+            false => Err(SolrSubqueryError::UnknownFields(unknown)),
+        }
+    }
+}
+
+fn entries<'a>(
+    schema: &'a Value,
+    key: &str,
+) -> Result<impl Iterator<Item = &'a Value>, SolrSubqueryError> {
+    schema[key]
+        .as_array()
+        .map(|entries| entries.iter())
+        .ok_or_else(|| SolrSubqueryError::InvalidResponse(format!("missing {}", key)))
+}
+
+fn schema_field(field: &Value) -> SchemaField {
+    SchemaField {
+        name: string(field, "name"),
+        field_type: string(field, "type"),
+        multi_valued: field["multiValued"].as_bool().unwrap_or(false),
+        indexed: field["indexed"].as_bool().unwrap_or(true),
+        stored: field["stored"].as_bool().unwrap_or(true),
+    }
+}
+
+fn string(entry: &Value, key: &str) -> String {
+    entry[key].as_str().unwrap_or_default().to_string()
+}
+
+/// Matches a dynamic field pattern, which starts or ends with `*`
+fn matches_pattern(pattern: &str, field: &str) -> bool {
+    match (pattern.strip_prefix('*'), pattern.strip_suffix('*')) {
+        (Some(suffix), _) => field.ends_with(suffix),
+        (_, Some(prefix)) => field.starts_with(prefix),
+        _ => pattern == field,
+    }
+}
+
+#[cfg(test)]
+mod schema_tests {
+    use super::*;
+    use serde_json::json;
+    use std::error::Error;
+
+    #[test]
+    fn should_read_schema() -> Result<(), Box<dyn Error>> {
+        let schema = Schema::from_response(&json!({
+            "schema": {
+                "uniqueKey": "doc_id",
+                "fields": [
+                    {"name": "doc_id", "type": "string", "stored": true},
+                    {"name": "lang", "type": "string", "multiValued": true},
+                ],
+                "dynamicFields": [{"name": "*_txt_en", "type": "text_en", "indexed": true}],
+                "fieldTypes": [
+                    {"name": "string", "class": "solr.StrField"},
+                    {"name": "text_en", "class": "solr.TextField"},
+                ],
+            },
+        }))?;
+
+        assert_eq!(schema.unique_key, "doc_id");
+        assert!(schema.field("lang").ok_or("missing lang")?.multi_valued);
+        assert_eq!(
+            schema.field_type("title_txt_en").map(|t| t.class.as_str()),
+            Some("solr.TextField")
+        );
+        assert!(schema.field("title").is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_match_dynamic_field_patterns() {
+        assert!(matches_pattern("*_txt_en", "title_txt_en"));
+        assert!(matches_pattern("attr_*", "attr_color"));
+        assert!(!matches_pattern("*_s", "title_txt"));
+    }
+}
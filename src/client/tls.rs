@@ -0,0 +1,172 @@
+use crate::errors::SolrSubqueryError;
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+/// TLS settings of the connections to Solr
+pub struct TlsOptions {
+    /// PEM file of a root certificate trusted in addition to the usual ones
+    pub root_ca: Option<PathBuf>,
+    /// PEM files of the client certificate chain and of its private key, for mutual TLS
+    pub client_cert: Option<(PathBuf, PathBuf)>,
+    /// Accepts any server certificate, for development environments only
+    pub danger_accept_invalid_certs: bool,
+}
+
+impl TlsOptions {
+    /// Builds the rustls configuration, reading the certificates and the key
+    pub(crate) fn client_config(&self) -> Result<ClientConfig, SolrSubqueryError> {
+        let invalid = |e: &dyn std::fmt::Display| SolrSubqueryError::Tls(e.to_string());
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+
+        let mut roots = RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        if let Some(root_ca) = &self.root_ca {
+            for certificate in read_certificates(root_ca)? {
+                roots.add(certificate).map_err(|e| invalid(&e))?;
+            }
+        }
+
+        let builder = ClientConfig::builder_with_provider(provider.clone())
+            .with_protocol_versions(&[&rustls::version::TLS12, &rustls::version::TLS13])
+            .map_err(|e| invalid(&e))?;
+        let builder = match self.danger_accept_invalid_certs {
+            true => builder
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(AcceptAnyCertificate(provider))),
+            false => builder.with_root_certificates(roots),
+        };
+
+        match &self.client_cert {
+            Some((cert, key)) => {
+                let key = PrivateKeyDer::from_pem_file(key)
+                    .map_err(|e| SolrSubqueryError::Tls(format!("{}: {}", key.display(), e)))?;
+                builder
+                    .with_client_auth_cert(read_certificates(cert)?, key)
+                    .map_err(|e| invalid(&e))
+            }
+            None => Ok(builder.with_no_client_auth()),
+        }
+    }
+}
+
+fn read_certificates(path: &PathBuf) -> Result<Vec<CertificateDer<'static>>, SolrSubqueryError> {
+    let invalid =
+        |e: &dyn std::fmt::Display| SolrSubqueryError::Tls(format!("{}: {}", path.display(), e));
+
+    let certificates = CertificateDer::pem_file_iter(path)
+        .map_err(|e| invalid(&e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| invalid(&e))?;
+
+    match certificates.is_empty() {
+        true => Err(invalid(&"no certificate")),
+        false => Ok(certificates),
+    }
+}
+
+#[derive(Debug)]
+/// Verifies the signatures of the handshake but trusts any server certificate
+struct AcceptAnyCertificate(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AcceptAnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tls_tests {
+    use super::*;
+    use crate::client::SolrClient;
+    use std::error::Error;
+
+    /// Self-signed certificate of `solr.test`
+    const CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----
+MIIBfjCCASWgAwIBAgIUObneZOHwSPPZkaVfDrv+GOTJoFkwCgYIKoZIzj0EAwIw
+FDESMBAGA1UEAwwJc29sci50ZXN0MCAXDTI2MTAxNjEyNDA0MVoYDzIxMjYwOTIy
+MTI0MDQxWjAUMRIwEAYDVQQDDAlzb2xyLnRlc3QwWTATBgcqhkjOPQIBBggqhkjO
+PQMBBwNCAASZNAmX8he6+yOBICnvAkMEAVJQM/VzvVUOVEe1yY/9QvAPovnxWlOc
+JCEympaLMtDXNyeUci3//61+LWeZ7u/Ao1MwUTAdBgNVHQ4EFgQUX2//yjx/71kS
+WfoKm42ND0zsNEIwHwYDVR0jBBgwFoAUX2//yjx/71kSWfoKm42ND0zsNEIwDwYD
+VR0TAQH/BAUwAwEB/zAKBggqhkjOPQQDAgNHADBEAiAJ2wZgGBkmfxbc3J0u1Iip
+Jm/e943kBcAtZ2XTTyXkrgIgEiZmbcCST8bMXOs1fdC6l1y8Bpxvkct46oJqwxwf
+KNk=
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn should_load_tls_options() -> Result<(), Box<dyn Error>> {
+        let root_ca = std::env::temp_dir().join("solr-subquery-tls-tests-ca.pem");
+        std::fs::write(&root_ca, CERTIFICATE)?;
+
+        SolrClient::new().with_tls(TlsOptions {
+            root_ca: Some(root_ca.clone()),
+            ..TlsOptions::default()
+        })?;
+        SolrClient::new().with_tls(TlsOptions {
+            danger_accept_invalid_certs: true,
+            ..TlsOptions::default()
+        })?;
+
+        let missing_key = SolrClient::new().with_tls(TlsOptions {
+            client_cert: Some((root_ca.clone(), root_ca.clone())),
+            ..TlsOptions::default()
+        });
+        assert!(matches!(missing_key, Err(SolrSubqueryError::Tls(_))));
+
+        let missing_ca = SolrClient::new().with_tls(TlsOptions {
+            root_ca: Some(root_ca.with_extension("missing")),
+            ..TlsOptions::default()
+        });
+        assert!(matches!(missing_ca, Err(SolrSubqueryError::Tls(_))));
+
+        Ok(())
+    }
+}
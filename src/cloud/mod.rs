@@ -0,0 +1,192 @@
+use crate::client::SolrClient;
+use crate::errors::{redact, SolrSubqueryError};
+use crate::replicas::Replicas;
+use crate::solr_query::SolrQuery;
+
+use serde_json::Value;
+use url::Url;
+
+#[derive(Debug, Clone, PartialEq)]
+/// A SolrCloud cluster whose collections are resolved to live replicas
+///
+/// The cluster state is read with the `CLUSTERSTATUS` action of the Collections API, which
+/// reflects the live nodes and replica states Solr keeps in ZooKeeper.
+pub struct SolrCloud {
+    nodes: Vec<Url>,
+}
+
+impl SolrCloud {
+    /// Creates the cluster from the URLs of some of its nodes (e.g. `http://solr1:8983/solr`)
+    pub fn new<U: TryInto<Url>>(nodes: Vec<U>) -> Result<SolrCloud, SolrSubqueryError>
+    where
+        <U as TryInto<Url>>::Error: std::error::Error + 'static,
+    {
+        let nodes = nodes
+            .into_iter()
+            .map(|node| node.try_into().map_err(SolrSubqueryError::url_conversion))
+            .collect::<Result<Vec<Url>, _>>()?;
+
+        match nodes.is_empty() {
+            true => Err(SolrSubqueryError::NoReplica),
+            false => Ok(SolrCloud { nodes }),
+        }
+    }
+
+    /// Gets the URLs of a collection on the nodes hosting one of its active replicas
+    ///
+    /// The nodes are asked in turn until one answers.
+    pub fn live_replicas(
+        &self,
+        client: &SolrClient,
+        collection: &str,
+    ) -> Result<Vec<Url>, SolrSubqueryError> {
+        let mut last_error = SolrSubqueryError::NoReplica;
+
+        for node in &self.nodes {
+            let mut url = node.clone();
+            url.path_segments_mut()
+                .map_err(|_| SolrSubqueryError::invalid_url(redact(node)))?
+                .pop_if_empty()
+                .extend(["admin", "collections"]);
+            url.query_pairs_mut()
+                .append_pair("action", "CLUSTERSTATUS")
+                .append_pair("collection", collection)
+                .append_pair("wt", "json");
+
+            match client.get_json_uncached(&url) {
+                Ok(status) => return live_replicas(&status, collection),
+                Err(e) => last_error = e,
+            }
+        }
+
+        Err(last_error)
+    }
+
+    /// Builds a query sent to a live replica of a collection
+    ///
+    /// `request` is the request handler and its parameters, e.g. `select?q=*:*`.
+    pub fn query(
+        &self,
+        client: &SolrClient,
+        collection: &str,
+        request: &str,
+    ) -> Result<SolrQuery, SolrSubqueryError> {
+        let replica = self
+            .live_replicas(client, collection)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| SolrSubqueryError::NoLiveReplica(collection.to_string()))?;
+
+        SolrQuery::new(replica.join(request)?)
+    }
+
+    /// Gets the live replicas of a collection, evenly weighted, to balance the queries sent to it
+    pub fn replicas(
+        &self,
+        client: &SolrClient,
+        collection: &str,
+    ) -> Result<Replicas, SolrSubqueryError> {
+        Replicas::new(
+            self.live_replicas(client, collection)?
+                .into_iter()
+                .map(|replica| (replica, 1))
+                .collect(),
+        )
+        .map_err(|_| SolrSubqueryError::NoLiveReplica(collection.to_string()))
+    }
+}
+
+/// Reads the URLs of a collection on the live nodes hosting an active replica from a
+/// `CLUSTERSTATUS` response
+///
+/// URLs end with a slash so that request handlers can be joined to them.
+pub fn live_replicas(status: &Value, collection: &str) -> Result<Vec<Url>, SolrSubqueryError> {
+    let cluster = &status["cluster"];
+    let live_nodes = cluster["live_nodes"]
+        .as_array()
+        .ok_or_else(|| SolrSubqueryError::InvalidResponse("missing live_nodes".to_string()))?;
+    let shards = cluster["collections"][collection]["shards"]
+        .as_object()
+        .ok_or_else(|| SolrSubqueryError::NoLiveReplica(collection.to_string()))?;
+
+    let mut urls: Vec<Url> = Vec::new();
+    for replica in shards
+        .values()
+        .filter_map(|shard| shard["replicas"].as_object())
+        .flat_map(|replicas| replicas.values())
+        .filter(|replica| {
+            replica["state"] == "active" && live_nodes.contains(&replica["node_name"])
+        })
+    {
+        let base_url = replica["base_url"]
+            .as_str()
+            .ok_or_else(|| SolrSubqueryError::InvalidResponse("missing base_url".to_string()))?;
+        let url = Url::parse(&format!(
+            "{}/{}/",
+            base_url.trim_end_matches('/'),
+            collection
+        ))?;
+
+        if !urls.contains(&url) {
+            urls.push(url);
+        }
+    }
+
+    match urls.is_empty() {
+        true => Err(SolrSubqueryError::NoLiveReplica(collection.to_string())),
+        false => Ok(urls),
+    }
+}
+
+#[cfg(test)]
+mod cloud_tests {
+    use super::*;
+    use serde_json::json;
+    use std::error::Error;
+
+    #[test]
+    fn should_find_live_replicas() -> Result<(), Box<dyn Error>> {
+        let replica = |node: &str, state: &str| {
+            json!({
+                "core": "books_shard1_replica_n1",
+                "base_url": format!("http://{}/solr", node.replace("_solr", "")),
+                "node_name": node,
+                "state": state,
+            })
+        };
+        let status = json!({
+            "cluster": {
+                "collections": {
+                    "books": {
+                        "shards": {
+                            "shard1": {
+                                "replicas": {
+                                    "core_node1": replica("solr1:8983_solr", "active"),
+                                    "core_node2": replica("solr2:8983_solr", "recovering"),
+                                }
+                            },
+                            "shard2": {
+                                "replicas": {
+                                    "core_node3": replica("solr1:8983_solr", "active"),
+                                    "core_node4": replica("solr3:8983_solr", "active"),
+                                }
+                            }
+                        }
+                    }
+                },
+                "live_nodes": ["solr1:8983_solr", "solr2:8983_solr"]
+            }
+        });
+
+        assert_eq!(
+            live_replicas(&status, "books")?,
+            vec![Url::parse("http://solr1:8983/solr/books/")?]
+        );
+        assert_eq!(
+            live_replicas(&status, "authors"),
+            Err(SolrSubqueryError::NoLiveReplica("authors".to_string()))
+        );
+
+        Ok(())
+    }
+}
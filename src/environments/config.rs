@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::environments::{Environment, Environments};
+use crate::errors::SolrSubqueryError;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+/// A declarative environment overlay
+struct EnvironmentDefinition {
+    endpoint: Option<String>,
+    #[serde(default)]
+    params: BTreeMap<String, String>,
+}
+
+impl Environments {
+    /// Loads environments from a YAML (`.yml`, `.yaml`) or TOML (`.toml`) definition file
+    ///
+    /// ```yaml
+    /// production:
+    ///   endpoint: https://solr.example.com/solr/collection/select
+    ///   params:
+    ///     rows: "100"
+    /// development:
+    ///   params:
+    ///     rows: "10"
+    ///     debugQuery: "true"
+    /// ```
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Environments, SolrSubqueryError> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| SolrSubqueryError::InvalidConfig(format!("{}: {}", path.display(), e)))?;
+
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("yml") | Some("yaml") => Environments::from_yaml_str(&content),
+            Some("toml") => Environments::from_toml_str(&content),
+            _ => Err(SolrSubqueryError::InvalidConfig(format!(
+                "{}: unsupported file extension",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Loads environments from a YAML definition
+    pub fn from_yaml_str(yaml: &str) -> Result<Environments, SolrSubqueryError> {
+        let definitions: BTreeMap<String, EnvironmentDefinition> = serde_yaml::from_str(yaml)
+            .map_err(|e| SolrSubqueryError::InvalidConfig(e.to_string()))?;
+        into_environments(definitions)
+    }
+
+    /// Loads environments from a TOML definition
+    pub fn from_toml_str(toml: &str) -> Result<Environments, SolrSubqueryError> {
+        let definitions: BTreeMap<String, EnvironmentDefinition> =
+            toml::from_str(toml).map_err(|e| SolrSubqueryError::InvalidConfig(e.to_string()))?;
+        into_environments(definitions)
+    }
+}
+
+fn into_environments(
+    definitions: BTreeMap<String, EnvironmentDefinition>,
+) -> Result<Environments, SolrSubqueryError> {
+    let mut environments = Environments::new();
+
+    for (name, definition) in definitions {
+        let mut environment = Environment::new();
+        if let Some(endpoint) = &definition.endpoint {
+            environment = environment.endpoint(endpoint.as_str())?;
+        }
+        for (param, value) in &definition.params {
+            environment = environment.param(param, value);
+        }
+        environments.add(&name, environment);
+    }
+
+    Ok(environments)
+}
+
+#[cfg(test)]
+mod config_tests {
+    use super::*;
+    use crate::solr_query::SolrQuery;
+    use std::error::Error;
+    use urlencoding::decode;
+
+    #[test]
+    fn should_load_environments_from_yaml_and_toml() -> Result<(), Box<dyn Error>> {
+        let yaml = r#"
+production:
+  endpoint: https://solr.example.com/solr/collection/select
+  params:
+    rows: "100"
+development:
+  params:
+    debugQuery: "true"
+"#;
+
+        let toml = r#"
+[production]
+endpoint = "https://solr.example.com/solr/collection/select"
+params = { rows = "100" }
+
+[development]
+params = { debugQuery = "true" }
+"#;
+
+        let environments = Environments::from_yaml_str(yaml)?;
+        assert_eq!(environments, Environments::from_toml_str(toml)?);
+
+        let query =
+            SolrQuery::new("http://localhost:8983/solr/collection/select?q=title:rust&rows=10")?;
+        let url_string = environments.apply("development", &query)?.url.to_string();
+        let result = decode(&url_string)?;
+
+        assert_eq!(
+            result,
+            "http://localhost:8983/solr/collection/select?q=title:rust&rows=10&debugQuery=true"
+        );
+
+        Ok(())
+    }
+}
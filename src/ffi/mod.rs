@@ -0,0 +1,246 @@
+use crate::errors::SolrSubqueryError;
+use crate::solr_query::{Operator, SolrQuery, SubQuery};
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+/// Opaque handle of a query, `solr_subquery_t` in C
+pub struct FfiQuery(SolrQuery);
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Boxes the query, or records the error and returns null
+fn into_raw(result: Result<SolrQuery, SolrSubqueryError>) -> *mut FfiQuery {
+    match result {
+        Ok(query) => Box::into_raw(Box::new(FfiQuery(query))),
+        Err(err) => fail(&err.to_string()),
+    }
+}
+
+/// Records the message of the last error and returns null
+fn fail<T>(message: &str) -> *mut T {
+    let message = CString::new(message.replace('\0', " ")).ok();
+    LAST_ERROR.with(|last| *last.borrow_mut() = message);
+    ptr::null_mut()
+}
+
+/// Runs the body of an exported function, a panic can't unwind into C and is reported as an error
+fn guard<T, F: FnOnce() -> *mut T>(body: F) -> *mut T {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(|payload| {
+        let reason = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown reason");
+        fail(&format!("Panicked: {}", reason))
+    })
+}
+
+/// Parses a query from a NUL terminated URL, returns null on error
+///
+/// # Safety
+///
+/// `url` must be a valid NUL terminated string, or null.
+#[no_mangle]
+pub unsafe extern "C" fn solr_subquery_new(url: *const c_char) -> *mut FfiQuery {
+    if url.is_null() {
+        return into_raw(Err(SolrSubqueryError::invalid_url("null")));
+    }
+
+    guard(|| {
+        let url = CStr::from_ptr(url).to_string_lossy();
+        into_raw(SolrQuery::new(url.as_ref()))
+    })
+}
+
+/// Inner joins two queries into a new one, returns null on error
+///
+/// # Safety
+///
+/// Both queries must have been returned by this library and not freed, or be null.
+#[no_mangle]
+pub unsafe extern "C" fn solr_subquery_inner_join(
+    query: *const FfiQuery,
+    other: *const FfiQuery,
+) -> *mut FfiQuery {
+    match (query.as_ref(), other.as_ref()) {
+        (Some(query), Some(other)) => guard(|| into_raw(query.0.inner_join(&other.0))),
+        _ => fail("Null query"),
+    }
+}
+
+/// Merges two queries with an operator (0: AND, 1: OR, 2: NOT), returns null on error
+///
+/// # Safety
+///
+/// Both queries must have been returned by this library and not freed, or be null.
+#[no_mangle]
+pub unsafe extern "C" fn solr_subquery_merge(
+    query: *const FfiQuery,
+    other: *const FfiQuery,
+    operator: c_int,
+) -> *mut FfiQuery {
+    let operator = match operator {
+        0 => Operator::And,
+        1 => Operator::Or,
+        2 => Operator::Not,
+        _ => {
+            return into_raw(Err(SolrSubqueryError::InvalidOperator(
+                operator.to_string(),
+            )))
+        }
+    };
+
+    match (query.as_ref(), other.as_ref()) {
+        (Some(query), Some(other)) => guard(|| into_raw(query.0.merge_queries(&other.0, operator))),
+        _ => fail("Null query"),
+    }
+}
+
+/// Gets the inverse of a query
+///
+/// # Safety
+///
+/// The query must have been returned by this library and not freed, or be null.
+#[no_mangle]
+pub unsafe extern "C" fn solr_subquery_inverse(query: *const FfiQuery) -> *mut FfiQuery {
+    match query.as_ref() {
+        Some(query) => guard(|| into_raw(Ok(query.0.inverse()))),
+        None => fail("Null query"),
+    }
+}
+
+/// Gets the URL of a query, to be freed with `solr_subquery_string_free`, returns null on error
+///
+/// # Safety
+///
+/// The query must have been returned by this library and not freed, or be null.
+#[no_mangle]
+pub unsafe extern "C" fn solr_subquery_url(query: *const FfiQuery) -> *mut c_char {
+    match query.as_ref() {
+        // URLs are percent-encoded, they can't contain NUL bytes
+        Some(query) => CString::new(query.0.url.as_str())
+            .map(CString::into_raw)
+            .unwrap_or(ptr::null_mut()),
+        None => fail("Null query"),
+    }
+}
+
+/// Gets the message of the last error of the calling thread, null if none
+///
+/// The string is owned by the library and valid until the next failing call.
+#[no_mangle]
+pub extern "C" fn solr_subquery_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Frees a query
+///
+/// # Safety
+///
+/// The query must have been returned by this library and not freed yet, or be null.
+#[no_mangle]
+pub unsafe extern "C" fn solr_subquery_free(query: *mut FfiQuery) {
+    if !query.is_null() {
+        drop(Box::from_raw(query));
+    }
+}
+
+/// Frees a string returned by this library
+///
+/// # Safety
+///
+/// The string must have been returned by this library and not freed yet, or be null.
+#[no_mangle]
+pub unsafe extern "C" fn solr_subquery_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+#[cfg(test)]
+mod ffi_tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn should_join_queries_through_ffi() -> Result<(), Box<dyn Error>> {
+        let first_url = CString::new("http://localhost:8983/solr/collection/select?q=a:1")?;
+        let second_url = CString::new("http://localhost:8983/solr/collection/select?q=b:1")?;
+        let invalid_url = CString::new("http://localhost:8983/solr/collection/select")?;
+
+        unsafe {
+            let first = solr_subquery_new(first_url.as_ptr());
+            let second = solr_subquery_new(second_url.as_ptr());
+            let joined = solr_subquery_inner_join(first, second);
+
+            let url = solr_subquery_url(joined);
+            assert_eq!(
+                CStr::from_ptr(url).to_str()?,
+                "http://localhost:8983/solr/collection/select?q=%28a%3A1%29+AND+%28b%3A1%29"
+            );
+
+            assert!(solr_subquery_new(invalid_url.as_ptr()).is_null());
+            assert_eq!(
+                CStr::from_ptr(solr_subquery_last_error()).to_str()?,
+                SolrSubqueryError::MissingQQueryParameter {
+                    url: "http://localhost:8983/solr/collection/select".to_string()
+                }
+                .to_string()
+            );
+
+            solr_subquery_string_free(url);
+            solr_subquery_free(joined);
+            solr_subquery_free(second);
+            solr_subquery_free(first);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_reject_null_queries() -> Result<(), Box<dyn Error>> {
+        let url = CString::new("http://localhost:8983/solr/collection/select?q=a:1")?;
+
+        unsafe {
+            let query = solr_subquery_new(url.as_ptr());
+
+            assert!(solr_subquery_inner_join(query, ptr::null()).is_null());
+            assert!(solr_subquery_merge(ptr::null(), query, 1).is_null());
+            assert!(solr_subquery_inverse(ptr::null()).is_null());
+            assert!(solr_subquery_url(ptr::null()).is_null());
+            assert_eq!(
+                CStr::from_ptr(solr_subquery_last_error()).to_str()?,
+                "Null query"
+            );
+
+            solr_subquery_free(query);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_report_panics_as_errors() -> Result<(), Box<dyn Error>> {
+        let result: *mut FfiQuery = guard(|| panic!("merge failed"));
+
+        assert!(result.is_null());
+        unsafe {
+            assert_eq!(
+                CStr::from_ptr(solr_subquery_last_error()).to_str()?,
+                "Panicked: merge failed"
+            );
+        }
+
+        Ok(())
+    }
+}
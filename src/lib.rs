@@ -1,4 +1,9 @@
+#[cfg(feature = "python")]
 mod bindings;
+#[cfg(feature = "ffi")]
+mod ffi;
+#[cfg(feature = "wasm")]
+mod wasm;
 
 mod errors;
 pub use errors::SolrSubqueryError;
@@ -8,3 +13,48 @@ pub use solr_query::*;
 
 mod query_chain;
 pub use query_chain::*;
+
+mod query_diff;
+pub use query_diff::*;
+
+mod query_builder;
+pub use query_builder::*;
+
+mod replicas;
+pub use replicas::*;
+
+mod query_tree;
+pub use query_tree::*;
+
+#[cfg(feature = "client")]
+mod client;
+#[cfg(feature = "client")]
+pub use client::*;
+
+#[cfg(feature = "client")]
+mod cloud;
+#[cfg(feature = "client")]
+pub use cloud::*;
+
+mod aliases;
+pub use aliases::*;
+
+mod environments;
+pub use environments::*;
+
+mod query_ast;
+pub use query_ast::*;
+
+mod rewrite;
+pub use rewrite::*;
+
+mod cache;
+pub use cache::*;
+
+mod streaming;
+pub use streaming::*;
+
+#[cfg(any(test, feature = "testing"))]
+mod testing;
+#[cfg(any(test, feature = "testing"))]
+pub use testing::*;
@@ -0,0 +1,209 @@
+use crate::query_ast::{text, tokenize, TokenKind};
+use crate::solr_query::Operator;
+
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, Default)]
+/// State of a parenthesized group while looking for implicit operators
+struct Group {
+    after_operand: bool,
+    implicit: bool,
+    other_explicit: bool,
+    /// Whether the last operand is required or prohibited (`+`/`-`)
+    modified: bool,
+}
+
+/// Makes the implicit operators of a q value explicit, `a b` becomes `a AND b` when the
+/// default operator (`q.op`) is `AND`
+///
+/// Returns `None` if a group mixes implicit operators with explicit ones other than the
+/// default, as the precedence the standard query parser gives them can't be written with
+/// explicit operators only. Also returns `None` when the default operator is `OR` and an
+/// operand next to an implicit operator has a `+`/`-` modifier, as an explicit `OR` makes the
+/// required operand on its left optional (`+a b` matches less than `+a OR b`), and for values
+/// starting with local params, whose parser reads the rest of the value (and may set its own
+/// `q.op`) with its own syntax.
+pub fn explicit_operators(q: &str, default: Operator) -> Option<String> {
+    if q.trim_start().starts_with("{!") {
+        return None;
+    }
+
+    let mut insertions = Vec::new();
+    collect_insertions(q, 0, default, &mut insertions)?;
+
+    let mut explicit = q.to_string();
+    for position in insertions.into_iter().rev() {
+        let operator = match explicit[..position].ends_with(char::is_whitespace) {
+            true => format!("{} ", default),
+            false => format!(" {} ", default),
+        };
+        explicit.insert_str(position, &operator);
+    }
+
+    Some(explicit)
+}
+
+/// Collects the byte positions of the operands following another operand without operator
+fn collect_insertions(
+    q: &str,
+    offset: usize,
+    default: Operator,
+    insertions: &mut Vec<usize>,
+) -> Option<()> {
+    let mut groups = vec![Group::default()];
+
+    for token in tokenize(q) {
+        match (token.kind, text(q, token)) {
+            (TokenKind::Open, _) => {
+                begin_operand(
+                    groups.last_mut()?,
+                    offset + token.start,
+                    false,
+                    default,
+                    insertions,
+                )?;
+                groups.push(Group::default());
+            }
+            (TokenKind::Close, _) => {
+                check(groups.pop()?)?;
+                let group = groups.last_mut()?;
+                group.after_operand = true;
+                group.modified = false;
+            }
+            (_, operator @ ("AND" | "&&" | "OR" | "||")) => {
+                let group = groups.last_mut()?;
+                group.after_operand = false;
+                group.other_explicit |=
+                    matches!(operator, "AND" | "&&") != (default == Operator::And);
+            }
+            (_, "NOT" | "!") => {
+                let group = groups.last_mut()?;
+                begin_operand(group, offset + token.start, false, default, insertions)?;
+                group.after_operand = false;
+            }
+            (_, word) => {
+                let group = groups.last_mut()?;
+                let modified = word.starts_with(['+', '-']);
+                begin_operand(group, offset + token.start, modified, default, insertions)?;
+                group.after_operand = true;
+                group.modified = modified;
+
+                if let Some(inner) = field_group(word) {
+                    collect_insertions(
+                        &word[inner.clone()],
+                        offset + token.start + inner.start,
+                        default,
+                        insertions,
+                    )?;
+                }
+            }
+        }
+    }
+
+    match groups.as_slice() {
+        [group] => check(*group),
+        _ => None,
+    }
+}
+
+fn begin_operand(
+    group: &mut Group,
+    position: usize,
+    modified: bool,
+    default: Operator,
+    insertions: &mut Vec<usize>,
+) -> Option<()> {
+    if group.after_operand {
+        if default == Operator::Or && (group.modified || modified) {
+            return None;
+        }
+
+        group.implicit = true;
+        insertions.push(position);
+    }
+
+    Some(())
+}
+
+fn check(group: Group) -> Option<()> {
+    match group.implicit && group.other_explicit {
+        true => None,
+        false => Some(()),
+    }
+}
+
+/// Gets the range of the operands of a field group such as `title:(rust lang)`
+fn field_group(word: &str) -> Option<Range<usize>> {
+    let field = word.trim_start_matches(['+', '-']);
+    let (name, group) = field.split_once(":(")?;
+    let valid_name = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '-' | '*'));
+
+    match valid_name && group.ends_with(')') {
+        true => {
+            let start = word.len() - group.len();
+            Some(start..word.len() - 1)
+        }
+        false => None,
+    }
+}
+
+#[cfg(test)]
+mod explicit_tests {
+    use super::*;
+
+    #[test]
+    fn should_make_implicit_operators_explicit() {
+        let and = |q: &str| explicit_operators(q, Operator::And);
+
+        assert_eq!(and("a:1 b:1").as_deref(), Some("a:1 AND b:1"));
+        assert_eq!(
+            and("title:(rust lang) -year:2020").as_deref(),
+            Some("title:(rust AND lang) AND -year:2020")
+        );
+        assert_eq!(
+            and("(a:1 b:1)(c:1) NOT d:1").as_deref(),
+            Some("(a:1 AND b:1) AND (c:1) AND NOT d:1")
+        );
+        assert_eq!(
+            and("\"rust lang\" year:[2020 TO *]").as_deref(),
+            Some("\"rust lang\" AND year:[2020 TO *]")
+        );
+        assert_eq!(and("a:1 AND b:1").as_deref(), Some("a:1 AND b:1"));
+        assert_eq!(and("a:1 b:1 OR c:1"), None);
+        assert_eq!(
+            explicit_operators("a:1 b:1 OR c:1", Operator::Or).as_deref(),
+            Some("a:1 OR b:1 OR c:1")
+        );
+    }
+
+    #[test]
+    fn should_not_rewrite_modified_operands_with_or() {
+        let or = |q: &str| explicit_operators(q, Operator::Or);
+
+        assert_eq!(or("+a:1 b:1"), None);
+        assert_eq!(or("a:1 -b:1"), None);
+        assert_eq!(or("+a:1 NOT b:1"), None);
+        assert_eq!(or("title:(+rust lang)"), None);
+        assert_eq!(or("+a:1 OR b:1").as_deref(), Some("+a:1 OR b:1"));
+        assert_eq!(or("(+a:1) b:1").as_deref(), Some("(+a:1) OR b:1"));
+        assert_eq!(
+            explicit_operators("+a:1 b:1", Operator::And).as_deref(),
+            Some("+a:1 AND b:1")
+        );
+    }
+
+    #[test]
+    fn should_not_rewrite_local_params_values() {
+        assert_eq!(
+            explicit_operators("{!term f=lang}en fr", Operator::And),
+            None
+        );
+        assert_eq!(
+            explicit_operators(" {!lucene q.op=OR}en fr", Operator::And),
+            None
+        );
+    }
+}
@@ -0,0 +1,434 @@
+mod explicit;
+pub use explicit::*;
+mod syntax;
+pub use syntax::*;
+mod wildcard;
+pub use wildcard::*;
+
+#[derive(Debug, Clone, PartialEq)]
+/// Boolean structure of a q value
+///
+/// Only explicit `AND`, `OR` and `NOT` operators are understood. Groups using
+/// implicit or mixed operators are kept verbatim rather than guessed.
+pub enum QueryAst {
+    /// A single clause such as `title:rust`, `"a phrase"` or `year:[2020 TO *]`
+    Clause(String),
+    /// A group the parser doesn't restructure, kept as written
+    Verbatim(String),
+    And(Vec<QueryAst>),
+    Or(Vec<QueryAst>),
+    Not(Box<QueryAst>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TokenKind {
+    Open,
+    Close,
+    Word,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Token {
+    kind: TokenKind,
+    start: usize,
+    end: usize,
+}
+
+impl QueryAst {
+    /// Parses a q value, falling back to `Verbatim` for what can't be restructured
+    pub fn parse(q: &str) -> QueryAst {
+        parse_tokens(q, &tokenize(q))
+    }
+
+    /// Simplifies double negations, applies De Morgan's laws to negated groups of
+    /// negations, flattens nested groups using the same operator and removes
+    /// groups with a single operand
+    pub fn normalize(self) -> QueryAst {
+        match self {
+            QueryAst::Not(child) => match child.normalize() {
+                QueryAst::Not(grandchild) => *grandchild,
+                QueryAst::And(children) if children.iter().all(is_negation) => {
+                    QueryAst::Or(children.into_iter().map(negate).collect()).normalize()
+                }
+                QueryAst::Or(children) if children.iter().all(is_negation) => {
+                    QueryAst::And(children.into_iter().map(negate).collect()).normalize()
+                }
+                child => QueryAst::Not(Box::new(child)),
+            },
+            QueryAst::And(children) => {
+                let mut children = flatten(children, |c| match c {
+                    QueryAst::And(children) => Ok(children),
+                    c => Err(c),
+                });
+
+                // `*:*` only guards purely negative groups, rendering adds it back when needed
+                if children.iter().any(|c| !is_match_all(c)) {
+                    children.retain(|c| !is_match_all(c));
+                }
+
+                single_or(children, QueryAst::And)
+            }
+            QueryAst::Or(children) => {
+                let children = flatten(children, |c| match c {
+                    QueryAst::Or(children) => Ok(children),
+                    c => Err(c),
+                });
+                single_or(children, QueryAst::Or)
+            }
+            ast => ast,
+        }
+    }
+
+    /// Removes the operands repeated in a group, `a AND b AND a` becomes `a AND b`
+    ///
+    /// Operands are compared structurally, normalizing first also catches
+    /// duplicates written with different groupings.
+    pub fn deduplicate(self) -> QueryAst {
+        let unique = |children: Vec<QueryAst>| {
+            let mut unique: Vec<QueryAst> = Vec::new();
+            for child in children.into_iter().map(QueryAst::deduplicate) {
+                if !unique.contains(&child) {
+                    unique.push(child);
+                }
+            }
+            unique
+        };
+
+        match self {
+            QueryAst::And(children) => single_or(unique(children), QueryAst::And),
+            QueryAst::Or(children) => single_or(unique(children), QueryAst::Or),
+            QueryAst::Not(child) => QueryAst::Not(Box::new(child.deduplicate())),
+            ast => ast,
+        }
+    }
+
+    /// Rewrites the clauses for which `rewrite` returns a replacement
+    ///
+    /// Clauses of verbatim groups are rewritten in place, their operators are kept as written.
+    pub fn map_clauses<F>(self, rewrite: &F) -> QueryAst
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        match self {
+            QueryAst::Clause(clause) => QueryAst::Clause(rewrite(&clause).unwrap_or(clause)),
+            QueryAst::And(children) => QueryAst::And(
+                children
+                    .into_iter()
+                    .map(|c| c.map_clauses(rewrite))
+                    .collect(),
+            ),
+            QueryAst::Or(children) => QueryAst::Or(
+                children
+                    .into_iter()
+                    .map(|c| c.map_clauses(rewrite))
+                    .collect(),
+            ),
+            QueryAst::Not(child) => QueryAst::Not(Box::new(child.map_clauses(rewrite))),
+            QueryAst::Verbatim(group) => QueryAst::Verbatim(map_words(&group, rewrite)),
+        }
+    }
+
+    /// Renders the q value, `nested` operands are grouped and negations guarded by `*:*`
+    fn render(&self, nested: bool) -> String {
+        let rendered = match self {
+            QueryAst::Clause(clause) => return clause.to_string(),
+            QueryAst::Verbatim(group) => group.to_string(),
+            QueryAst::And(children) => {
+                let positives = children
+                    .iter()
+                    .filter(|c| !is_negation(c))
+                    .map(|c| c.render(true))
+                    .collect::<Vec<_>>();
+
+                let mut rendered = match positives.is_empty() {
+                    true => "*:*".to_string(),
+                    false => positives.join(" AND "),
+                };
+                for child in children {
+                    if let QueryAst::Not(negated) = child {
+                        rendered.push_str(&format!(" NOT {}", negated.render(true)));
+                    }
+                }
+                rendered
+            }
+            QueryAst::Or(children) => children
+                .iter()
+                .map(|c| c.render(true))
+                .collect::<Vec<_>>()
+                .join(" OR "),
+            QueryAst::Not(child) if nested => format!("*:* NOT {}", child.render(true)),
+            QueryAst::Not(child) => return format!("NOT {}", child.render(true)),
+        };
+
+        match nested {
+            true => format!("({})", rendered),
+            false => rendered,
+        }
+    }
+}
+
+impl std::fmt::Display for QueryAst {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.render(false))
+    }
+}
+
+fn is_negation(ast: &QueryAst) -> bool {
+    matches!(ast, QueryAst::Not(_))
+}
+
+fn is_match_all(ast: &QueryAst) -> bool {
+    *ast == QueryAst::Clause("*:*".to_string())
+}
+
+fn negate(ast: QueryAst) -> QueryAst {
+    match ast {
+        QueryAst::Not(child) => *child,
+        ast => QueryAst::Not(Box::new(ast)),
+    }
+}
+
+/// Normalizes the children and inlines those using the same operator as their parent
+fn flatten<F>(children: Vec<QueryAst>, same_operator: F) -> Vec<QueryAst>
+where
+    F: Fn(QueryAst) -> Result<Vec<QueryAst>, QueryAst>,
+{
+    let mut flattened = Vec::new();
+
+    for child in children {
+        match same_operator(child.normalize()) {
+            Ok(grandchildren) => flattened.extend(grandchildren),
+            Err(child) => flattened.push(child),
+        }
+    }
+
+    flattened
+}
+
+/// Rewrites the words of a group other than operators, from the last one so positions hold
+fn map_words<F>(group: &str, rewrite: &F) -> String
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut rewritten = group.to_string();
+
+    for token in tokenize(group).into_iter().rev() {
+        let word = text(group, token);
+        if token.kind != TokenKind::Word || matches!(word, "AND" | "&&" | "OR" | "||" | "NOT" | "!")
+        {
+            continue;
+        }
+
+        if let Some(replacement) = rewrite(word) {
+            rewritten.replace_range(token.start..token.end, &replacement);
+        }
+    }
+
+    rewritten
+}
+
+fn single_or(mut children: Vec<QueryAst>, group: fn(Vec<QueryAst>) -> QueryAst) -> QueryAst {
+    match children.len() {
+        1 => children.remove(0),
+        _ => group(children),
+    }
+}
+
+/// Splits a q value into parentheses and words
+///
+/// Phrases, ranges, local params and field groups such as `title:(a b)` are single words.
+fn tokenize(q: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    let (mut in_quotes, mut escaped, mut in_range, mut in_field_group) = (false, false, 0, 0);
+
+    for (i, c) in q.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+
+        match c {
+            '\\' => escaped = true,
+            '"' => in_quotes = !in_quotes,
+            _ if in_quotes => {}
+            '[' | '{' => in_range += 1,
+            ']' | '}' => in_range -= 1,
+            _ if in_range > 0 => {}
+            '(' if start.is_some() || in_field_group > 0 => in_field_group += 1,
+            ')' if in_field_group > 0 => in_field_group -= 1,
+            _ if in_field_group > 0 => {}
+            '(' | ')' => {
+                if let Some(word_start) = start.take() {
+                    tokens.push(word(word_start, i));
+                }
+                let kind = match c {
+                    '(' => TokenKind::Open,
+                    _ => TokenKind::Close,
+                };
+                tokens.push(Token {
+                    kind,
+                    start: i,
+                    end: i + 1,
+                });
+                continue;
+            }
+            c if c.is_whitespace() => {
+                if let Some(word_start) = start.take() {
+                    tokens.push(word(word_start, i));
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        start.get_or_insert(i);
+    }
+
+    if let Some(word_start) = start {
+        tokens.push(word(word_start, q.len()));
+    }
+
+    tokens
+}
+
+fn word(start: usize, end: usize) -> Token {
+    Token {
+        kind: TokenKind::Word,
+        start,
+        end,
+    }
+}
+
+fn parse_tokens(q: &str, tokens: &[Token]) -> QueryAst {
+    parse_sequence(q, tokens).unwrap_or_else(|| {
+        let text = match (tokens.first(), tokens.last()) {
+            (Some(first), Some(last)) => &q[first.start..last.end],
+            _ => "",
+        };
+        QueryAst::Verbatim(text.to_string())
+    })
+}
+
+/// Parses operands joined by a single kind of explicit operator
+fn parse_sequence(q: &str, tokens: &[Token]) -> Option<QueryAst> {
+    let (first, mut i) = parse_operand(q, tokens, 0)?;
+    let mut operands = vec![first];
+    let mut operators = Vec::new();
+
+    while i < tokens.len() {
+        let operator = match text(q, tokens[i]) {
+            "AND" | "&&" => "AND",
+            "OR" | "||" => "OR",
+            "NOT" | "!" => "NOT",
+            _ => return None,
+        };
+
+        let (operand, next) = parse_operand(q, tokens, i + 1)?;
+        operands.push(match operator {
+            "NOT" => QueryAst::Not(Box::new(operand)),
+            _ => operand,
+        });
+        operators.push(operator);
+        i = next;
+    }
+
+    if operators.is_empty() {
+        operands.pop()
+    } else if operators.iter().all(|o| *o == "OR") {
+        Some(QueryAst::Or(operands))
+    } else if operators.iter().all(|o| *o != "OR") {
+        Some(QueryAst::And(operands))
+    } else {
+        None
+    }
+}
+
+fn parse_operand(q: &str, tokens: &[Token], i: usize) -> Option<(QueryAst, usize)> {
+    let token = tokens.get(i)?;
+
+    match (token.kind, text(q, *token)) {
+        (TokenKind::Open, _) => {
+            let close = closing_token(tokens, i)?;
+            Some((parse_tokens(q, &tokens[i + 1..close]), close + 1))
+        }
+        (TokenKind::Close, _) => None,
+        (_, "NOT") | (_, "!") => {
+            let (operand, next) = parse_operand(q, tokens, i + 1)?;
+            Some((QueryAst::Not(Box::new(operand)), next))
+        }
+        (_, "AND") | (_, "&&") | (_, "OR") | (_, "||") => None,
+        (_, clause) => Some((QueryAst::Clause(clause.to_string()), i + 1)),
+    }
+}
+
+fn closing_token(tokens: &[Token], open: usize) -> Option<usize> {
+    let mut depth = 0;
+
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        match token.kind {
+            TokenKind::Open => depth += 1,
+            TokenKind::Close => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            TokenKind::Word => {}
+        }
+    }
+
+    None
+}
+
+fn text(q: &str, token: Token) -> &str {
+    &q[token.start..token.end]
+}
+
+#[cfg(test)]
+mod query_ast_tests {
+    use super::*;
+
+    fn normalize(q: &str) -> String {
+        QueryAst::parse(q).normalize().to_string()
+    }
+
+    #[test]
+    fn should_normalize_boolean_structure() {
+        assert_eq!(normalize("NOT (NOT (a:1))"), "a:1");
+        assert_eq!(
+            normalize("((a:1) AND (b:1)) AND ((c:1))"),
+            "a:1 AND b:1 AND c:1"
+        );
+        assert_eq!(normalize("*:* NOT ((a:1) OR (b:1))"), "NOT (a:1 OR b:1)");
+        assert_eq!(normalize("*:* NOT (*:* NOT (a:1))"), "a:1");
+        assert_eq!(normalize("NOT ((NOT (a:1)) AND (NOT (b:1)))"), "a:1 OR b:1");
+        assert_eq!(normalize("(a:1) NOT (b:1)"), "a:1 NOT b:1");
+        assert_eq!(normalize("(a:1) OR (NOT (b:1))"), "a:1 OR (*:* NOT b:1)");
+    }
+
+    #[test]
+    fn should_keep_ambiguous_groups_verbatim() {
+        assert_eq!(
+            normalize("((a:1 b:1)) OR (title:(rust lang))"),
+            "(a:1 b:1) OR title:(rust lang)"
+        );
+        assert_eq!(
+            normalize("(a:1) AND (b:1) OR (c:\"x (y\")"),
+            "(a:1) AND (b:1) OR (c:\"x (y\")"
+        );
+        assert_eq!(normalize("year:[2020 TO *]"), "year:[2020 TO *]");
+    }
+
+    #[test]
+    fn should_deduplicate_clauses() {
+        let deduplicate = |q: &str| QueryAst::parse(q).normalize().deduplicate().to_string();
+
+        assert_eq!(deduplicate("((a:1) AND (b:1)) AND (a:1)"), "a:1 AND b:1");
+        assert_eq!(
+            deduplicate("((a:1) OR (b:1)) AND ((b:1) OR (a:1)) AND ((a:1) OR (b:1))"),
+            "(a:1 OR b:1) AND (b:1 OR a:1)"
+        );
+        assert_eq!(deduplicate("(a:1) OR ((a:1))"), "a:1");
+    }
+}
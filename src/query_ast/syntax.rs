@@ -0,0 +1,234 @@
+use crate::errors::SolrSubqueryError;
+use crate::query_ast::{text, tokenize, QueryAst, TokenKind};
+use crate::solr_query::SolrQuery;
+
+/// Characters of the q value shown on each side of the error in rendered snippets
+const SNIPPET_CONTEXT: usize = 40;
+
+#[derive(Debug, Clone, PartialEq)]
+/// Syntax error of a q value, located by byte offsets
+pub struct SyntaxError {
+    pub message: String,
+    /// Byte offset of the start of the faulty part
+    pub offset: usize,
+    /// Byte length of the faulty part
+    pub len: usize,
+    /// The q value the error was found in
+    pub q: String,
+}
+
+impl SyntaxError {
+    fn new(message: &str, q: &str, offset: usize, len: usize) -> SyntaxError {
+        SyntaxError {
+            message: message.to_string(),
+            offset,
+            len,
+            q: q.to_string(),
+        }
+    }
+
+    /// Renders the error with the surrounding part of the q value and a caret under the faulty part
+    ///
+    /// ```text
+    /// unclosed parenthesis at byte 10
+    ///   | (a:1) AND (b:1 OR c:1
+    ///   |           ^
+    /// ```
+    pub fn render(&self) -> String {
+        let start = self.q[..self.offset]
+            .char_indices()
+            .rev()
+            .nth(SNIPPET_CONTEXT - 1)
+            .map_or(0, |(i, _)| i);
+        let before = self.q[start..self.offset].chars().count();
+        let end = self.q[self.offset..]
+            .char_indices()
+            .nth(self.q[self.offset..self.offset + self.len].chars().count() + SNIPPET_CONTEXT)
+            .map_or(self.q.len(), |(i, _)| self.offset + i);
+
+        let (ellipsis, trailing) = (
+            if start > 0 { "..." } else { "" },
+            if end < self.q.len() { "..." } else { "" },
+        );
+        let carets = self.q[self.offset..self.offset + self.len]
+            .chars()
+            .count()
+            .max(1);
+
+        format!(
+            "{}\n  | {}{}{}\n  | {}{}",
+            self,
+            ellipsis,
+            &self.q[start..end],
+            trailing,
+            " ".repeat(ellipsis.len() + before),
+            "^".repeat(carets)
+        )
+    }
+}
+
+impl std::fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} at byte {}", self.message, self.offset)
+    }
+}
+
+impl QueryAst {
+    /// Parses a q value, failing on unbalanced delimiters and operators missing an operand
+    pub fn parse_strict(q: &str) -> Result<QueryAst, SyntaxError> {
+        check_delimiters(q)?;
+        check_operators(q)?;
+        Ok(QueryAst::parse(q))
+    }
+}
+
+impl SolrQuery {
+    /// Checks the syntax of the q value of the query
+    pub fn check_syntax(&self) -> Result<(), SolrSubqueryError> {
+        QueryAst::parse_strict(&self.q_param()?)
+            .map(|_| ())
+            .map_err(SolrSubqueryError::InvalidSyntax)
+    }
+}
+
+/// Checks that quotes, parentheses and range brackets are closed
+fn check_delimiters(q: &str) -> Result<(), SyntaxError> {
+    let (mut parentheses, mut brackets) = (Vec::new(), Vec::new());
+    let mut quote = None;
+    let mut escaped = false;
+
+    for (i, c) in q.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+
+        match c {
+            '\\' => escaped = true,
+            '"' if quote.is_some() => quote = None,
+            '"' => quote = Some(i),
+            _ if quote.is_some() => {}
+            '[' | '{' => brackets.push(i),
+            ']' | '}' if brackets.pop().is_none() => {
+                return Err(SyntaxError::new("unexpected closing bracket", q, i, 1))
+            }
+            _ if !brackets.is_empty() => {}
+            '(' => parentheses.push(i),
+            ')' if parentheses.pop().is_none() => {
+                return Err(SyntaxError::new("unexpected closing parenthesis", q, i, 1))
+            }
+            _ => {}
+        }
+    }
+
+    if escaped {
+        return Err(SyntaxError::new("dangling escape", q, q.len() - 1, 1));
+    }
+    if let Some(i) = quote {
+        return Err(SyntaxError::new("unclosed quote", q, i, 1));
+    }
+    if let Some(i) = brackets.pop() {
+        return Err(SyntaxError::new("unclosed bracket", q, i, 1));
+    }
+    if let Some(i) = parentheses.pop() {
+        return Err(SyntaxError::new("unclosed parenthesis", q, i, 1));
+    }
+
+    Ok(())
+}
+
+/// Checks that every boolean operator has its operands
+fn check_operators(q: &str) -> Result<(), SyntaxError> {
+    let tokens = tokenize(q);
+    let is_binary = |i: usize| {
+        tokens[i].kind == TokenKind::Word
+            && matches!(text(q, tokens[i]), "AND" | "&&" | "OR" | "||")
+    };
+    let is_operator = |i: usize| {
+        is_binary(i)
+            || (tokens[i].kind == TokenKind::Word && matches!(text(q, tokens[i]), "NOT" | "!"))
+    };
+
+    for (i, token) in tokens.iter().enumerate() {
+        if !is_operator(i) {
+            continue;
+        }
+
+        let missing_left =
+            is_binary(i) && (i == 0 || tokens[i - 1].kind == TokenKind::Open || is_operator(i - 1));
+        let missing_right =
+            i + 1 == tokens.len() || tokens[i + 1].kind == TokenKind::Close || is_binary(i + 1);
+
+        if missing_left || missing_right {
+            let message = format!("missing operand of `{}`", text(q, *token));
+            return Err(SyntaxError::new(
+                &message,
+                q,
+                token.start,
+                token.end - token.start,
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod syntax_tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn should_locate_syntax_errors() {
+        let offset = |q: &str| QueryAst::parse_strict(q).unwrap_err().offset;
+
+        assert_eq!(offset("(a:1) AND (b:1 OR c:1"), 10);
+        assert_eq!(offset("a:1) AND b:1"), 3);
+        assert_eq!(offset("title:\"rust lang AND b:1"), 6);
+        assert_eq!(offset("year:[2020 TO * AND b:1"), 5);
+        assert_eq!(offset("a:1 AND AND b:1"), 4);
+        assert_eq!(offset("(a:1 OR) AND b:1"), 5);
+        assert_eq!(offset("a:1 AND NOT"), 8);
+
+        assert!(
+            QueryAst::parse_strict("NOT (a:1) AND title:(\"x (y\" z) AND year:[* TO 2020}").is_ok()
+        );
+    }
+
+    #[test]
+    fn should_render_caret_under_error() -> Result<(), Box<dyn Error>> {
+        let error = QueryAst::parse_strict("(a:1) AND (b:1 OR c:1").unwrap_err();
+        assert_eq!(
+            error.render(),
+            "unclosed parenthesis at byte 10\n  | (a:1) AND (b:1 OR c:1\n  |           ^"
+        );
+
+        let long_q = format!("{} AND b:1 OR", vec!["a:1"; 50].join(" AND "));
+        let error = SolrQuery::new(
+            format!(
+                "http://localhost:8983/solr/collection/select?q={}",
+                urlencoding::encode(&long_q)
+            )
+            .as_str(),
+        )?
+        .check_syntax()
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Invalid query syntax: missing operand of `OR` at byte {}",
+                long_q.len() - 2
+            )
+        );
+        match error {
+            SolrSubqueryError::InvalidSyntax(e) => assert_eq!(
+                e.render(),
+                "missing operand of `OR` at byte 404\n  | ...AND a:1 AND a:1 AND a:1 AND a:1 AND b:1 OR\n  |                                            ^^"
+            ),
+            e => panic!("unexpected error {}", e),
+        }
+
+        Ok(())
+    }
+}
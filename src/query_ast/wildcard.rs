@@ -0,0 +1,96 @@
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Type of a field whose prefix wildcards can be rewritten as ranges
+pub enum WildcardField {
+    /// Integers always written with the given number of digits, such as years
+    Integer { digits: usize },
+    /// Dates, prefixes must end on a component such as `2021`, `2021-03` or `2021-03-05T10`
+    Date,
+}
+
+/// Units of the date components, in the order they are written
+const DATE_UNITS: [(usize, &str, &str); 6] = [
+    (4, "-01-01T00:00:00Z", "YEAR"),
+    (7, "-01T00:00:00Z", "MONTH"),
+    (10, "T00:00:00Z", "DAY"),
+    (13, ":00:00Z", "HOUR"),
+    (16, ":00Z", "MINUTE"),
+    (19, "Z", "SECOND"),
+];
+
+/// Rewrites a `field:prefix*` clause as the equivalent range, if the field is known
+pub(crate) fn wildcard_to_range(clause: &str, fields: &[(&str, WildcardField)]) -> Option<String> {
+    let (modifier, clause) = match clause.strip_prefix(['+', '-']) {
+        Some(rest) => (&clause[..1], rest),
+        None => ("", clause),
+    };
+    let (field, value) = clause.split_once(':')?;
+    let prefix = value.strip_suffix('*')?;
+    let (_, kind) = fields.iter().find(|(name, _)| *name == field)?;
+
+    let range = match kind {
+        WildcardField::Integer { digits } => {
+            if prefix.is_empty()
+                || prefix.len() > *digits
+                || !prefix.bytes().all(|b| b.is_ascii_digit())
+            {
+                return None;
+            }
+
+            let padding = digits - prefix.len();
+            format!(
+                "[{}{} TO {}{}]",
+                prefix,
+                "0".repeat(padding),
+                prefix,
+                "9".repeat(padding)
+            )
+        }
+        WildcardField::Date => {
+            let (_, suffix, unit) = DATE_UNITS.iter().find(|(len, _, _)| *len == prefix.len())?;
+            if !is_date_prefix(prefix) {
+                return None;
+            }
+
+            let start = format!("{}{}", prefix, suffix);
+            format!("[{} TO {}+1{}}}", start, start, unit)
+        }
+    };
+
+    Some(format!("{}{}:{}", modifier, field, range))
+}
+
+/// Checks that a prefix follows `YYYY-MM-DDTHH:MM:SS`
+fn is_date_prefix(prefix: &str) -> bool {
+    prefix
+        .bytes()
+        .zip("0000-00-00T00:00:00".bytes())
+        .all(|(c, pattern)| match pattern {
+            b'0' => c.is_ascii_digit(),
+            _ => c == pattern,
+        })
+}
+
+#[cfg(test)]
+mod wildcard_tests {
+    use super::*;
+
+    #[test]
+    fn should_rewrite_wildcards_as_ranges() {
+        let fields = [
+            ("year", WildcardField::Integer { digits: 4 }),
+            ("date", WildcardField::Date),
+        ];
+
+        assert_eq!(
+            wildcard_to_range("year:19*", &fields),
+            Some("year:[1900 TO 1999]".to_string())
+        );
+        assert_eq!(
+            wildcard_to_range("-date:2021-03*", &fields),
+            Some("-date:[2021-03-01T00:00:00Z TO 2021-03-01T00:00:00Z+1MONTH}".to_string())
+        );
+        assert_eq!(wildcard_to_range("date:202*", &fields), None);
+        assert_eq!(wildcard_to_range("year:19a*", &fields), None);
+        assert_eq!(wildcard_to_range("title:19*", &fields), None);
+    }
+}
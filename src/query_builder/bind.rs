@@ -0,0 +1,93 @@
+use crate::errors::SolrSubqueryError;
+use crate::query_builder::escape_query_chars;
+use crate::solr_query::{QueryParam, SolrQuery, SubQuery};
+
+/// Words the standard query parser reads as operators even when escaped character by character
+const OPERATOR_WORDS: [&str; 3] = ["AND", "OR", "NOT"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// How a bound value is written in the q value
+pub enum BindMode {
+    /// A single term, special characters and whitespace escaped
+    Term,
+    /// A quoted phrase, quotes and backslashes escaped
+    Phrase,
+}
+
+impl SolrQuery {
+    /// Restricts the query to the documents whose field has the given value
+    ///
+    /// The value is data, not query syntax: it can't add clauses or operators,
+    /// so it can safely come from user input.
+    pub fn bind(&self, field: &str, value: &str) -> Result<SolrQuery, SolrSubqueryError> {
+        self.bind_with(field, value, BindMode::Term)
+    }
+
+    /// Restricts the query to the documents whose field has the given value, see [`SolrQuery::bind`]
+    pub fn bind_with(
+        &self,
+        field: &str,
+        value: &str,
+        mode: BindMode,
+    ) -> Result<SolrQuery, SolrSubqueryError> {
+        let mut clause_url = self.url.clone();
+        clause_url.set_param(("q", &bound_clause(field, value, mode)?));
+
+        self.inner_join(&SolrQuery::new(clause_url)?)
+    }
+}
+
+/// Writes the `field:value` clause of a bound value
+fn bound_clause(field: &str, value: &str, mode: BindMode) -> Result<String, SolrSubqueryError> {
+    let valid_field = field
+        .chars()
+        .all(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '-'))
+        && field.starts_with(|c: char| c.is_alphabetic() || c == '_');
+    if !valid_field {
+        return Err(SolrSubqueryError::InvalidFieldName(field.to_string()));
+    }
+
+    let value = match mode {
+        BindMode::Term if !value.is_empty() && !OPERATOR_WORDS.contains(&value) => {
+            escape_query_chars(value)
+        }
+        _ => format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"")),
+    };
+
+    Ok(format!("{}:{}", field, value))
+}
+
+#[cfg(test)]
+mod bind_tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn should_bind_values_as_data() -> Result<(), Box<dyn Error>> {
+        assert_eq!(
+            bound_clause("author", "x OR *:*", BindMode::Term)?,
+            "author:x\\ OR\\ \\*\\:\\*"
+        );
+        assert_eq!(
+            bound_clause("author", "OR", BindMode::Term)?,
+            "author:\"OR\""
+        );
+        assert_eq!(
+            bound_clause("title", "say \"hi\") OR (a:1", BindMode::Phrase)?,
+            "title:\"say \\\"hi\\\") OR (a:1\""
+        );
+        assert!(matches!(
+            bound_clause("a:1 OR b", "x", BindMode::Term),
+            Err(SolrSubqueryError::InvalidFieldName(_))
+        ));
+
+        let query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=lang:en")?
+            .bind("author", "O'Brien) OR (*:*")?;
+        assert_eq!(
+            query.url.params("q"),
+            vec!["(lang:en) AND (author:O'Brien\\)\\ OR\\ \\(\\*\\:\\*)"]
+        );
+
+        Ok(())
+    }
+}
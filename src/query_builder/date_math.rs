@@ -0,0 +1,126 @@
+use crate::errors::SolrSubqueryError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Unit of a date math offset
+pub enum DateUnit {
+    Years,
+    Months,
+    Days,
+    Hours,
+    Minutes,
+    Seconds,
+}
+
+impl DateUnit {
+    fn name(&self) -> &'static str {
+        match self {
+            DateUnit::Years => "YEARS",
+            DateUnit::Months => "MONTHS",
+            DateUnit::Days => "DAYS",
+            DateUnit::Hours => "HOURS",
+            DateUnit::Minutes => "MINUTES",
+            DateUnit::Seconds => "SECONDS",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A bound of a date range
+pub enum DateBound {
+    /// `*`
+    Unbounded,
+    /// An absolute date such as `2021-03-05T00:00:00Z`
+    Date(String),
+    /// `NOW` shifted by an offset, e.g. `NOW-30DAYS`
+    Now(i64, DateUnit),
+}
+
+impl DateBound {
+    /// The current time
+    pub fn now() -> DateBound {
+        DateBound::Now(0, DateUnit::Days)
+    }
+
+    /// The current time minus an offset
+    pub fn ago(amount: u32, unit: DateUnit) -> DateBound {
+        DateBound::Now(-i64::from(amount), unit)
+    }
+
+    /// The start of a day
+    pub fn day(year: u16, month: u8, day: u8) -> Result<DateBound, SolrSubqueryError> {
+        let days_in_month = match month {
+            4 | 6 | 9 | 11 => 30,
+            2 if year.is_multiple_of(4)
+                && (!year.is_multiple_of(100) || year.is_multiple_of(400)) =>
+            {
+                29
+            }
+            2 => 28,
+            1..=12 => 31,
+            _ => 0,
+        };
+        if year > 9999 || day == 0 || day > days_in_month {
+            return Err(SolrSubqueryError::InvalidDate(format!(
+                "{:04}-{:02}-{:02}",
+                year, month, day
+            )));
+        }
+
+        Ok(DateBound::Date(format!(
+            "{:04}-{:02}-{:02}T00:00:00Z",
+            year, month, day
+        )))
+    }
+}
+
+impl std::fmt::Display for DateBound {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DateBound::Unbounded => write!(f, "*"),
+            DateBound::Date(date) => write!(f, "{}", date),
+            DateBound::Now(0, _) => write!(f, "NOW"),
+            DateBound::Now(amount, unit) => write!(f, "NOW{:+}{}", amount, unit.name()),
+        }
+    }
+}
+
+/// Clause matching the dates of a field between two inclusive bounds
+pub fn date_range(field: &str, from: &DateBound, to: &DateBound) -> String {
+    format!("{}:[{} TO {}]", field, from, to)
+}
+
+/// Clause matching the dates of a field within the last `days` days
+pub fn last_n_days(field: &str, days: u32) -> String {
+    date_range(
+        field,
+        &DateBound::ago(days, DateUnit::Days),
+        &DateBound::now(),
+    )
+}
+
+#[cfg(test)]
+mod date_math_tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn should_build_date_ranges() -> Result<(), Box<dyn Error>> {
+        assert_eq!(
+            last_n_days("indexed_at", 30),
+            "indexed_at:[NOW-30DAYS TO NOW]"
+        );
+        assert_eq!(
+            date_range(
+                "published",
+                &DateBound::day(2020, 2, 29)?,
+                &DateBound::Unbounded
+            ),
+            "published:[2020-02-29T00:00:00Z TO *]"
+        );
+        assert_eq!(DateBound::Now(2, DateUnit::Hours).to_string(), "NOW+2HOURS");
+        assert!(DateBound::day(2021, 2, 29).is_err());
+        assert!(DateBound::day(2021, 13, 1).is_err());
+
+        Ok(())
+    }
+}
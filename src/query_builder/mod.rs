@@ -0,0 +1,133 @@
+mod bind;
+pub use bind::*;
+mod date_math;
+pub use date_math::*;
+mod spatial;
+pub use spatial::*;
+mod template;
+pub use template::*;
+
+use crate::errors::SolrSubqueryError;
+use crate::solr_query::SolrQuery;
+
+use url::Url;
+
+#[derive(Debug, Clone, PartialEq)]
+/// Builds a Solr query from a base endpoint and parameters
+pub struct SolrQueryBuilder {
+    url: Result<Url, SolrSubqueryError>,
+}
+
+impl SolrQueryBuilder {
+    /// Creates a new builder from a base endpoint such as `http://host:8983/solr/core/select`
+    pub fn new(base: &str) -> SolrQueryBuilder {
+        SolrQueryBuilder {
+            url: Url::parse(base).map_err(SolrSubqueryError::from),
+        }
+    }
+
+    /// Appends a parameter
+    pub fn param<V: ToString>(mut self, name: &str, value: V) -> SolrQueryBuilder {
+        if let Ok(url) = &mut self.url {
+            url.query_pairs_mut().append_pair(name, &value.to_string());
+        }
+        self
+    }
+
+    /// Builds the query
+    pub fn build(self) -> Result<SolrQuery, SolrSubqueryError> {
+        SolrQuery::new(self.url?)
+    }
+}
+
+#[doc(hidden)]
+pub const fn is_http_url(url: &str) -> bool {
+    starts_with(url.as_bytes(), b"http://") || starts_with(url.as_bytes(), b"https://")
+}
+
+const fn starts_with(bytes: &[u8], prefix: &[u8]) -> bool {
+    if bytes.len() < prefix.len() {
+        return false;
+    }
+
+    let mut i = 0;
+    while i < prefix.len() {
+        if bytes[i] != prefix[i] {
+            return false;
+        }
+        i += 1;
+    }
+
+    true
+}
+
+/// Builds a [`SolrQuery`] from a base endpoint literal and parameters
+///
+/// Fails to compile if the endpoint is not an http(s) URL or if `q` is missing.
+///
+/// ```ignore
+/// let query = solr_query!(
+///     "http://localhost:8983/solr/core/select",
+///     q = "title:rust",
+///     rows = 20,
+///     fq = ["lang:en", "year:[2020 TO *]"],
+/// )?;
+/// ```
+#[macro_export]
+macro_rules! solr_query {
+    ($base:literal, q = $q:expr $(, $name:tt = $value:tt)* $(,)?) => {{
+        const _: () = assert!(
+            $crate::is_http_url($base),
+            "solr_query! expects an http(s) endpoint literal"
+        );
+
+        let builder = $crate::SolrQueryBuilder::new($base).param("q", $q);
+        $(let builder = $crate::solr_query!(@param builder, $crate::solr_query!(@name $name), $value);)*
+        builder.build()
+    }};
+    (@name $name:ident) => {
+        stringify!($name)
+    };
+    (@name $name:literal) => {
+        $name
+    };
+    (@param $builder:ident, $name:expr, [$($value:expr),* $(,)?]) => {
+        $builder$(.param($name, $value))*
+    };
+    (@param $builder:ident, $name:expr, $value:expr) => {
+        $builder.param($name, $value)
+    };
+}
+
+#[cfg(test)]
+mod query_builder_tests {
+    use super::*;
+    use std::error::Error;
+    use urlencoding::decode;
+
+    #[test]
+    fn should_build_query_with_macro() -> Result<(), Box<dyn Error>> {
+        let query = crate::solr_query!(
+            "http://localhost:8983/solr/collection/select",
+            q = "title:rust",
+            rows = 20,
+            "q.op" = "AND",
+            fq = ["lang:en", "year:[2020 TO *]"],
+        )?;
+
+        let url_string = query.url.to_string();
+        let result = decode(&url_string)?;
+        let expected = "http://localhost:8983/solr/collection/select?q=title:rust&rows=20&q.op=AND&fq=lang:en&fq=year:[2020+TO+*]";
+
+        assert_eq!(result, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn should_not_build_invalid_query() {
+        let query = SolrQueryBuilder::new("not an url")
+            .param("q", "*:*")
+            .build();
+        assert!(matches!(query, Err(SolrSubqueryError::InvalidUrl { .. })));
+    }
+}
@@ -0,0 +1,85 @@
+use crate::errors::SolrSubqueryError;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A point of a location field
+pub struct GeoPoint {
+    lat: f64,
+    lon: f64,
+}
+
+impl GeoPoint {
+    /// Creates a point, latitude and longitude being in degrees
+    pub fn new(lat: f64, lon: f64) -> Result<GeoPoint, SolrSubqueryError> {
+        if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+            return Err(SolrSubqueryError::InvalidCoordinates(lat, lon));
+        }
+
+        Ok(GeoPoint { lat, lon })
+    }
+}
+
+impl std::fmt::Display for GeoPoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{},{}", self.lat, self.lon)
+    }
+}
+
+/// Clause matching the documents whose location is within `km` kilometers of a point
+///
+/// The `geofilt` parser is embedded with `_query_` so the clause can be merged
+/// with other subqueries.
+pub fn within_radius(field: &str, center: GeoPoint, km: f64) -> String {
+    format!(
+        "_query_:\"{{!geofilt sfield={} pt={} d={}}}\"",
+        field, center, km
+    )
+}
+
+/// Clause matching the documents whose location is within the bounding box of a circle
+///
+/// Cheaper than [`within_radius`] but also matches the corners of the box.
+pub fn within_bbox(field: &str, center: GeoPoint, km: f64) -> String {
+    format!(
+        "_query_:\"{{!bbox sfield={} pt={} d={}}}\"",
+        field, center, km
+    )
+}
+
+/// Clause matching the documents whose location is within a rectangle
+pub fn within_rectangle(field: &str, lower_left: GeoPoint, upper_right: GeoPoint) -> String {
+    format!("{}:[{} TO {}]", field, lower_left, upper_right)
+}
+
+#[cfg(test)]
+mod spatial_tests {
+    use super::*;
+    use crate::solr_query::{SolrQuery, SubQuery};
+    use crate::SolrQueryBuilder;
+    use std::error::Error;
+    use urlencoding::decode;
+
+    #[test]
+    fn should_merge_spatial_clauses() -> Result<(), Box<dyn Error>> {
+        let paris = GeoPoint::new(48.85, 2.35)?;
+        let first_query = SolrQueryBuilder::new("http://localhost:8983/solr/collection/select")
+            .param("q", within_radius("location", paris, 10.0))
+            .build()?;
+        let second_query =
+            SolrQuery::new("http://localhost:8983/solr/collection/select?q=lang:fr")?;
+
+        let url_string = first_query.inner_join(&second_query)?.url.to_string();
+        let result = decode(&url_string)?;
+
+        assert_eq!(
+            result,
+            "http://localhost:8983/solr/collection/select?q=(_query_:\"{!geofilt+sfield=location+pt=48.85,2.35+d=10}\")+AND+(lang:fr)"
+        );
+        assert_eq!(
+            within_rectangle("location", GeoPoint::new(45.0, -1.5)?, paris),
+            "location:[45,-1.5 TO 48.85,2.35]"
+        );
+        assert!(GeoPoint::new(91.0, 0.0).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,253 @@
+use crate::errors::SolrSubqueryError;
+use crate::solr_query::SolrQuery;
+
+use std::collections::BTreeSet;
+use url::Url;
+
+/// Characters with a meaning in the standard query parser syntax
+const SPECIAL_CHARS: &str = "+-&|!(){}[]^\"~*?:\\/";
+
+/// Query parsers reading the value following their local params as a literal term
+const LITERAL_PARSERS: [&str; 4] = ["term", "raw", "field", "prefix"];
+
+#[derive(Debug, Clone, PartialEq)]
+/// A query URL whose parameter values contain named placeholders such as `{author}`
+///
+/// Placeholders are identifiers between braces, local params (`{!edismax}`) and
+/// ranges (`{* TO 2020}`) are left untouched.
+///
+/// Values are escaped according to where their placeholder is: quoted when it's the value of a
+/// local param (`{!term f={field}}`), inserted as is after the local params of a parser taking
+/// a literal term (`{!term f=lang}{lang}`) and escaped for the standard query parser otherwise.
+pub struct QueryTemplate {
+    url: Url,
+}
+
+impl QueryTemplate {
+    pub fn new(url: &str) -> Result<QueryTemplate, SolrSubqueryError> {
+        Ok(QueryTemplate {
+            url: Url::parse(url)?,
+        })
+    }
+
+    /// Gets the names of the placeholders
+    pub fn placeholders(&self) -> BTreeSet<String> {
+        self.url
+            .query_pairs()
+            .flat_map(|(_, value)| {
+                placeholder_spans(&value)
+                    .into_iter()
+                    .map(|(start, end)| value[start + 1..end - 1].to_string())
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Replaces every placeholder by its escaped value
+    pub fn bind(&self, values: &[(&str, &str)]) -> Result<SolrQuery, SolrSubqueryError> {
+        let mut parameters = Vec::new();
+
+        for (key, value) in self.url.query_pairs() {
+            let mut bound = String::new();
+            let mut last = 0;
+            let local_params = local_params(&value);
+
+            for (start, end) in placeholder_spans(&value) {
+                let name = &value[start + 1..end - 1];
+                let (_, replacement) = values
+                    .iter()
+                    .find(|(n, _)| *n == name)
+                    .ok_or_else(|| SolrSubqueryError::UnboundPlaceholder(name.to_string()))?;
+
+                let escaped = match local_params {
+                    Some((params_end, _)) if start < params_end => {
+                        local_param_value(&value, (start, end), replacement)?
+                    }
+                    Some((_, parser)) if LITERAL_PARSERS.contains(&parser) => {
+                        replacement.to_string()
+                    }
+                    _ => escape_query_chars(replacement),
+                };
+
+                bound.push_str(&value[last..start]);
+                bound.push_str(&escaped);
+                last = end;
+            }
+            bound.push_str(&value[last..]);
+
+            parameters.push((key.to_string(), bound));
+        }
+
+        let mut url = self.url.clone();
+        url.query_pairs_mut().clear().extend_pairs(parameters);
+        SolrQuery::new(url)
+    }
+}
+
+/// Escapes the characters of a value so the standard query parser reads it as a single term
+pub fn escape_query_chars(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if SPECIAL_CHARS.contains(c) || c.is_whitespace() {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Finds the local params starting a value, as the byte position following them and the name of
+/// their query parser
+fn local_params(value: &str) -> Option<(usize, &str)> {
+    let params = value.strip_prefix("{!")?;
+    let (mut depth, mut quote) = (1, None);
+    let mut end = None;
+
+    for (i, c) in params.char_indices() {
+        match (c, quote) {
+            ('\'' | '"', None) => quote = Some(c),
+            (c, Some(open)) if c == open => quote = None,
+            (_, Some(_)) => {}
+            ('{', None) => depth += 1,
+            ('}', None) => {
+                depth -= 1;
+                if depth == 0 {
+                    end = Some(i);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let end = end?;
+    let parser = match params[..end].split_whitespace().next() {
+        Some(word) if !word.contains('=') => word,
+        _ => params[..end]
+            .split_whitespace()
+            .find_map(|word| word.strip_prefix("type="))
+            .unwrap_or("lucene"),
+    };
+
+    Some((end + 3, parser))
+}
+
+/// Quotes the value bound to a placeholder standing for the whole value of a local param
+fn local_param_value(
+    value: &str,
+    (start, end): (usize, usize),
+    replacement: &str,
+) -> Result<String, SolrSubqueryError> {
+    let whole_value = value[..start].ends_with('=')
+        && value[end..].starts_with(|c: char| c.is_whitespace() || c == '}');
+
+    match whole_value {
+        true => Ok(format!(
+            "'{}'",
+            replacement.replace('\\', "\\\\").replace('\'', "\\'")
+        )),
+        false => Err(SolrSubqueryError::MisplacedPlaceholder(
+            value[start + 1..end - 1].to_string(),
+        )),
+    }
+}
+
+/// Finds the `{name}` placeholders of a value, as byte ranges including the braces
+fn placeholder_spans(value: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut rest = value;
+    let mut offset = 0;
+
+    while let Some(start) = rest.find('{') {
+        let candidate = &rest[start + 1..];
+        let name_len = candidate
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(candidate.len());
+        let is_placeholder = name_len > 0
+            && !candidate.starts_with(|c: char| c.is_ascii_digit())
+            && candidate[name_len..].starts_with('}');
+
+        if is_placeholder {
+            spans.push((offset + start, offset + start + name_len + 2));
+        }
+
+        let skip = start + 1;
+        rest = &rest[skip..];
+        offset += skip;
+    }
+
+    spans
+}
+
+#[cfg(test)]
+mod template_tests {
+    use super::*;
+    use crate::solr_query::QueryParam;
+    use std::error::Error;
+    use urlencoding::decode;
+
+    #[test]
+    fn should_bind_escaped_values() -> Result<(), Box<dyn Error>> {
+        let template = QueryTemplate::new(
+            "http://localhost:8983/solr/collection/select?q=author:{author} AND year:{year}&fq={!term f=lang}{lang}&fq=date:{* TO NOW}",
+        )?;
+
+        assert_eq!(
+            template.placeholders().into_iter().collect::<Vec<_>>(),
+            vec!["author", "lang", "year"]
+        );
+
+        let query = template.bind(&[
+            ("author", "O'Brien (Jr.)"),
+            ("year", "2020"),
+            ("lang", "en"),
+        ])?;
+        let url_string = query.url.to_string();
+        let result = decode(&url_string)?;
+
+        assert_eq!(
+            result,
+            "http://localhost:8983/solr/collection/select?q=author:O'Brien\\+\\(Jr.\\)+AND+year:2020&fq={!term+f=lang}en&fq=date:{*+TO+NOW}"
+        );
+
+        assert!(matches!(
+            template.bind(&[("author", "a")]),
+            Err(SolrSubqueryError::UnboundPlaceholder(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_escape_values_according_to_local_params() -> Result<(), Box<dyn Error>> {
+        let template = QueryTemplate::new(
+            "http://localhost:8983/solr/collection/select?q={!lucene}title:{title}&fq={!term f={field}}{term}&fq={!type=raw f=id}{id}",
+        )?;
+
+        let query = template.bind(&[
+            ("title", "C++ (2nd)"),
+            ("field", "it's"),
+            ("term", "Rust: 2021 (ed.)"),
+            ("id", "a b"),
+        ])?;
+
+        assert_eq!(
+            query.url.params("q"),
+            vec!["{!lucene}title:C\\+\\+\\ \\(2nd\\)"]
+        );
+        assert_eq!(
+            query.url.params("fq"),
+            vec!["{!term f='it\\'s'}Rust: 2021 (ed.)", "{!type=raw f=id}a b"]
+        );
+
+        let misplaced = QueryTemplate::new(
+            "http://localhost:8983/solr/collection/select?q={!term f=a_{field}}x",
+        )?;
+        assert!(matches!(
+            misplaced.bind(&[("field", "b")]),
+            Err(SolrSubqueryError::MisplacedPlaceholder(name)) if name == "field"
+        ));
+
+        Ok(())
+    }
+}
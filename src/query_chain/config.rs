@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::errors::SolrSubqueryError;
+use crate::query_builder::SolrQueryBuilder;
+use crate::query_chain::QueryChain;
+use crate::solr_query::Operator;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+/// A declarative query chain definition
+struct ChainDefinition {
+    endpoint: String,
+    subqueries: Vec<SubqueryDefinition>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SubqueryDefinition {
+    q: String,
+    label: Option<String>,
+    operator: Option<String>,
+    #[serde(default)]
+    params: BTreeMap<String, String>,
+}
+
+impl QueryChain {
+    /// Loads a query chain from a YAML (`.yml`, `.yaml`) or TOML (`.toml`) definition file
+    ///
+    /// ```yaml
+    /// endpoint: http://localhost:8983/solr/collection/select
+    /// subqueries:
+    ///   - q: "*:*"
+    ///   - q: lang:en
+    ///     label: english-only
+    ///   - q: year:[2020 TO *]
+    ///     operator: OR
+    ///     params:
+    ///       rows: "100"
+    /// ```
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<QueryChain, SolrSubqueryError> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| SolrSubqueryError::InvalidConfig(format!("{}: {}", path.display(), e)))?;
+
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("yml") | Some("yaml") => QueryChain::from_yaml_str(&content),
+            Some("toml") => QueryChain::from_toml_str(&content),
+            _ => Err(SolrSubqueryError::InvalidConfig(format!(
+                "{}: unsupported file extension",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Loads a query chain from a YAML definition
+    pub fn from_yaml_str(yaml: &str) -> Result<QueryChain, SolrSubqueryError> {
+        let definition: ChainDefinition = serde_yaml::from_str(yaml)
+            .map_err(|e| SolrSubqueryError::InvalidConfig(e.to_string()))?;
+        definition.into_chain()
+    }
+
+    /// Loads a query chain from a TOML definition
+    pub fn from_toml_str(toml: &str) -> Result<QueryChain, SolrSubqueryError> {
+        let definition: ChainDefinition =
+            toml::from_str(toml).map_err(|e| SolrSubqueryError::InvalidConfig(e.to_string()))?;
+        definition.into_chain()
+    }
+}
+
+impl ChainDefinition {
+    fn into_chain(self) -> Result<QueryChain, SolrSubqueryError> {
+        let mut chain = QueryChain::new(vec![]);
+
+        for subquery in self.subqueries {
+            let mut builder = SolrQueryBuilder::new(&self.endpoint).param("q", &subquery.q);
+            for (name, value) in &subquery.params {
+                builder = builder.param(name, value);
+            }
+
+            let operator = match &subquery.operator {
+                Some(operator) => operator.parse()?,
+                None => Operator::And,
+            };
+
+            chain.add_subquery_with_operator(builder.build()?.url, operator)?;
+            if let Some(last) = chain.labels.last_mut() {
+                *last = subquery.label;
+            }
+        }
+
+        Ok(chain)
+    }
+}
+
+#[cfg(test)]
+mod config_tests {
+    use super::*;
+    use std::error::Error;
+    use urlencoding::decode;
+
+    #[test]
+    fn should_load_chain_from_yaml_and_toml() -> Result<(), Box<dyn Error>> {
+        let yaml = r#"
+endpoint: http://localhost:8983/solr/collection/select
+subqueries:
+  - q: "1:*"
+  - q: "2:*"
+    label: second
+    operator: OR
+    params:
+      rows: "10"
+"#;
+
+        let toml = r#"
+endpoint = "http://localhost:8983/solr/collection/select"
+
+[[subqueries]]
+q = "1:*"
+
+[[subqueries]]
+q = "2:*"
+label = "second"
+operator = "OR"
+params = { rows = "10" }
+"#;
+
+        let yaml_chain = QueryChain::from_yaml_str(yaml)?;
+        assert_eq!(yaml_chain, QueryChain::from_toml_str(toml)?);
+        assert_eq!(yaml_chain.label(1), Some("second"));
+
+        let url_string = yaml_chain.collapse()?.url.to_string();
+        let result = decode(&url_string)?;
+
+        assert_eq!(
+            result,
+            "http://localhost:8983/solr/collection/select?q=(1:*)+OR+(2:*)&rows=10"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_not_load_unknown_format() {
+        assert!(matches!(
+            QueryChain::from_file("chain.json"),
+            Err(SolrSubqueryError::InvalidConfig(_))
+        ));
+    }
+}
@@ -1,7 +1,14 @@
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, VecDeque};
+
+#[cfg(feature = "config-file")]
+mod config;
+mod named;
+mod report;
+pub use report::*;
 
 use crate::errors::SolrSubqueryError;
-use crate::solr_query::{SolrQuery, SubQuery};
+use crate::solr_query::{InverseMode, JoinPolicy, Operator, SolrQuery, SubQuery};
+use serde_json::{json, Map, Value};
 use url::Url;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -9,47 +16,684 @@ use url::Url;
 pub struct QueryChain {
     queries: VecDeque<SolrQuery>,
     iteration: usize,
+    counts: BTreeMap<usize, u64>,
+    labels: Vec<Option<String>>,
+    operators: Vec<Operator>,
+    definitions: BTreeMap<String, SolrQuery>,
+    /// Error of the merge that stopped the iteration, if any
+    error: Option<SolrSubqueryError>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A step of a query chain along with the label of its last subquery
+pub struct ChainStep {
+    pub step: usize,
+    pub label: Option<String>,
+    pub query: SolrQuery,
 }
 
 impl QueryChain {
     pub fn new(queries: Vec<SolrQuery>) -> QueryChain {
         QueryChain {
+            labels: vec![None; queries.len()],
+            operators: vec![Operator::And; queries.len()],
             queries: queries.into(),
             iteration: 0,
+            counts: BTreeMap::new(),
+            definitions: BTreeMap::new(),
+            error: None,
         }
     }
 
     pub fn add_subquery<U: TryInto<Url>>(&mut self, url: U) -> Result<(), SolrSubqueryError>
+    where
+        <U as TryInto<Url>>::Error: std::error::Error + 'static,
+    {
+        self.add_subquery_with_operator(url, Operator::And)
+    }
+
+    /// Adds a subquery merged with the previous step using the given operator
+    ///
+    /// `Operator::And` inner joins the queries, other operators merge them.
+    pub fn add_subquery_with_operator<U: TryInto<Url>>(
+        &mut self,
+        url: U,
+        operator: Operator,
+    ) -> Result<(), SolrSubqueryError>
     where
         <U as TryInto<Url>>::Error: std::error::Error + 'static,
     {
         let query = SolrQuery::new(url)?;
         self.queries.push_back(query);
+        self.labels.push(None);
+        self.operators.push(operator);
+        self.invalidate_counts_from(self.step_offset() + self.queries.len() - 1);
+        Ok(())
+    }
+
+    /// Adds a subquery described by a label
+    pub fn add_labeled_subquery<U: TryInto<Url>>(
+        &mut self,
+        label: &str,
+        url: U,
+    ) -> Result<(), SolrSubqueryError>
+    where
+        <U as TryInto<Url>>::Error: std::error::Error + 'static,
+    {
+        self.add_subquery(url)?;
+        if let Some(last) = self.labels.last_mut() {
+            *last = Some(label.to_string());
+        }
+        Ok(())
+    }
+
+    /// Inserts a subquery at the given step, shifting the following subqueries
+    pub fn insert_subquery<U: TryInto<Url>>(
+        &mut self,
+        index: usize,
+        url: U,
+    ) -> Result<(), SolrSubqueryError>
+    where
+        <U as TryInto<Url>>::Error: std::error::Error + 'static,
+    {
+        let position = self.editable_position(index, true)?;
+        let query = SolrQuery::new(url)?;
+
+        self.queries.insert(position, query);
+        self.labels.insert(index, None);
+        self.operators.insert(index, Operator::And);
+        self.invalidate_counts_from(index);
+        Ok(())
+    }
+
+    /// Removes the subquery of the given step, shifting the following subqueries
+    pub fn remove(&mut self, index: usize) -> Result<SolrQuery, SolrSubqueryError> {
+        let position = self.editable_position(index, false)?;
+        let query = self
+            .queries
+            .remove(position)
+            .ok_or(SolrSubqueryError::InvalidStep(index))?;
+
+        self.labels.remove(index);
+        self.operators.remove(index);
+        self.invalidate_counts_from(index);
+        Ok(query)
+    }
+
+    /// Swaps the subqueries of two steps along with their labels and operators
+    pub fn swap(&mut self, a: usize, b: usize) -> Result<(), SolrSubqueryError> {
+        let position_a = self.editable_position(a, false)?;
+        let position_b = self.editable_position(b, false)?;
+
+        self.queries.swap(position_a, position_b);
+        self.labels.swap(a, b);
+        self.operators.swap(a, b);
+        self.invalidate_counts_from(a.min(b));
+        Ok(())
+    }
+
+    /// Makes every merge of the chain fail once it exceeds the given number of clauses
+    ///
+    /// See `SolrQuery::with_clause_budget`.
+    pub fn with_clause_budget(mut self, clause_budget: usize) -> QueryChain {
+        self.queries = self
+            .queries
+            .into_iter()
+            .map(|q| q.with_clause_budget(clause_budget))
+            .collect();
+        self
+    }
+
+    /// Gets the label of the subquery added at the given step
+    pub fn label(&self, step: usize) -> Option<&str> {
+        self.labels.get(step).and_then(|l| l.as_deref())
+    }
+
+    /// Iterates over the steps along with their labels
+    ///
+    /// The iteration ends after the error of the first merge that fails.
+    pub fn labeled(mut self) -> impl Iterator<Item = Result<ChainStep, SolrSubqueryError>> {
+        let mut failed = false;
+
+        std::iter::from_fn(move || {
+            if failed {
+                return None;
+            }
+
+            let step = self.iteration;
+            match self.next() {
+                Some(query) => Some(Ok(ChainStep {
+                    step,
+                    label: self.label(step).map(|l| l.to_string()),
+                    query,
+                })),
+                None => {
+                    failed = true;
+                    self.take_error().map(Err)
+                }
+            }
+        })
+    }
+
+    /// Takes the error of the merge that stopped the iteration, if any
+    ///
+    /// The failing step is left pending, so iterating again retries the merge.
+    pub fn take_error(&mut self) -> Option<SolrSubqueryError> {
+        self.error.take()
+    }
+
+    /// Iterates over the remaining steps without consuming the chain
+    ///
+    /// Steps are merged on the fly, so the chain can be iterated again or shared.
+    pub fn iter(&self) -> ChainIter<'_> {
+        // Once the iteration started, the first subquery is the last step already yielded
+        match self.iteration {
+            0 => ChainIter {
+                chain: self,
+                index: 0,
+                merged: None,
+            },
+            _ => ChainIter {
+                chain: self,
+                index: 1,
+                merged: self.queries.front().cloned(),
+            },
+        }
+    }
+
+    /// Merges all the subqueries into the final query without building intermediate steps
+    pub fn collapse(self) -> Result<SolrQuery, SolrSubqueryError> {
+        let first_step = self.step_offset();
+        let mut queries = self.queries.iter().enumerate();
+        let (_, first) = queries.next().ok_or(SolrSubqueryError::EmptyChain)?;
+
+        queries.try_fold(first.clone(), |merged, (i, query)| {
+            merge_step(&merged, query, self.operator(first_step + i))
+        })
+    }
+
+    /// Merges all the subqueries and keeps a single copy of the repeated clauses
+    ///
+    /// See `SolrQuery::deduplicate`.
+    pub fn collapse_deduplicated(self) -> Result<SolrQuery, SolrSubqueryError> {
+        self.collapse()?.deduplicate()
+    }
+
+    /// Merges all the subqueries from right to left, e.g. `(A) AND ((B) AND (C))`
+    ///
+    /// The operator of a step still joins its subquery with the preceding one,
+    /// only the grouping changes.
+    pub fn collapse_rev(self) -> Result<SolrQuery, SolrSubqueryError> {
+        let first_step = self.step_offset();
+        let mut queries = self.queries.iter().enumerate().rev();
+        let (_, last) = queries.next().ok_or(SolrSubqueryError::EmptyChain)?;
+
+        queries.try_fold(last.clone(), |merged, (i, query)| {
+            merge_step(query, &merged, self.operator(first_step + i + 1))
+        })
+    }
+
+    /// Iterates over the subqueries that haven't been merged yet
+    ///
+    /// Once the iteration started, the first one is the last merged step.
+    pub fn pending(&self) -> impl Iterator<Item = &SolrQuery> {
+        self.queries.iter()
+    }
+
+    /// Replaces the subqueries not merged yet
+    ///
+    /// The memoized counts are forgotten from the first step whose subquery is no longer
+    /// equivalent, see `SolrQuery::is_equivalent`.
+    pub(crate) fn map_pending<F>(&mut self, mut map: F) -> Result<(), SolrSubqueryError>
+    where
+        F: FnMut(&SolrQuery) -> Result<SolrQuery, SolrSubqueryError>,
+    {
+        let queries = self
+            .queries
+            .iter()
+            .map(&mut map)
+            .collect::<Result<VecDeque<_>, _>>()?;
+
+        if let Some(changed) = queries
+            .iter()
+            .zip(&self.queries)
+            .position(|(a, b)| !a.is_equivalent(b))
+        {
+            self.invalidate_counts_from(self.step_offset() + changed);
+        }
+
+        self.queries = queries;
         Ok(())
     }
+
+    /// Gets the operator merging the subquery of the given step with the previous step
+    pub fn operator(&self, step: usize) -> Operator {
+        self.operators.get(step).copied().unwrap_or(Operator::And)
+    }
+
+    /// Gets the query matching every document not matched by the whole chain
+    ///
+    /// This is the complement of the final reduction (`*:* NOT (...)`), not the
+    /// last step with its last operator flipped as returned by `SolrQuery::inverse`.
+    pub fn inverse(&self) -> Result<SolrQuery, SolrSubqueryError> {
+        Ok(self
+            .clone()
+            .collapse()?
+            .inverse_with(InverseMode::Complement))
+    }
+
+    /// Gets the memoized `numFound` of a step, if any
+    pub fn cached_count(&self, step: usize) -> Option<u64> {
+        self.counts.get(&step).copied()
+    }
+
+    /// Memoizes the `numFound` of a step
+    pub fn cache_count(&mut self, step: usize, num_found: u64) {
+        self.counts.insert(step, num_found);
+    }
+
+    /// Gets the memoized `numFound` of a step or computes and memoizes it
+    pub fn count_or_insert_with<F, E>(&mut self, step: usize, count: F) -> Result<u64, E>
+    where
+        F: FnOnce() -> Result<u64, E>,
+    {
+        if let Some(num_found) = self.cached_count(step) {
+            return Ok(num_found);
+        }
+
+        let num_found = count()?;
+        self.cache_count(step, num_found);
+        Ok(num_found)
+    }
+
+    /// Forgets the memoized counts of a step and of all the following ones
+    pub fn invalidate_counts_from(&mut self, step: usize) {
+        self.counts.split_off(&step);
+    }
+
+    /// Builds a JSON Request API body registering each subquery as a named query
+    ///
+    /// Subqueries are stored under `queries` and referenced from the main `bool` query,
+    /// the other parameters of the last subquery are kept under `params`, as arrays when
+    /// repeated such as `fq`.
+    pub fn to_json_request(&self) -> Result<Value, SolrSubqueryError> {
+        let mut queries = Map::new();
+        let mut query = Value::Null;
+
+        for (i, subquery) in self.queries.iter().enumerate() {
+            let name = format!("q{}", i);
+            queries.insert(name.clone(), Value::String(subquery.q_param()?));
+
+            let param = json!({ "param": name });
+            query = match (i, self.operator(self.step_offset() + i)) {
+                (0, _) => param,
+                (_, Operator::And) if is_conjunction(&query) => {
+                    if let Some(must) = query["bool"]["must"].as_array_mut() {
+                        must.push(param);
+                    }
+                    query
+                }
+                (_, Operator::And) => json!({ "bool": { "must": [query, param] } }),
+                (_, Operator::Or) => json!({ "bool": { "should": [query, param] } }),
+                (_, Operator::Not) => json!({ "bool": { "must": [query], "must_not": [param] } }),
+            };
+        }
+
+        let mut params = Map::new();
+        if let Some(last) = self.queries.back() {
+            for (key, value) in last.url.query_pairs().filter(|(k, _)| k != "q") {
+                let value = Value::String(value.to_string());
+                match params.get_mut(key.as_ref()) {
+                    Some(Value::Array(values)) => values.push(value),
+                    Some(first) => *first = json!([first.take(), value]),
+                    None => {
+                        params.insert(key.to_string(), value);
+                    }
+                }
+            }
+        }
+
+        Ok(json!({
+            "queries": queries,
+            "query": query,
+            "params": params,
+        }))
+    }
+
+    /// Computes every step without executing anything and reports on the resulting queries
+    pub fn compose_report(&self) -> Result<ComposeReport, SolrSubqueryError> {
+        let steps = self
+            .iter()
+            .enumerate()
+            .map(|(i, query)| {
+                let step = self.step_offset() + i;
+                StepReport::new(step, self.label(step), &query?)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(ComposeReport { steps })
+    }
+
+    /// Renders the chain as a Graphviz DOT graph of subqueries and merge nodes
+    pub fn to_dot(&self) -> Result<String, SolrSubqueryError> {
+        let escape = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+        let mut lines = vec!["digraph chain {".to_string()];
+
+        for (i, query) in self.queries.iter().enumerate() {
+            let step = self.step_offset() + i;
+            let label = match self.label(step) {
+                Some(label) => format!("{}\\n{}", escape(label), escape(&query.q_param()?)),
+                None => escape(&query.q_param()?),
+            };
+            lines.push(format!("  q{} [shape=box, label=\"{}\"];", step, label));
+
+            if i > 0 {
+                let previous = if i == 1 {
+                    format!("q{}", step - 1)
+                } else {
+                    format!("m{}", step - 1)
+                };
+
+                lines.push(format!(
+                    "  m{} [shape=ellipse, label=\"step {}\"];",
+                    step, step
+                ));
+                lines.push(format!("  {} -> m{};", previous, step));
+                lines.push(format!(
+                    "  q{} -> m{} [label=\"{}\"];",
+                    step,
+                    step,
+                    self.operator(step)
+                ));
+            }
+        }
+
+        lines.push("}".to_string());
+        Ok(lines.join("\n"))
+    }
+
+    /// Serializes the subqueries with their join policy and clause budget, the labels,
+    /// operators, memoized counts and iteration state
+    pub fn to_json(&self) -> String {
+        let queries = self
+            .queries
+            .iter()
+            .map(|q| {
+                json!({
+                    "url": q.url.as_str(),
+                    "negation": q.negation_url().as_str(),
+                    "handler": q.endpoint().handler,
+                    "join_policy": join_policy_name(q.join_policy()),
+                    "clause_budget": q.clause_budget(),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let steps = self
+            .labels
+            .iter()
+            .zip(&self.operators)
+            .map(|(label, operator)| json!({ "label": label, "operator": operator.to_string() }))
+            .collect::<Vec<_>>();
+
+        let counts = self
+            .counts
+            .iter()
+            .map(|(step, count)| (step.to_string(), json!(count)))
+            .collect::<Map<_, _>>();
+
+        json!({
+            "queries": queries,
+            "steps": steps,
+            "counts": counts,
+            "iteration": self.iteration,
+        })
+        .to_string()
+    }
+
+    /// Deserializes a query chain serialized with `to_json`
+    pub fn from_json(json: &str) -> Result<QueryChain, SolrSubqueryError> {
+        let invalid = |e: &str| SolrSubqueryError::InvalidJson(e.to_string());
+        let value: Value = serde_json::from_str(json).map_err(|e| invalid(&e.to_string()))?;
+
+        let mut queries = VecDeque::new();
+        for query in value["queries"]
+            .as_array()
+            .ok_or_else(|| invalid("missing queries"))?
+        {
+            let url = query["url"]
+                .as_str()
+                .ok_or_else(|| invalid("missing url"))?;
+            let negation = query["negation"]
+                .as_str()
+                .ok_or_else(|| invalid("missing negation"))?;
+
+            // Handlers may have several path segments, see `SolrQuery::from_parts`
+            let handler_segments = query["handler"]
+                .as_str()
+                .map_or(1, |handler| handler.split('/').count());
+
+            let join_policy = match query["join_policy"].as_str() {
+                Some(name) => {
+                    parse_join_policy(name).ok_or_else(|| invalid("invalid join policy"))?
+                }
+                None => JoinPolicy::default(),
+            };
+
+            let mut subquery = SolrQuery::with_negation(Url::parse(url)?, Url::parse(negation)?)?
+                .with_handler_segments(handler_segments)
+                .with_join_policy(join_policy);
+            if let Some(clause_budget) = query["clause_budget"].as_u64() {
+                subquery = subquery.with_clause_budget(clause_budget as usize);
+            }
+            queries.push_back(subquery);
+        }
+
+        let mut labels = Vec::new();
+        let mut operators = Vec::new();
+        for step in value["steps"]
+            .as_array()
+            .ok_or_else(|| invalid("missing steps"))?
+        {
+            labels.push(step["label"].as_str().map(|l| l.to_string()));
+            operators.push(
+                step["operator"]
+                    .as_str()
+                    .ok_or_else(|| invalid("missing operator"))?
+                    .parse()?,
+            );
+        }
+
+        let mut counts = BTreeMap::new();
+        for (step, count) in value["counts"]
+            .as_object()
+            .ok_or_else(|| invalid("missing counts"))?
+        {
+            counts.insert(
+                step.parse().map_err(|_| invalid("invalid count step"))?,
+                count.as_u64().ok_or_else(|| invalid("invalid count"))?,
+            );
+        }
+
+        let iteration = value["iteration"]
+            .as_u64()
+            .ok_or_else(|| invalid("missing iteration"))? as usize;
+
+        Ok(QueryChain {
+            queries,
+            iteration,
+            counts,
+            labels,
+            operators,
+            definitions: BTreeMap::new(),
+            error: None,
+        })
+    }
+
+    /// Step index of the query at the front of the queue
+    pub(crate) fn step_offset(&self) -> usize {
+        self.iteration.saturating_sub(1)
+    }
+
+    /// Position in the queue of a step that has not been yielded yet
+    ///
+    /// `allow_end` accepts the step following the last one, where a subquery can be appended.
+    fn editable_position(&self, step: usize, allow_end: bool) -> Result<usize, SolrSubqueryError> {
+        let steps = self.step_offset() + self.queries.len();
+
+        if step < self.iteration || step > steps || (step == steps && !allow_end) {
+            return Err(SolrSubqueryError::InvalidStep(step));
+        }
+
+        Ok(step - self.step_offset())
+    }
 }
 
+/// Merges the steps one after the other
+///
+/// A failing merge ends the iteration, its error is kept until `QueryChain::take_error`.
 impl Iterator for QueryChain {
     type Item = SolrQuery;
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.iteration == 0 {
             self.iteration += 1;
-            return self.queries.get(0).cloned();
+            return self.queries.front().cloned();
         }
 
         match (self.queries.pop_front(), self.queries.pop_front()) {
-            (Some(q1), Some(q2)) => {
-                self.iteration += 1;
-                let new_query = q1.inner_join(&q2).unwrap();
-                self.queries.push_front(new_query.clone());
-                Some(new_query)
-            }
+            (Some(q1), Some(q2)) => match merge_step(&q1, &q2, self.operator(self.iteration)) {
+                Ok(new_query) => {
+                    self.iteration += 1;
+                    self.queries.push_front(new_query.clone());
+                    Some(new_query)
+                }
+                Err(e) => {
+                    self.queries.push_front(q2);
+                    self.queries.push_front(q1);
+                    self.error = Some(e);
+                    None
+                }
+            },
             _ => None,
         }
     }
 }
 
+/// A non consuming iterator over the steps of a query chain
+pub struct ChainIter<'a> {
+    chain: &'a QueryChain,
+    index: usize,
+    merged: Option<SolrQuery>,
+}
+
+impl<'a> Iterator for ChainIter<'a> {
+    type Item = Result<SolrQuery, SolrSubqueryError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let query = self.chain.queries.get(self.index)?;
+        let step = self.chain.step_offset() + self.index;
+        self.index += 1;
+
+        #[cfg(feature = "tracing")]
+        let _span =
+            tracing::debug_span!("chain_step", step, label = self.chain.label(step)).entered();
+
+        let merged = match &self.merged {
+            None => Ok(query.clone()),
+            Some(merged) => merge_step(merged, query, self.chain.operator(step)),
+        };
+
+        match merged {
+            Ok(merged) => {
+                self.merged = Some(merged.clone());
+                Some(Ok(merged))
+            }
+            Err(e) => {
+                self.index = self.chain.queries.len();
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a QueryChain {
+    type Item = Result<SolrQuery, SolrSubqueryError>;
+    type IntoIter = ChainIter<'a>;
+
+    fn into_iter(self) -> ChainIter<'a> {
+        self.iter()
+    }
+}
+
+impl FromIterator<SolrQuery> for QueryChain {
+    fn from_iter<I: IntoIterator<Item = SolrQuery>>(iter: I) -> QueryChain {
+        QueryChain::new(iter.into_iter().collect())
+    }
+}
+
+impl Extend<SolrQuery> for QueryChain {
+    fn extend<I: IntoIterator<Item = SolrQuery>>(&mut self, iter: I) {
+        let first_new_step = self.step_offset() + self.queries.len();
+
+        for query in iter {
+            self.queries.push_back(query);
+            self.labels.push(None);
+            self.operators.push(Operator::And);
+        }
+
+        self.invalidate_counts_from(first_new_step);
+    }
+}
+
+impl TryFrom<Vec<&str>> for QueryChain {
+    type Error = SolrSubqueryError;
+
+    fn try_from(urls: Vec<&str>) -> Result<QueryChain, SolrSubqueryError> {
+        urls.into_iter().map(SolrQuery::new).collect()
+    }
+}
+
+/// Checks if a JSON query is a `bool` query made of `must` clauses only
+fn is_conjunction(query: &Value) -> bool {
+    query["bool"]
+        .as_object()
+        .is_some_and(|b| b.len() == 1 && b.contains_key("must"))
+}
+
+/// Name of a join policy in the JSON serialization of a chain
+fn join_policy_name(join_policy: JoinPolicy) -> &'static str {
+    match join_policy {
+        JoinPolicy::Strict => "strict",
+        JoinPolicy::IgnorePort => "ignore_port",
+        JoinPolicy::IgnoreHost => "ignore_host",
+        JoinPolicy::IgnorePath => "ignore_path",
+    }
+}
+
+fn parse_join_policy(name: &str) -> Option<JoinPolicy> {
+    [
+        JoinPolicy::Strict,
+        JoinPolicy::IgnorePort,
+        JoinPolicy::IgnoreHost,
+        JoinPolicy::IgnorePath,
+    ]
+    .into_iter()
+    .find(|join_policy| join_policy_name(*join_policy) == name)
+}
+
+/// Merges a step with the next subquery
+fn merge_step(
+    merged: &SolrQuery,
+    query: &SolrQuery,
+    operator: Operator,
+) -> Result<SolrQuery, SolrSubqueryError> {
+    match operator {
+        Operator::And => merged.inner_join(query),
+        operator => merged.merge_queries(query, operator),
+    }
+}
+
 #[cfg(test)]
 mod query_chain_tests {
     use super::*;
@@ -97,4 +741,443 @@ mod query_chain_tests {
 
         Ok(())
     }
+
+    #[test]
+    fn should_memoize_counts() -> Result<(), Box<dyn Error>> {
+        let first_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=1:*")?;
+        let second_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=2:*")?;
+
+        let mut query_chain = QueryChain::new(vec![first_query, second_query]);
+
+        let mut requests = 0;
+        for _ in 0..2 {
+            let count = query_chain.count_or_insert_with(1, || -> Result<u64, ()> {
+                requests += 1;
+                Ok(42)
+            });
+            assert_eq!(count, Ok(42));
+        }
+        assert_eq!(requests, 1);
+
+        query_chain.cache_count(2, 10);
+        query_chain.add_subquery("http://localhost:8983/solr/collection/select?q=3:*")?;
+
+        assert_eq!(query_chain.cached_count(1), Some(42));
+        assert_eq!(query_chain.cached_count(2), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_build_json_request() -> Result<(), Box<dyn Error>> {
+        let first_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=1:*")?;
+        let second_query = SolrQuery::new(
+            "http://localhost:8983/solr/collection/select?q=2:*&rows=10&fq=a:1&fq=b:1&fq=c:1",
+        )?;
+
+        let query_chain = QueryChain::new(vec![first_query, second_query]);
+
+        assert_eq!(
+            query_chain.to_json_request()?,
+            json!({
+                "queries": { "q0": "1:*", "q1": "2:*" },
+                "query": { "bool": { "must": [{ "param": "q0" }, { "param": "q1" }] } },
+                "params": { "rows": "10", "fq": ["a:1", "b:1", "c:1"] },
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_collapse_chain() -> Result<(), Box<dyn Error>> {
+        let first_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=1:*")?;
+        let second_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=2:*")?;
+        let third_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=3:*")?;
+
+        let query_chain = QueryChain::new(vec![first_query, second_query, third_query]);
+        let last_step = query_chain.clone().last();
+
+        assert_eq!(Some(query_chain.collapse()?), last_step);
+        assert_eq!(
+            QueryChain::new(vec![]).collapse(),
+            Err(SolrSubqueryError::EmptyChain)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_inverse_chain() -> Result<(), Box<dyn Error>> {
+        let first_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=1:*")?;
+        let second_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=2:*")?;
+
+        let query_chain = QueryChain::new(vec![first_query, second_query]);
+
+        let inverse_string = query_chain.inverse()?.url.to_string();
+        let inverse_result = decode(&inverse_string)?;
+
+        assert_eq!(
+            inverse_result,
+            "http://localhost:8983/solr/collection/select?q=*:*+NOT+((1:*)+AND+(2:*))"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_label_steps() -> Result<(), Box<dyn Error>> {
+        let first_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=*:*")?;
+
+        let mut query_chain = QueryChain::new(vec![first_query]);
+        query_chain.add_labeled_subquery(
+            "english-only",
+            "http://localhost:8983/solr/collection/select?q=lang:en",
+        )?;
+
+        assert_eq!(query_chain.label(0), None);
+        assert_eq!(query_chain.label(1), Some("english-only"));
+
+        let labels = query_chain
+            .labeled()
+            .map(|step| step.map(|step| (step.step, step.label)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        assert_eq!(labels, vec![(0, None), (1, Some("english-only".into()))]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_merge_steps_with_operators() -> Result<(), Box<dyn Error>> {
+        let first_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=1:*")?;
+
+        let mut query_chain = QueryChain::new(vec![first_query]);
+        query_chain.add_subquery_with_operator(
+            "http://localhost:8983/solr/collection/select?q=2:*",
+            Operator::Or,
+        )?;
+        query_chain.add_subquery("http://localhost:8983/solr/collection/select?q=3:*")?;
+
+        assert_eq!(
+            query_chain.to_json_request()?["query"],
+            json!({ "bool": { "must": [
+                { "bool": { "should": [{ "param": "q0" }, { "param": "q1" }] } },
+                { "param": "q2" },
+            ] } })
+        );
+
+        let url_string = query_chain.collapse()?.url.to_string();
+        let result = decode(&url_string)?;
+
+        assert_eq!(
+            result,
+            "http://localhost:8983/solr/collection/select?q=((1:*)+OR+(2:*))+AND+(3:*)"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_serialize_chain_to_json() -> Result<(), Box<dyn Error>> {
+        let first_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=1:*")?;
+
+        let mut query_chain = QueryChain::new(vec![first_query]);
+        query_chain.add_labeled_subquery(
+            "second",
+            "http://localhost:8983/solr/collection/select?q=2:*",
+        )?;
+        query_chain.add_subquery_with_operator(
+            "http://localhost:8983/solr/collection/select?q=3:*",
+            Operator::Not,
+        )?;
+        query_chain.cache_count(0, 42);
+
+        query_chain.next();
+        query_chain.next();
+
+        let mut restored = QueryChain::from_json(&query_chain.to_json())?;
+        assert_eq!(restored, query_chain);
+        assert_eq!(restored.next(), query_chain.next());
+
+        assert!(matches!(
+            QueryChain::from_json("{}"),
+            Err(SolrSubqueryError::InvalidJson(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_serialize_merge_settings_to_json() -> Result<(), Box<dyn Error>> {
+        let first_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=1:*")?
+            .with_join_policy(JoinPolicy::IgnorePort);
+
+        let mut query_chain = QueryChain::new(vec![first_query]).with_clause_budget(2);
+        query_chain.add_subquery("http://localhost:8984/solr/collection/select?q=2:*")?;
+        query_chain.add_subquery("http://localhost:8983/solr/collection/select?q=3:*")?;
+
+        let restored = QueryChain::from_json(&query_chain.to_json())?;
+        assert_eq!(restored, query_chain);
+        assert_eq!(
+            restored.pending().next().map(|q| q.join_policy()),
+            Some(JoinPolicy::IgnorePort)
+        );
+        assert!(matches!(
+            restored.collapse(),
+            Err(SolrSubqueryError::TooManyClauses { budget: 2, .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_iterate_without_consuming() -> Result<(), Box<dyn Error>> {
+        let first_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=1:*")?;
+        let second_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=2:*")?;
+        let third_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=3:*")?;
+
+        let query_chain = QueryChain::new(vec![first_query, second_query, third_query]);
+
+        let steps = query_chain.iter().collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(steps, query_chain.iter().collect::<Result<Vec<_>, _>>()?);
+        assert_eq!(steps, query_chain.clone().collect::<Vec<_>>());
+
+        let mut started_chain = query_chain.clone();
+        started_chain.next();
+        started_chain.next();
+        assert_eq!(
+            started_chain.iter().collect::<Result<Vec<_>, _>>()?,
+            vec![steps[2].clone()]
+        );
+        assert_eq!(started_chain.iter().count(), started_chain.count());
+
+        let invalid_query = SolrQuery::new("http://localhost:8984/solr/collection/select?q=4:*")?;
+        let invalid_chain = QueryChain::new(vec![steps[2].clone(), invalid_query]);
+        let mut iter = invalid_chain.iter();
+
+        assert!(iter.next().is_some());
+        assert!(matches!(
+            iter.next(),
+            Some(Err(SolrSubqueryError::DifferentsPorts { .. }))
+        ));
+        assert_eq!(iter.next(), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_render_chain_as_dot() -> Result<(), Box<dyn Error>> {
+        let first_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=1:*")?;
+
+        let mut query_chain = QueryChain::new(vec![first_query]);
+        query_chain.add_labeled_subquery(
+            "quoted",
+            "http://localhost:8983/solr/collection/select?q=title:\"rust\"",
+        )?;
+        query_chain.add_subquery_with_operator(
+            "http://localhost:8983/solr/collection/select?q=3:*",
+            Operator::Or,
+        )?;
+
+        let expected = [
+            "digraph chain {",
+            "  q0 [shape=box, label=\"1:*\"];",
+            "  q1 [shape=box, label=\"quoted\\ntitle:\\\"rust\\\"\"];",
+            "  m1 [shape=ellipse, label=\"step 1\"];",
+            "  q0 -> m1;",
+            "  q1 -> m1 [label=\"AND\"];",
+            "  q2 [shape=box, label=\"3:*\"];",
+            "  m2 [shape=ellipse, label=\"step 2\"];",
+            "  m1 -> m2;",
+            "  q2 -> m2 [label=\"OR\"];",
+            "}",
+        ]
+        .join("\n");
+
+        assert_eq!(query_chain.to_dot()?, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_preview_then_consume_chain() -> Result<(), Box<dyn Error>> {
+        let first_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=1:*")?;
+        let second_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=2:*")?;
+
+        let query_chain = QueryChain::new(vec![first_query, second_query]);
+
+        let mut preview = Vec::new();
+        for step in &query_chain {
+            preview.push(step?);
+        }
+
+        assert_eq!(preview, query_chain.collect::<Vec<_>>());
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_edit_chain() -> Result<(), Box<dyn Error>> {
+        let first_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=1:*")?;
+        let third_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=3:*")?;
+
+        let mut query_chain = QueryChain::new(vec![first_query.clone(), third_query.clone()]);
+        query_chain.cache_count(0, 10);
+        query_chain.cache_count(1, 5);
+
+        query_chain.insert_subquery(1, "http://localhost:8983/solr/collection/select?q=2:*")?;
+        assert_eq!(query_chain.cached_count(0), Some(10));
+        assert_eq!(query_chain.cached_count(1), None);
+
+        query_chain.swap(0, 2)?;
+        assert_eq!(query_chain.remove(0)?, third_query);
+        assert_eq!(query_chain.remove(1)?, first_query);
+
+        let url_string = query_chain.clone().collapse()?.url.to_string();
+        let result = decode(&url_string)?;
+        assert_eq!(result, "http://localhost:8983/solr/collection/select?q=2:*");
+
+        query_chain.next();
+        assert_eq!(
+            query_chain.remove(0),
+            Err(SolrSubqueryError::InvalidStep(0))
+        );
+        assert_eq!(
+            query_chain.swap(0, 3),
+            Err(SolrSubqueryError::InvalidStep(0))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_collect_chain() -> Result<(), Box<dyn Error>> {
+        let urls = vec![
+            "http://localhost:8983/solr/collection/select?q=1:*",
+            "http://localhost:8983/solr/collection/select?q=2:*",
+        ];
+
+        let query_chain = QueryChain::try_from(urls.clone())?;
+        let collected = urls
+            .iter()
+            .map(|url| SolrQuery::new(*url))
+            .collect::<Result<QueryChain, _>>()?;
+        assert_eq!(query_chain, collected);
+
+        let mut extended = QueryChain::try_from(vec![urls[0]])?;
+        extended.cache_count(1, 5);
+        extended.extend(vec![SolrQuery::new(urls[1])?]);
+        assert_eq!(extended.cached_count(1), None);
+        assert_eq!(extended, query_chain);
+
+        assert!(matches!(
+            QueryChain::try_from(vec!["not an url"]),
+            Err(SolrSubqueryError::InvalidUrl { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_collapse_chain_from_the_right() -> Result<(), Box<dyn Error>> {
+        let first_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=1:*")?;
+        let second_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=2:*")?;
+
+        let mut query_chain = QueryChain::new(vec![first_query, second_query]);
+        query_chain.add_subquery_with_operator(
+            "http://localhost:8983/solr/collection/select?q=3:*",
+            Operator::Or,
+        )?;
+
+        let url_string = query_chain.collapse_rev()?.url.to_string();
+        let result = decode(&url_string)?;
+
+        assert_eq!(
+            result,
+            "http://localhost:8983/solr/collection/select?q=(1:*)+AND+((2:*)+OR+(3:*))"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_collapse_chain_without_duplicates() -> Result<(), Box<dyn Error>> {
+        let first_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=1:*")?;
+        let second_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=2:*")?;
+
+        let query_chain = QueryChain::new(vec![first_query.clone(), second_query, first_query]);
+
+        let url_string = query_chain.collapse_deduplicated()?.url.to_string();
+        let result = decode(&url_string)?;
+
+        assert_eq!(
+            result,
+            "http://localhost:8983/solr/collection/select?q=1:*+AND+2:*"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_enforce_clause_budget_on_chain() -> Result<(), Box<dyn Error>> {
+        let first_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=1:*")?;
+
+        let mut query_chain = QueryChain::new(vec![first_query]).with_clause_budget(2);
+        query_chain.add_subquery("http://localhost:8983/solr/collection/select?q=2:*")?;
+        query_chain.add_subquery("http://localhost:8983/solr/collection/select?q=3:*")?;
+
+        assert_eq!(
+            query_chain.collapse(),
+            Err(SolrSubqueryError::TooManyClauses {
+                count: 3,
+                budget: 2,
+                q: "((1:*) AND (2:*)) AND (3:*)".into(),
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_stop_iteration_over_clause_budget() -> Result<(), Box<dyn Error>> {
+        let first_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=1:*")?;
+
+        let mut query_chain = QueryChain::new(vec![first_query]).with_clause_budget(2);
+        query_chain
+            .add_labeled_subquery("two", "http://localhost:8983/solr/collection/select?q=2:*")?;
+        query_chain.add_labeled_subquery(
+            "three",
+            "http://localhost:8983/solr/collection/select?q=3:*",
+        )?;
+
+        let steps = query_chain.clone().labeled().collect::<Vec<_>>();
+        assert_eq!(steps.len(), 3);
+        assert_eq!(
+            steps[1].as_ref().map(|s| s.label.as_deref()),
+            Ok(Some("two"))
+        );
+        assert!(matches!(
+            steps[2],
+            Err(SolrSubqueryError::TooManyClauses {
+                count: 3,
+                budget: 2,
+                ..
+            })
+        ));
+
+        assert_eq!(query_chain.by_ref().count(), 2);
+        assert!(matches!(
+            query_chain.take_error(),
+            Some(SolrSubqueryError::TooManyClauses { count: 3, .. })
+        ));
+        assert_eq!(query_chain.take_error(), None);
+
+        // The failing step stays pending
+        assert_eq!(query_chain.pending().count(), 2);
+        assert_eq!(query_chain.next(), None);
+        assert!(query_chain.take_error().is_some());
+
+        Ok(())
+    }
 }
@@ -0,0 +1,99 @@
+use crate::errors::SolrSubqueryError;
+use crate::query_chain::QueryChain;
+use crate::solr_query::{Operator, SolrQuery};
+
+use url::Url;
+
+impl QueryChain {
+    /// Registers a subquery under a name so it can be referenced several times
+    ///
+    /// The URL is parsed once, references reuse the parsed query. Definitions
+    /// aren't serialized by `to_json`.
+    pub fn define<U: TryInto<Url>>(&mut self, name: &str, url: U) -> Result<(), SolrSubqueryError>
+    where
+        <U as TryInto<Url>>::Error: std::error::Error + 'static,
+    {
+        self.definitions
+            .insert(name.to_string(), SolrQuery::new(url)?);
+        Ok(())
+    }
+
+    /// Gets a defined subquery, e.g. to use it as a `QueryTree` leaf or in a merge
+    pub fn defined(&self, name: &str) -> Result<&SolrQuery, SolrSubqueryError> {
+        self.definitions
+            .get(name)
+            .ok_or_else(|| SolrSubqueryError::UnknownSubquery(name.to_string()))
+    }
+
+    /// Adds a defined subquery merged with the previous step, labeled with its name
+    pub fn add_defined(&mut self, name: &str, operator: Operator) -> Result<(), SolrSubqueryError> {
+        let query = self.defined(name)?.clone();
+
+        self.queries.push_back(query);
+        self.labels.push(Some(name.to_string()));
+        self.operators.push(operator);
+        self.invalidate_counts_from(self.step_offset() + self.queries.len() - 1);
+        Ok(())
+    }
+
+    /// Gets the names of the defined subqueries the chain was built from, in step order
+    pub fn contributors(&self) -> Vec<&str> {
+        let mut contributors: Vec<&str> = Vec::new();
+        for label in self.labels.iter().flatten() {
+            if self.definitions.contains_key(label) && !contributors.contains(&label.as_str()) {
+                contributors.push(label);
+            }
+        }
+        contributors
+    }
+}
+
+#[cfg(test)]
+mod named_tests {
+    use super::*;
+    use crate::query_tree::QueryTree;
+    use std::error::Error;
+    use urlencoding::decode;
+
+    #[test]
+    fn should_reuse_named_subqueries() -> Result<(), Box<dyn Error>> {
+        let mut chain = QueryChain::new(vec![]);
+        chain.define(
+            "english",
+            "http://localhost:8983/solr/collection/select?q=lang:en",
+        )?;
+        chain.define(
+            "recent",
+            "http://localhost:8983/solr/collection/select?q=year:2020",
+        )?;
+        chain.define(
+            "unused",
+            "http://localhost:8983/solr/collection/select?q=a:1",
+        )?;
+
+        chain.add_defined("english", Operator::And)?;
+        chain.add_defined("recent", Operator::Or)?;
+        chain.add_defined("english", Operator::And)?;
+
+        let tree = QueryTree::or(vec![
+            QueryTree::leaf(chain.defined("english")?.clone()),
+            QueryTree::not(QueryTree::leaf(chain.defined("recent")?.clone())),
+        ]);
+        assert!(tree.to_query().is_ok());
+
+        assert_eq!(chain.contributors(), vec!["english", "recent"]);
+        assert!(matches!(
+            chain.add_defined("french", Operator::And),
+            Err(SolrSubqueryError::UnknownSubquery(_))
+        ));
+
+        let url_string = chain.collapse()?.url.to_string();
+        let result = decode(&url_string)?;
+        assert_eq!(
+            result,
+            "http://localhost:8983/solr/collection/select?q=((lang:en)+OR+(year:2020))+AND+(lang:en)"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,166 @@
+use crate::errors::SolrSubqueryError;
+use crate::solr_query::{count_clauses, Operator, SolrQuery};
+
+use serde_json::{json, Value};
+
+/// Default `maxBooleanClauses` of Solr
+pub const MAX_BOOLEAN_CLAUSES: usize = 1024;
+
+/// Default maximum request header size of Solr's Jetty, which bounds GET URLs
+pub const MAX_URL_LENGTH: usize = 8192;
+
+#[derive(Debug, Clone, PartialEq)]
+/// A potential problem found while composing a step
+pub enum ComposeWarning {
+    /// The URL is longer than `MAX_URL_LENGTH`
+    LongUrl(usize),
+    /// The query has more clauses than `MAX_BOOLEAN_CLAUSES`
+    TooManyClauses(usize),
+    /// The query only has negative clauses
+    PureNegative,
+}
+
+impl std::fmt::Display for ComposeWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ComposeWarning::LongUrl(length) => write!(
+                f,
+                "URL is {} characters long, more than {}",
+                length, MAX_URL_LENGTH
+            ),
+            ComposeWarning::TooManyClauses(count) => write!(
+                f,
+                "Query has {} clauses, more than {}",
+                count, MAX_BOOLEAN_CLAUSES
+            ),
+            ComposeWarning::PureNegative => write!(f, "Query only has negative clauses"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// The composed query of a step
+pub struct StepReport {
+    pub step: usize,
+    pub label: Option<String>,
+    pub q: String,
+    pub q_length: usize,
+    pub url_length: usize,
+    pub clause_count: usize,
+    pub warnings: Vec<ComposeWarning>,
+}
+
+impl StepReport {
+    pub(crate) fn new(
+        step: usize,
+        label: Option<&str>,
+        query: &SolrQuery,
+    ) -> Result<StepReport, SolrSubqueryError> {
+        let q = query.q_param()?;
+        let url_length = query.url.as_str().len();
+        let clause_count = count_clauses(&q);
+
+        let mut warnings = Vec::new();
+        if url_length > MAX_URL_LENGTH {
+            warnings.push(ComposeWarning::LongUrl(url_length));
+        }
+        if clause_count > MAX_BOOLEAN_CLAUSES {
+            warnings.push(ComposeWarning::TooManyClauses(clause_count));
+        }
+        if q.trim_start().starts_with(&format!("{} ", Operator::Not)) {
+            warnings.push(ComposeWarning::PureNegative);
+        }
+
+        Ok(StepReport {
+            step,
+            label: label.map(|l| l.to_string()),
+            q_length: q.chars().count(),
+            q,
+            url_length,
+            clause_count,
+            warnings,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A pre-flight report of every step of a query chain
+pub struct ComposeReport {
+    pub steps: Vec<StepReport>,
+}
+
+impl ComposeReport {
+    /// Checks if any step has a warning
+    pub fn has_warnings(&self) -> bool {
+        self.steps.iter().any(|s| !s.warnings.is_empty())
+    }
+
+    /// Renders the report as JSON
+    pub fn to_json(&self) -> Value {
+        Value::Array(
+            self.steps
+                .iter()
+                .map(|s| {
+                    json!({
+                        "step": s.step,
+                        "label": s.label,
+                        "q": s.q,
+                        "q_length": s.q_length,
+                        "url_length": s.url_length,
+                        "clause_count": s.clause_count,
+                        "warnings": s.warnings.iter().map(|w| w.to_string()).collect::<Vec<_>>(),
+                    })
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod report_tests {
+    use super::*;
+    use crate::query_chain::QueryChain;
+    use std::error::Error;
+
+    #[test]
+    fn should_report_composed_steps() -> Result<(), Box<dyn Error>> {
+        let first_query = SolrQuery::new(
+            "http://localhost:8983/solr/collection/select?q=title:\"rust lang\" year:[2020 TO *]",
+        )?;
+
+        let mut query_chain = QueryChain::new(vec![first_query]);
+        query_chain.add_labeled_subquery(
+            "english-only",
+            "http://localhost:8983/solr/collection/select?q=lang:(en OR fr)",
+        )?;
+
+        let report = query_chain.compose_report()?;
+
+        assert_eq!(report.steps.len(), 2);
+        assert_eq!(report.steps[0].clause_count, 2);
+        assert_eq!(report.steps[1].label.as_deref(), Some("english-only"));
+        assert_eq!(
+            report.steps[1].q,
+            "(title:\"rust lang\" year:[2020 TO *]) AND (lang:(en OR fr))"
+        );
+        assert_eq!(report.steps[1].clause_count, 4);
+        assert!(!report.has_warnings());
+
+        let unbalanced_query =
+            SolrQuery::new("http://localhost:8983/solr/collection/select?q=code:a] b:1 c:1")?;
+        let report = QueryChain::new(vec![unbalanced_query]).compose_report()?;
+        assert_eq!(report.steps[0].clause_count, 3);
+
+        let negative_query =
+            SolrQuery::new("http://localhost:8983/solr/collection/select?q=title:rust")?.inverse();
+        let report = QueryChain::new(vec![negative_query]).compose_report()?;
+
+        assert_eq!(report.steps[0].warnings, vec![ComposeWarning::PureNegative]);
+        assert_eq!(
+            report.to_json()[0]["warnings"],
+            json!(["Query only has negative clauses"])
+        );
+
+        Ok(())
+    }
+}
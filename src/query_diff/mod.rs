@@ -0,0 +1,192 @@
+use crate::solr_query::SolrQuery;
+
+use serde_json::{json, Value};
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const RESET: &str = "\x1b[0m";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// How a line differs between two queries
+pub enum DiffKind {
+    Unchanged,
+    Removed,
+    Added,
+}
+
+impl DiffKind {
+    fn marker(&self) -> char {
+        match self {
+            DiffKind::Unchanged => ' ',
+            DiffKind::Removed => '-',
+            DiffKind::Added => '+',
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            DiffKind::Unchanged => "unchanged",
+            DiffKind::Removed => "removed",
+            DiffKind::Added => "added",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A decoded parameter (or the endpoint) of a query, tagged with its change
+pub struct DiffLine {
+    pub kind: DiffKind,
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A human readable comparison of two queries
+pub struct QueryDiff {
+    pub lines: Vec<DiffLine>,
+}
+
+impl QueryDiff {
+    /// Compares the endpoints and decoded parameters of two queries
+    pub fn new(a: &SolrQuery, b: &SolrQuery) -> QueryDiff {
+        let mut lines = Vec::new();
+
+        let (a_endpoint, b_endpoint) = (endpoint(a), endpoint(b));
+        if a_endpoint == b_endpoint {
+            lines.push(line(DiffKind::Unchanged, "endpoint", &a_endpoint));
+        } else {
+            lines.push(line(DiffKind::Removed, "endpoint", &a_endpoint));
+            lines.push(line(DiffKind::Added, "endpoint", &b_endpoint));
+        }
+
+        let a_params = params(a);
+        let b_params = params(b);
+
+        let mut names: Vec<&str> = Vec::new();
+        for (name, _) in a_params.iter().chain(b_params.iter()) {
+            if !names.contains(&name.as_str()) {
+                names.push(name);
+            }
+        }
+
+        for name in names {
+            let a_values = values(&a_params, name);
+            let b_values = values(&b_params, name);
+
+            for value in &a_values {
+                let kind = if b_values.contains(value) {
+                    DiffKind::Unchanged
+                } else {
+                    DiffKind::Removed
+                };
+                lines.push(line(kind, name, value));
+            }
+
+            for value in b_values.iter().filter(|v| !a_values.contains(v)) {
+                lines.push(line(DiffKind::Added, name, value));
+            }
+        }
+
+        QueryDiff { lines }
+    }
+
+    /// Checks if both queries have the same endpoint and parameters
+    pub fn is_empty(&self) -> bool {
+        self.lines.iter().all(|l| l.kind == DiffKind::Unchanged)
+    }
+
+    /// Renders the comparison as a unified diff, optionally colored with ANSI escapes
+    pub fn render(&self, colored: bool) -> String {
+        self.lines
+            .iter()
+            .map(|l| {
+                let text = format!("{} {}={}", l.kind.marker(), l.name, l.value);
+                match (colored, l.kind) {
+                    (true, DiffKind::Removed) => format!("{}{}{}", RED, text, RESET),
+                    (true, DiffKind::Added) => format!("{}{}{}", GREEN, text, RESET),
+                    _ => text,
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Renders the comparison as JSON
+    pub fn to_json(&self) -> Value {
+        Value::Array(
+            self.lines
+                .iter()
+                .map(|l| json!({"change": l.kind.name(), "name": l.name, "value": l.value}))
+                .collect(),
+        )
+    }
+}
+
+/// Renders a unified diff of the decoded parameters of two queries
+pub fn render_diff(a: &SolrQuery, b: &SolrQuery) -> String {
+    QueryDiff::new(a, b).render(false)
+}
+
+fn line(kind: DiffKind, name: &str, value: &str) -> DiffLine {
+    DiffLine {
+        kind,
+        name: name.to_string(),
+        value: value.to_string(),
+    }
+}
+
+fn endpoint(query: &SolrQuery) -> String {
+    let mut url = query.url.clone();
+    url.set_query(None);
+    url.set_fragment(None);
+    url.to_string()
+}
+
+fn params(query: &SolrQuery) -> Vec<(String, String)> {
+    query
+        .url
+        .query_pairs()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+fn values(params: &[(String, String)], name: &str) -> Vec<String> {
+    params
+        .iter()
+        .filter(|(k, _)| k == name)
+        .map(|(_, v)| v.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod query_diff_tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn should_render_diff() -> Result<(), Box<dyn Error>> {
+        let first_query =
+            SolrQuery::new("http://localhost:8983/solr/collection/select?q=title:rust&rows=10")?;
+        let second_query = SolrQuery::new(
+            "http://localhost:8983/solr/collection/select?q=title:rust&rows=20&fq=lang:en",
+        )?;
+
+        let expected = [
+            "  endpoint=http://localhost:8983/solr/collection/select",
+            "  q=title:rust",
+            "- rows=10",
+            "+ rows=20",
+            "+ fq=lang:en",
+        ]
+        .join("\n");
+
+        assert_eq!(render_diff(&first_query, &second_query), expected);
+        assert_eq!(
+            QueryDiff::new(&first_query, &second_query).to_json()[2],
+            json!({"change": "removed", "name": "rows", "value": "10"})
+        );
+        assert!(QueryDiff::new(&first_query, &first_query).is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,166 @@
+use crate::errors::SolrSubqueryError;
+use crate::solr_query::{Operator, QueryParam, SolrQuery};
+
+#[derive(Debug, Clone, PartialEq)]
+/// An arbitrary nested composition of Solr queries
+///
+/// Unlike `QueryChain`, which only folds subqueries from left to right, a tree
+/// expresses selections such as `(A OR B) AND NOT (C OR D)`.
+pub enum QueryTree {
+    Leaf(Box<SolrQuery>),
+    And(Vec<QueryTree>),
+    Or(Vec<QueryTree>),
+    Not(Box<QueryTree>),
+}
+
+impl QueryTree {
+    /// Creates a leaf
+    pub fn leaf(query: SolrQuery) -> QueryTree {
+        QueryTree::Leaf(Box::new(query))
+    }
+
+    /// Creates a node matching all of its children
+    pub fn and(children: Vec<QueryTree>) -> QueryTree {
+        QueryTree::And(children)
+    }
+
+    /// Creates a node matching any of its children
+    pub fn or(children: Vec<QueryTree>) -> QueryTree {
+        QueryTree::Or(children)
+    }
+
+    /// Creates a node matching every document not matched by its child
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(child: QueryTree) -> QueryTree {
+        QueryTree::Not(Box::new(child))
+    }
+
+    /// Gets the complement of this node, removing a double negation if any
+    pub fn inverse(self) -> QueryTree {
+        match self {
+            QueryTree::Not(child) => *child,
+            node => QueryTree::not(node),
+        }
+    }
+
+    /// Renders the tree to a single merged query
+    ///
+    /// All leaves must share the same host, port and path unless the join policy
+    /// of the last leaf is more lenient. Parameters other than `q` are taken from
+    /// the last leaf.
+    pub fn to_query(&self) -> Result<SolrQuery, SolrSubqueryError> {
+        let leaves = self.leaves();
+        let last = leaves.last().ok_or(SolrSubqueryError::EmptyTree)?;
+
+        for leaf in &leaves {
+            last.check_can_join(leaf)?;
+        }
+
+        let q = self.q()?;
+
+        let mut url = last.url.clone();
+        url.set_param(("q", &q));
+
+        let mut negation = last.url.clone();
+        negation.set_param(("q", &complement(&q)));
+
+        Ok(SolrQuery::with_negation(url, negation)?.with_settings_of(last))
+    }
+
+    /// Renders the q parameter of the tree
+    fn q(&self) -> Result<String, SolrSubqueryError> {
+        match self {
+            QueryTree::Leaf(query) => query.q_param(),
+            QueryTree::And(children) => join(children, Operator::And),
+            QueryTree::Or(children) => join(children, Operator::Or),
+            QueryTree::Not(child) => Ok(complement(&child.q()?)),
+        }
+    }
+
+    fn leaves(&self) -> Vec<&SolrQuery> {
+        match self {
+            QueryTree::Leaf(query) => vec![&**query],
+            QueryTree::And(children) | QueryTree::Or(children) => {
+                children.iter().flat_map(|c| c.leaves()).collect()
+            }
+            QueryTree::Not(child) => child.leaves(),
+        }
+    }
+}
+
+impl From<SolrQuery> for QueryTree {
+    fn from(query: SolrQuery) -> QueryTree {
+        QueryTree::leaf(query)
+    }
+}
+
+fn join(children: &[QueryTree], operator: Operator) -> Result<String, SolrSubqueryError> {
+    if children.is_empty() {
+        return Err(SolrSubqueryError::EmptyTree);
+    }
+
+    Ok(children
+        .iter()
+        .map(|c| Ok(format!("({})", c.q()?)))
+        .collect::<Result<Vec<_>, SolrSubqueryError>>()?
+        .join(&format!(" {} ", operator)))
+}
+
+/// A negation nested in a group needs a positive clause to match anything
+fn complement(q: &str) -> String {
+    format!("*:* {} ({})", Operator::Not, q)
+}
+
+#[cfg(test)]
+mod query_tree_tests {
+    use super::*;
+    use std::error::Error;
+    use urlencoding::decode;
+
+    fn leaf(q: &str) -> Result<QueryTree, SolrSubqueryError> {
+        Ok(QueryTree::leaf(SolrQuery::new(
+            format!("http://localhost:8983/solr/collection/select?q={}", q).as_str(),
+        )?))
+    }
+
+    #[test]
+    fn should_render_nested_tree() -> Result<(), Box<dyn Error>> {
+        let tree = QueryTree::and(vec![
+            QueryTree::or(vec![leaf("a:1")?, leaf("b:1")?]),
+            QueryTree::not(QueryTree::or(vec![leaf("c:1")?, leaf("d:1")?])),
+        ]);
+
+        let url_string = tree.to_query()?.url.to_string();
+        let result = decode(&url_string)?;
+        let expected = "http://localhost:8983/solr/collection/select?q=((a:1)+OR+(b:1))+AND+(*:*+NOT+((c:1)+OR+(d:1)))";
+
+        assert_eq!(result, expected);
+
+        let inverse_url_string = tree.to_query()?.inverse().url.to_string();
+        let inverse_result = decode(&inverse_url_string)?;
+        let inverse_expected = "http://localhost:8983/solr/collection/select?q=*:*+NOT+(((a:1)+OR+(b:1))+AND+(*:*+NOT+((c:1)+OR+(d:1))))";
+
+        assert_eq!(inverse_result, inverse_expected);
+        assert_eq!(tree.clone().inverse().inverse(), tree);
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_not_render_invalid_tree() -> Result<(), Box<dyn Error>> {
+        assert_eq!(
+            QueryTree::or(vec![]).to_query(),
+            Err(SolrSubqueryError::EmptyTree)
+        );
+
+        let other_host = QueryTree::leaf(SolrQuery::new(
+            "http://otherhost:8983/solr/collection/select?q=*:*",
+        )?);
+        assert!(matches!(
+            QueryTree::and(vec![leaf("a:1")?, other_host]).to_query(),
+            Err(SolrSubqueryError::DifferentsHosts { .. })
+        ));
+
+        Ok(())
+    }
+}
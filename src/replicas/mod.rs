@@ -0,0 +1,162 @@
+use crate::errors::SolrSubqueryError;
+use crate::solr_query::SolrQuery;
+
+use url::Url;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Replica {
+    base: Url,
+    weight: u32,
+    current_weight: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Equivalent Solr nodes receiving requests in a smooth weighted round-robin
+pub struct Replicas {
+    replicas: Vec<Replica>,
+}
+
+impl Replicas {
+    /// Creates the replicas from base URLs (e.g. `http://solr1:8983`) and their weights
+    pub fn new<U: TryInto<Url>>(replicas: Vec<(U, u32)>) -> Result<Replicas, SolrSubqueryError>
+    where
+        <U as TryInto<Url>>::Error: std::error::Error + 'static,
+    {
+        let replicas = replicas
+            .into_iter()
+            .map(|(base, weight)| {
+                let base = base.try_into().map_err(SolrSubqueryError::url_conversion)?;
+
+                Ok(Replica {
+                    base,
+                    weight,
+                    current_weight: 0,
+                })
+            })
+            .collect::<Result<Vec<_>, SolrSubqueryError>>()?;
+
+        if replicas.iter().all(|r| r.weight == 0) {
+            return Err(SolrSubqueryError::NoReplica);
+        }
+
+        Ok(Replicas { replicas })
+    }
+
+    /// Gets the number of replicas receiving requests
+    pub fn len(&self) -> usize {
+        self.replicas.iter().filter(|r| r.weight > 0).count()
+    }
+
+    /// Checks if no replica receives requests, which `new` prevents
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Gets the base URL of the replica receiving the next request
+    pub fn next_base(&mut self) -> &Url {
+        self.next_base_excluding(&[])
+            .expect("replicas have a positive weight")
+    }
+
+    /// Gets the base URL of the replica receiving the next request among the ones not excluded,
+    /// e.g. because they already failed, `None` if every replica is excluded
+    pub fn next_base_excluding(&mut self, excluded: &[Url]) -> Option<&Url> {
+        let mut candidates = self
+            .replicas
+            .iter_mut()
+            .filter(|r| r.weight > 0 && !excluded.contains(&r.base))
+            .collect::<Vec<_>>();
+        let total_weight: i64 = candidates.iter().map(|r| r.weight as i64).sum();
+
+        for replica in candidates.iter_mut() {
+            replica.current_weight += replica.weight as i64;
+        }
+
+        let selected = candidates
+            .into_iter()
+            .rev()
+            .max_by_key(|r| r.current_weight)?;
+
+        selected.current_weight -= total_weight;
+        Some(&selected.base)
+    }
+
+    /// Sends the query to the replica receiving the next request
+    pub fn route(&mut self, query: &SolrQuery) -> Result<SolrQuery, SolrSubqueryError> {
+        let base = self.next_base().clone();
+        query.with_origin(&base)
+    }
+}
+
+#[cfg(test)]
+mod replicas_tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn should_balance_requests_by_weight() -> Result<(), Box<dyn Error>> {
+        let mut replicas = Replicas::new(vec![
+            ("http://solr1:8983", 2),
+            ("http://solr2:8984", 1),
+            ("http://solr3:8985", 0),
+        ])?;
+
+        let hosts = (0..6)
+            .map(|_| {
+                replicas
+                    .next_base()
+                    .host_str()
+                    .unwrap_or_default()
+                    .to_string()
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            hosts,
+            vec!["solr1", "solr2", "solr1", "solr1", "solr2", "solr1"]
+        );
+
+        let query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=*:*")?;
+        let routed = replicas.route(&query)?;
+
+        assert_eq!(
+            routed.url.as_str(),
+            "http://solr1:8983/solr/collection/select?q=*:*"
+        );
+        assert_eq!(routed.inverse().url.host_str(), Some("solr1"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_skip_excluded_replicas() -> Result<(), Box<dyn Error>> {
+        let mut replicas = Replicas::new(vec![("http://solr1:8983", 3), ("http://solr2:8984", 1)])?;
+        let failed = vec![Url::parse("http://solr1:8983")?];
+
+        for _ in 0..3 {
+            assert_eq!(
+                replicas
+                    .next_base_excluding(&failed)
+                    .and_then(|b| b.host_str()),
+                Some("solr2")
+            );
+        }
+        assert_eq!(replicas.next_base().host_str(), Some("solr1"));
+
+        let failed = vec![
+            Url::parse("http://solr1:8983")?,
+            Url::parse("http://solr2:8984")?,
+        ];
+        assert_eq!(replicas.next_base_excluding(&failed), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_not_create_replicas_without_weight() {
+        assert_eq!(
+            Replicas::new(vec![("http://solr1:8983", 0)]),
+            Err(SolrSubqueryError::NoReplica)
+        );
+    }
+}
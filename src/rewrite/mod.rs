@@ -0,0 +1,206 @@
+use crate::errors::SolrSubqueryError;
+use crate::query_chain::QueryChain;
+use crate::solr_query::SolrQuery;
+
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// Term mappings harmonizing the vocabulary of subqueries written by different curators
+///
+/// Terms are matched against whole clause values, quoted or not, so `colour` is
+/// rewritten in `title:colour` and `"colour"` but not in `title:colours`.
+pub struct TermRewriter {
+    /// Replacement terms by field (`None` for any field) and term
+    mappings: BTreeMap<(Option<String>, String), Vec<String>>,
+}
+
+impl TermRewriter {
+    pub fn new() -> TermRewriter {
+        TermRewriter::default()
+    }
+
+    /// Replaces a term by another one in any field, e.g. `colour` by `color`
+    pub fn map(self, from: &str, to: &str) -> TermRewriter {
+        self.insert(None, from, &[to])
+    }
+
+    /// Replaces a term by any of several ones in any field, e.g. a controlled vocabulary entry
+    /// by its narrower terms
+    pub fn expand(self, from: &str, to: &[&str]) -> TermRewriter {
+        self.insert(None, from, to)
+    }
+
+    /// Replaces a term by any of several ones in the given field only
+    ///
+    /// Mappings of a field take precedence over the ones of any field.
+    pub fn expand_in(self, field: &str, from: &str, to: &[&str]) -> TermRewriter {
+        self.insert(Some(field), from, to)
+    }
+
+    fn insert(mut self, field: Option<&str>, from: &str, to: &[&str]) -> TermRewriter {
+        self.mappings.insert(
+            (field.map(|f| f.to_string()), from.to_string()),
+            to.iter().map(|t| t.to_string()).collect(),
+        );
+        self
+    }
+
+    /// Rewrites a clause such as `title:colour` or `-"colour"`, if one of its terms is mapped
+    pub fn rewrite_clause(&self, clause: &str) -> Option<String> {
+        let (modifier, clause) = match clause.strip_prefix(['+', '-']) {
+            Some(rest) => (&clause[..1], rest),
+            None => ("", clause),
+        };
+        let (field, value) = match clause.split_once(':') {
+            Some((field, value)) if !field.starts_with('"') => (Some(field), value),
+            _ => (None, clause),
+        };
+        let (term, quoted) = match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+            Some(term) => (term, true),
+            None => (value, false),
+        };
+
+        let replacements = field
+            .and_then(|f| self.mappings.get(&(Some(f.to_string()), term.to_string())))
+            .or_else(|| self.mappings.get(&(None, term.to_string())))?;
+
+        let render = |term: &String| match quoted || term.contains(char::is_whitespace) {
+            true => format!("\"{}\"", term),
+            false => term.to_string(),
+        };
+        let value = match replacements.as_slice() {
+            [term] => render(term),
+            terms => format!(
+                "({})",
+                terms.iter().map(render).collect::<Vec<_>>().join(" OR ")
+            ),
+        };
+
+        Some(match field {
+            Some(field) => format!("{}{}:{}", modifier, field, value),
+            None => format!("{}{}", modifier, value),
+        })
+    }
+}
+
+impl SolrQuery {
+    /// Rewrites the mapped terms of the query and of its inverse
+    ///
+    /// Clauses of groups using implicit operators are rewritten in place, see
+    /// `QueryAst::map_clauses`.
+    pub fn rewrite_terms(&self, rewriter: &TermRewriter) -> Result<SolrQuery, SolrSubqueryError> {
+        self.rewrite_q(|ast| ast.map_clauses(&|clause| rewriter.rewrite_clause(clause)))
+    }
+}
+
+impl QueryChain {
+    /// Rewrites the mapped terms of the subqueries not merged yet
+    ///
+    /// See `SolrQuery::rewrite_terms`.
+    pub fn with_rewriter(
+        mut self,
+        rewriter: &TermRewriter,
+    ) -> Result<QueryChain, SolrSubqueryError> {
+        self.map_pending(|query| query.rewrite_terms(rewriter))?;
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod rewrite_tests {
+    use super::*;
+    use crate::solr_query::QueryParam;
+    use std::error::Error;
+
+    #[test]
+    fn should_rewrite_mapped_terms() {
+        let rewriter = TermRewriter::new()
+            .map("colour", "color")
+            .expand("fruit", &["apple", "pear"])
+            .expand_in("subject", "fruit", &["citrus fruit"]);
+
+        assert_eq!(
+            rewriter.rewrite_clause("title:colour").as_deref(),
+            Some("title:color")
+        );
+        assert_eq!(
+            rewriter.rewrite_clause("-\"colour\"").as_deref(),
+            Some("-\"color\"")
+        );
+        assert_eq!(
+            rewriter.rewrite_clause("tags:fruit").as_deref(),
+            Some("tags:(apple OR pear)")
+        );
+        assert_eq!(
+            rewriter.rewrite_clause("subject:fruit").as_deref(),
+            Some("subject:\"citrus fruit\"")
+        );
+        assert_eq!(rewriter.rewrite_clause("title:colours"), None);
+    }
+
+    #[test]
+    fn should_harmonize_chain_subqueries() -> Result<(), Box<dyn Error>> {
+        let rewriter = TermRewriter::new().map("colour", "color");
+
+        let first_query = SolrQuery::new(
+            "http://localhost:8983/solr/collection/select?q=title:colour AND lang:en",
+        )?;
+        let mut query_chain = QueryChain::new(vec![first_query]);
+        query_chain.add_subquery("http://localhost:8983/solr/collection/select?q=tags:colour")?;
+
+        let merged = query_chain.with_rewriter(&rewriter)?.collapse()?;
+        assert_eq!(
+            merged.url.params("q"),
+            vec!["(title:color AND lang:en) AND (tags:color)"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_forget_counts_of_rewritten_steps() -> Result<(), Box<dyn Error>> {
+        let rewriter = TermRewriter::new().map("colour", "color");
+
+        let first_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=lang:en")?;
+        let mut query_chain = QueryChain::new(vec![first_query]);
+        query_chain.add_subquery("http://localhost:8983/solr/collection/select?q=tags:colour")?;
+        query_chain.cache_count(0, 10);
+        query_chain.cache_count(1, 4);
+
+        let mut query_chain = query_chain.with_rewriter(&rewriter)?;
+        assert_eq!(query_chain.cached_count(0), Some(10));
+        assert_eq!(query_chain.cached_count(1), None);
+        assert_eq!(
+            query_chain.count_or_insert_with(1, || Ok::<_, SolrSubqueryError>(6))?,
+            6
+        );
+
+        let query_chain = query_chain.with_rewriter(&rewriter)?;
+        assert_eq!(query_chain.cached_count(1), Some(6));
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_rewrite_implicit_operator_groups() -> Result<(), Box<dyn Error>> {
+        let rewriter = TermRewriter::new()
+            .map("colour", "color")
+            .expand("fruit", &["apple", "pear"]);
+
+        let query = SolrQuery::new(
+            "http://localhost:8983/solr/collection/select?q=title:colour lang:en OR -tags:fruit",
+        )?;
+        let rewritten = query.rewrite_terms(&rewriter)?;
+
+        assert_eq!(
+            rewritten.url.params("q"),
+            vec!["title:color lang:en OR -tags:(apple OR pear)"]
+        );
+        assert_eq!(
+            rewritten.inverse().url.params("q"),
+            vec!["NOT (title:color lang:en OR -tags:(apple OR pear))"]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+use crate::solr_query::SolrQuery;
+
+/// Boost parameters of the (e)dismax parsers, unioned when merging queries
+///
+/// Boosts are additive (`bq`, `bf`) or multiplicative (`boost`), so the boosts of
+/// both sides apply to the merged query. Queries nested with
+/// [`EdismaxStrategy::Nest`](crate::EdismaxStrategy::Nest) keep their own boosts instead.
+pub const BOOST_PARAMS: [&str; 3] = ["bq", "bf", "boost"];
+
+impl SolrQuery {
+    /// Adds a query whose matches get a higher score, e.g. `lang:en^2`
+    pub fn boost_query(self, query: &str) -> SolrQuery {
+        self.add_param("bq", query)
+    }
+
+    /// Adds a function query whose value is added to the score
+    pub fn boost_function(self, function: &str) -> SolrQuery {
+        self.add_param("bf", function)
+    }
+
+    /// Adds a function query whose value multiplies the score
+    pub fn multiplicative_boost(self, function: &str) -> SolrQuery {
+        self.add_param("boost", function)
+    }
+}
+
+/// Function query favoring recent dates, halving the boost of documents a year old
+pub fn recency_boost(date_field: &str) -> String {
+    format!("recip(ms(NOW,{}),3.16e-11,1,1)", date_field)
+}
+
+#[cfg(test)]
+mod boost_tests {
+    use super::*;
+    use crate::solr_query::{EdismaxStrategy, SubQuery};
+    use std::error::Error;
+    use urlencoding::decode;
+
+    #[test]
+    fn should_union_boosts_when_merging() -> Result<(), Box<dyn Error>> {
+        let first_query = SolrQuery::new(
+            "http://localhost:8983/solr/collection/select?q=rust&defType=edismax&qf=title",
+        )?
+        .multiplicative_boost(&recency_boost("published"));
+        let second_query = SolrQuery::new(
+            "http://localhost:8983/solr/collection/select?q=solr&defType=edismax&qf=title",
+        )?
+        .boost_query("lang:en^2");
+
+        let merged = first_query.inner_join_with(&second_query, EdismaxStrategy::Reject)?;
+        let url_string = merged.url.to_string();
+        let result = decode(&url_string)?;
+
+        assert_eq!(
+            result,
+            "http://localhost:8983/solr/collection/select?q=(rust)+AND+(solr)&defType=edismax&qf=title&bq=lang:en^2&boost=recip(ms(NOW,published),3.16e-11,1,1)"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_keep_boosts_nested() -> Result<(), Box<dyn Error>> {
+        let first_query = SolrQuery::new(
+            "http://localhost:8983/solr/collection/select?q=rust&defType=edismax&qf=title",
+        )?
+        .boost_function("log(popularity)");
+        let second_query =
+            SolrQuery::new("http://localhost:8983/solr/collection/select?q=lang:en")?;
+
+        let merged = first_query.inner_join_with(&second_query, EdismaxStrategy::Nest)?;
+        let url_string = merged.url.to_string();
+        let result = decode(&url_string)?;
+
+        assert_eq!(
+            result,
+            "http://localhost:8983/solr/collection/select?q=(_query_:\"{!edismax+qf='title'+bf='log(popularity)'}rust\")+AND+(lang:en)"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,292 @@
+use crate::errors::{redact, SolrSubqueryError};
+use crate::solr_query::{QueryParam, SolrQuery};
+
+use url::Url;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// Where a query is sent: a Solr instance, one of its cores or collections and a request handler
+///
+/// Path segments are kept percent-encoded, as written in the URL. The endpoint of a query is
+/// split when the query is built.
+pub struct SolrEndpoint {
+    pub scheme: String,
+    pub host: Option<String>,
+    /// `None` for the default port of the scheme
+    pub port: Option<u16>,
+    /// Path of the Solr instance, e.g. `/solr`
+    pub base_path: String,
+    pub core: String,
+    /// Request handler, whose segments are separated by `/`, e.g. `admin/ping`
+    pub handler: String,
+}
+
+impl SolrEndpoint {
+    /// Splits the URL of a query, its last path segment being the request handler and the one
+    /// before the core
+    ///
+    /// A handler with several segments such as `admin/ping` can't be told apart from the core in
+    /// an URL, see [`SolrQuery::from_parts`] to build queries sent to one.
+    pub fn from_url(url: &Url) -> SolrEndpoint {
+        SolrEndpoint::split(url, 1)
+    }
+
+    /// Splits the URL of a query, its `handler_segments` last path segments being the request handler
+    pub(crate) fn split(url: &Url, handler_segments: usize) -> SolrEndpoint {
+        let mut segments = url
+            .path_segments()
+            .map(|s| s.collect::<Vec<_>>())
+            .unwrap_or_default();
+        if segments.last() == Some(&"") {
+            segments.pop();
+        }
+
+        let handler = segments.split_off(segments.len().saturating_sub(handler_segments));
+        let core = segments.pop().unwrap_or_default();
+
+        SolrEndpoint {
+            scheme: url.scheme().to_string(),
+            host: url.host_str().map(|h| h.to_string()),
+            port: url.port(),
+            base_path: segments.iter().map(|s| format!("/{}", s)).collect(),
+            core: core.to_string(),
+            handler: handler.join("/"),
+        }
+    }
+
+    /// Gets the same endpoint on the origin (scheme, host and port) of an URL
+    pub(crate) fn with_origin(&self, url: &Url) -> SolrEndpoint {
+        SolrEndpoint {
+            scheme: url.scheme().to_string(),
+            host: url.host_str().map(|h| h.to_string()),
+            port: url.port(),
+            ..self.clone()
+        }
+    }
+
+    /// Checks if both endpoints target the same core of the same instance, whatever their handlers
+    pub fn same_core(&self, other: &SolrEndpoint) -> bool {
+        SolrEndpoint {
+            handler: other.handler.clone(),
+            ..self.clone()
+        } == *other
+    }
+}
+
+impl std::fmt::Display for SolrEndpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}://{}",
+            self.scheme,
+            self.host.as_deref().unwrap_or_default()
+        )?;
+        if let Some(port) = self.port {
+            write!(f, ":{}", port)?;
+        }
+        write!(f, "{}/{}/{}", self.base_path, self.core, self.handler)
+    }
+}
+
+impl SolrQuery {
+    /// Gets the endpoint the query is sent to
+    pub fn endpoint(&self) -> &SolrEndpoint {
+        &self.endpoint
+    }
+
+    /// Splits the endpoint again, its `handler_segments` last path segments being the request handler
+    pub(crate) fn with_handler_segments(mut self, handler_segments: usize) -> SolrQuery {
+        self.endpoint = SolrEndpoint::split(&self.url, handler_segments);
+        self
+    }
+
+    /// Gets the number of path segments of the request handler
+    pub(crate) fn handler_segments(&self) -> usize {
+        self.endpoint.handler.split('/').count()
+    }
+
+    /// Gets the core, or collection, the query is sent to
+    pub(crate) fn collection(&self) -> Result<&str, SolrSubqueryError> {
+        match self.endpoint.core.as_str() {
+            "" => Err(SolrSubqueryError::invalid_url(redact(&self.url))),
+            core => Ok(core),
+        }
+    }
+
+    /// Gets the fields listed by the `fl` parameters of the query
+    pub(crate) fn fl_fields(&self) -> Vec<String> {
+        self.url
+            .params("fl")
+            .iter()
+            .flat_map(|fl| fl.split(',').map(|f| f.trim().to_string()))
+            .filter(|f| !f.is_empty())
+            .collect()
+    }
+
+    /// Gets the URL, without parameters, of another handler of the core the query is sent to
+    pub(crate) fn core_handler_url(&self, handler: &str) -> Url {
+        let endpoint = &self.endpoint;
+        self.with_path(&format!(
+            "{}/{}/{}",
+            endpoint.base_path, endpoint.core, handler
+        ))
+    }
+
+    /// Gets the URL of the query with another path and without parameters
+    pub(crate) fn with_path(&self, path: &str) -> Url {
+        let mut url = self.url.clone();
+        url.set_query(None);
+        url.set_path(path);
+        url
+    }
+}
+
+#[cfg(test)]
+mod endpoint_tests {
+    use super::*;
+    use crate::query_chain::QueryChain;
+    use crate::solr_query::{JoinPolicy, SubQuery};
+    use crate::streaming::StreamingExpression;
+    use std::error::Error;
+
+    #[test]
+    fn should_split_endpoints() -> Result<(), Box<dyn Error>> {
+        let endpoint = SolrQuery::new("https://solr.example.org/solr/books/select/?q=*:*")?
+            .endpoint()
+            .clone();
+
+        assert_eq!(
+            endpoint,
+            SolrEndpoint {
+                scheme: "https".to_string(),
+                host: Some("solr.example.org".to_string()),
+                port: None,
+                base_path: "/solr".to_string(),
+                core: "books".to_string(),
+                handler: "select".to_string(),
+            }
+        );
+        assert_eq!(
+            endpoint.to_string(),
+            "https://solr.example.org/solr/books/select"
+        );
+
+        let other =
+            SolrEndpoint::from_url(&Url::parse("https://solr.example.org/solr/books/query")?);
+        assert!(endpoint.same_core(&other));
+        assert_ne!(endpoint, other);
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_derive_urls_from_multi_segment_handlers() -> Result<(), Box<dyn Error>> {
+        let ping =
+            SolrQuery::from_parts("http://localhost:8983/solr", "books", "admin/ping", "*:*")?
+                .with_param("fl", "id,title");
+
+        assert_eq!(ping.to_sql()?, "SELECT id, title FROM books");
+        assert_eq!(
+            StreamingExpression::search(&ping, "id")?
+                .stream_url(&ping)
+                .path(),
+            "/solr/books/stream"
+        );
+        assert_eq!(
+            StreamingExpression::search(&ping, "id")?.to_string(),
+            "search(books, q=\"*:*\", fl=\"id,title\", sort=\"id asc\", qt=\"/export\")"
+        );
+        assert_eq!(
+            ping.terms_for_field("subject").as_str(),
+            "http://localhost:8983/solr/books/terms?terms=true&terms.fl=subject&terms.limit=-1&wt=json"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_report_different_handlers() -> Result<(), Box<dyn Error>> {
+        let select = SolrQuery::new("http://localhost:8983/solr/books/select?q=a:1")?;
+        let query = SolrQuery::new("http://localhost:8983/solr/books/query?q=b:1")?;
+
+        let error = select.inner_join(&query).unwrap_err();
+        assert!(matches!(
+            &error,
+            SolrSubqueryError::DifferentHandlers { collection, left, right, .. }
+                if collection == "books" && left == "select" && right == "query"
+        ));
+        assert_eq!(
+            error.to_string(),
+            "Requests target the same collection books with different request handlers: select and query"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_keep_handlers_with_several_segments() -> Result<(), Box<dyn Error>> {
+        let ping =
+            SolrQuery::from_parts("http://localhost:8983/solr", "books", "admin/ping", "*:*")?;
+        assert_eq!(ping.endpoint().core, "books");
+        assert_eq!(ping.endpoint().handler, "admin/ping");
+        assert_eq!(
+            SolrEndpoint::from_url(&ping.url).handler,
+            "ping",
+            "the split can't be guessed from the URL"
+        );
+
+        let select = SolrQuery::from_parts("http://localhost:8983/solr", "books", "select", "a:1")?;
+        assert!(matches!(
+            ping.inner_join(&select),
+            Err(SolrSubqueryError::DifferentHandlers { collection, left, .. })
+                if collection == "books" && left == "admin/ping"
+        ));
+
+        let rewritten = ping.normalize()?;
+        assert_eq!(rewritten.endpoint(), ping.endpoint());
+
+        let restored = QueryChain::from_json(&QueryChain::new(vec![ping.clone()]).to_json())?;
+        assert_eq!(restored.pending().next(), Some(&ping));
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_report_different_schemes() -> Result<(), Box<dyn Error>> {
+        let http = SolrQuery::new("http://localhost:8983/solr/books/select?q=a:1")?;
+        let https = SolrQuery::new("https://localhost:8983/solr/books/select?q=b:1")?;
+
+        assert!(matches!(
+            http.inner_join(&https),
+            Err(SolrSubqueryError::DifferentSchemes { left, right, .. })
+                if left == "http" && right == "https"
+        ));
+        assert!(matches!(
+            http.clone()
+                .with_join_policy(JoinPolicy::IgnoreHost)
+                .inner_join(&https),
+            Err(SolrSubqueryError::DifferentSchemes { .. })
+        ));
+
+        let rebased = http.with_origin(&Url::parse("https://localhost:8983")?)?;
+        assert_eq!(rebased.endpoint().scheme, "https");
+        assert!(rebased.inner_join(&https).is_ok());
+
+        Ok(())
+    }
+    #[test]
+    fn should_keep_the_endpoint_of_the_merged_url() -> Result<(), Box<dyn Error>> {
+        let first = SolrQuery::new("http://localhost:8983/solr/books/select?q=a:1")?
+            .with_join_policy(JoinPolicy::IgnorePort);
+        let second = SolrQuery::new("http://localhost:8984/solr/books/select?q=b:1")?;
+
+        let joined = first.inner_join(&second)?;
+        assert_eq!(joined.endpoint(), &SolrEndpoint::from_url(&joined.url));
+        assert!(joined
+            .inner_join(&SolrQuery::new(
+                "http://localhost:8983/solr/books/select?q=c:1"
+            )?)
+            .is_ok());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,112 @@
+use crate::errors::SolrSubqueryError;
+use crate::solr_query::{QueryParam, SolrQuery};
+
+use url::Url;
+
+/// Parameters ignored by the export handler
+const PAGINATION_PARAMS: [&str; 3] = ["rows", "start", "cursorMark"];
+
+impl SolrQuery {
+    /// Rewrites the query for the `/export` handler, which streams the full sorted result set
+    ///
+    /// The export handler requires explicit `sort` and `fl` parameters on docValues
+    /// fields: wildcards and `score` are rejected. Pagination parameters are dropped.
+    pub fn to_export(&self) -> Result<SolrQuery, SolrSubqueryError> {
+        let invalid = |reason: &str| SolrSubqueryError::InvalidExport(reason.to_string());
+
+        let fl = self.url.params("fl");
+        if fl.is_empty() {
+            return Err(invalid("missing fl"));
+        }
+        if fl
+            .iter()
+            .flat_map(|f| f.split(|c: char| c == ',' || c.is_whitespace()))
+            .any(|f| f.contains('*') || f == "score")
+        {
+            return Err(invalid("fl can't use wildcards or score"));
+        }
+
+        let sort = self.url.params("sort");
+        if sort.is_empty() {
+            return Err(invalid("missing sort"));
+        }
+        if sort.iter().any(|s| {
+            s.split(',')
+                .any(|s| s.split_whitespace().next() == Some("score"))
+        }) {
+            return Err(invalid("sort can't use score"));
+        }
+
+        let export = |url: &Url| {
+            let mut exported = url.clone();
+            if let Ok(mut segments) = exported.path_segments_mut() {
+                for _ in 0..self.handler_segments() {
+                    segments.pop();
+                }
+                segments.push("export");
+            }
+
+            let parameters = url
+                .query_pairs()
+                .filter(|(k, _)| !PAGINATION_PARAMS.contains(&k.as_ref()))
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<Vec<_>>();
+            exported.query_pairs_mut().clear().extend_pairs(parameters);
+            exported
+        };
+
+        Ok(SolrQuery {
+            url: export(&self.url),
+            negation: export(&self.negation),
+            ..self.clone()
+        }
+        .with_handler_segments(1))
+    }
+}
+
+#[cfg(test)]
+mod export_tests {
+    use super::*;
+    use std::error::Error;
+    use urlencoding::decode;
+
+    #[test]
+    fn should_rewrite_query_for_export() -> Result<(), Box<dyn Error>> {
+        let query = SolrQuery::new(
+            "http://localhost:8983/solr/collection/select?q=lang:en&fl=id,year&sort=id+asc&rows=10",
+        )?;
+
+        let url_string = query.to_export()?.url.to_string();
+        let result = decode(&url_string)?;
+
+        assert_eq!(
+            result,
+            "http://localhost:8983/solr/collection/export?q=lang:en&fl=id,year&sort=id+asc"
+        );
+
+        let query = SolrQuery::new(
+            "http://localhost:8983/solr/collection/select?q=lang:en&fl=id,score_avg&sort=score_avg+desc",
+        )?;
+        assert!(query.to_export().is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_reject_unexportable_queries() -> Result<(), Box<dyn Error>> {
+        for url in [
+            "http://localhost:8983/solr/collection/select?q=lang:en&sort=id+asc",
+            "http://localhost:8983/solr/collection/select?q=lang:en&fl=id,*_s&sort=id+asc",
+            "http://localhost:8983/solr/collection/select?q=lang:en&fl=id",
+            "http://localhost:8983/solr/collection/select?q=lang:en&fl=id&sort=score+desc",
+            "http://localhost:8983/solr/collection/select?q=lang:en&fl=id&sort=id+asc,score+desc",
+        ] {
+            assert!(matches!(
+                SolrQuery::new(url)?.to_export(),
+                Err(SolrSubqueryError::InvalidExport(_))
+            ));
+        }
+
+        Ok(())
+    }
+}
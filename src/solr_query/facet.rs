@@ -0,0 +1,56 @@
+use crate::solr_query::SolrQuery;
+
+impl SolrQuery {
+    /// Enables faceting
+    pub fn facet(self) -> SolrQuery {
+        self.with_param("facet", "true")
+    }
+
+    /// Adds a field facet, enabling faceting
+    pub fn facet_field(self, field: &str) -> SolrQuery {
+        self.facet().add_param("facet.field", field)
+    }
+
+    /// Adds a query facet, enabling faceting
+    pub fn facet_query(self, query: &str) -> SolrQuery {
+        self.facet().add_param("facet.query", query)
+    }
+
+    /// Sets the maximum number of buckets of the field facets, `-1` for no limit
+    pub fn facet_limit(self, limit: i64) -> SolrQuery {
+        self.with_param("facet.limit", &limit.to_string())
+    }
+
+    /// Sets the minimum count of the returned buckets
+    pub fn facet_mincount(self, mincount: u64) -> SolrQuery {
+        self.with_param("facet.mincount", &mincount.to_string())
+    }
+}
+
+#[cfg(test)]
+mod facet_tests {
+    use crate::solr_query::{SolrQuery, SubQuery};
+    use std::error::Error;
+    use urlencoding::decode;
+
+    #[test]
+    fn should_keep_facets_when_merging() -> Result<(), Box<dyn Error>> {
+        let first_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=a:1")?
+            .facet_field("lang")
+            .facet_limit(50)
+            .facet_mincount(1);
+        let second_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=b:1")?
+            .facet_field("year")
+            .facet_limit(10);
+
+        let url_string = first_query.inner_join(&second_query)?.url.to_string();
+        let result = decode(&url_string)?;
+
+        assert_eq!(
+            result,
+            "http://localhost:8983/solr/collection/select?q=(a:1)+AND+(b:1)&facet=true&facet.field=year&facet.limit=10&facet.field=lang&facet.mincount=1"
+        );
+
+        Ok(())
+    }
+}
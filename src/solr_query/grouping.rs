@@ -0,0 +1,140 @@
+use crate::errors::SolrSubqueryError;
+use crate::solr_query::SolrQuery;
+
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+/// Groups of the documents sharing a value of a field
+pub struct FieldGroups {
+    pub field: String,
+    /// Number of documents matched by the query
+    pub matches: u64,
+    /// Number of groups, only returned with `group.ngroups=true`
+    pub ngroups: Option<u64>,
+    pub groups: Vec<Group>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Documents sharing a value of the grouped field
+pub struct Group {
+    /// Value of the field, `null` for the documents without it
+    pub value: Value,
+    pub num_found: u64,
+    pub docs: Vec<Value>,
+}
+
+impl SolrQuery {
+    /// Groups the results by the values of a field
+    pub fn group_by(self, field: &str) -> SolrQuery {
+        self.with_param("group", "true")
+            .add_param("group.field", field)
+    }
+
+    /// Keeps a single document per value of a field with the collapsing query parser
+    pub fn collapse(self, field: &str) -> SolrQuery {
+        self.add_param("fq", &format!("{{!collapse field={}}}", field))
+    }
+}
+
+/// Parses the `grouped` section of a response to a grouped query
+pub fn grouped_results(response: &Value) -> Result<Vec<FieldGroups>, SolrSubqueryError> {
+    let invalid = |what: &str| SolrSubqueryError::InvalidResponse(format!("missing {}", what));
+
+    response["grouped"]
+        .as_object()
+        .ok_or_else(|| invalid("grouped"))?
+        .iter()
+        .map(|(field, grouped)| {
+            let groups = grouped["groups"]
+                .as_array()
+                .ok_or_else(|| invalid("groups"))?
+                .iter()
+                .map(|group| {
+                    Ok(Group {
+                        value: group["groupValue"].clone(),
+                        num_found: group["doclist"]["numFound"]
+                            .as_u64()
+                            .ok_or_else(|| invalid("numFound"))?,
+                        docs: group["doclist"]["docs"]
+                            .as_array()
+                            .cloned()
+                            .unwrap_or_default(),
+                    })
+                })
+                .collect::<Result<Vec<_>, SolrSubqueryError>>()?;
+
+            Ok(FieldGroups {
+                field: field.to_string(),
+                matches: grouped["matches"]
+                    .as_u64()
+                    .ok_or_else(|| invalid("matches"))?,
+                ngroups: grouped["ngroups"].as_u64(),
+                groups,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod grouping_tests {
+    use super::*;
+    use serde_json::json;
+    use std::error::Error;
+    use urlencoding::decode;
+
+    #[test]
+    fn should_group_and_collapse() -> Result<(), Box<dyn Error>> {
+        let query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=*:*")?
+            .group_by("lang")
+            .collapse("isbn");
+
+        let url_string = query.url.to_string();
+        let result = decode(&url_string)?;
+
+        assert_eq!(
+            result,
+            "http://localhost:8983/solr/collection/select?q=*:*&group=true&group.field=lang&fq={!collapse+field=isbn}"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_parse_grouped_response() -> Result<(), Box<dyn Error>> {
+        let response = json!({
+            "grouped": {
+                "lang": {
+                    "matches": 3,
+                    "groups": [
+                        {"groupValue": "en", "doclist": {"numFound": 2, "start": 0, "docs": [{"id": "1"}]}},
+                        {"groupValue": null, "doclist": {"numFound": 1, "start": 0, "docs": [{"id": "3"}]}},
+                    ],
+                },
+            },
+        });
+
+        assert_eq!(
+            grouped_results(&response)?,
+            vec![FieldGroups {
+                field: "lang".to_string(),
+                matches: 3,
+                ngroups: None,
+                groups: vec![
+                    Group {
+                        value: json!("en"),
+                        num_found: 2,
+                        docs: vec![json!({"id": "1"})],
+                    },
+                    Group {
+                        value: Value::Null,
+                        num_found: 1,
+                        docs: vec![json!({"id": "3"})],
+                    },
+                ],
+            }]
+        );
+        assert!(grouped_results(&json!({"response": {}})).is_err());
+
+        Ok(())
+    }
+}
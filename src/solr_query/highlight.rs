@@ -0,0 +1,54 @@
+use crate::solr_query::SolrQuery;
+
+impl SolrQuery {
+    /// Enables highlighting
+    pub fn highlight(self) -> SolrQuery {
+        self.with_param("hl", "true")
+    }
+
+    /// Adds a field to highlight, enabling highlighting
+    ///
+    /// Each field is sent as its own `hl.fl` value so the fields of merged
+    /// queries can be unioned.
+    pub fn highlight_field(self, field: &str) -> SolrQuery {
+        self.highlight().add_param("hl.fl", field)
+    }
+
+    /// Sets the maximum number of snippets per field
+    pub fn highlight_snippets(self, snippets: u32) -> SolrQuery {
+        self.with_param("hl.snippets", &snippets.to_string())
+    }
+
+    /// Sets the size of the snippets in characters, `0` to highlight whole fields
+    pub fn highlight_fragsize(self, fragsize: u32) -> SolrQuery {
+        self.with_param("hl.fragsize", &fragsize.to_string())
+    }
+}
+
+#[cfg(test)]
+mod highlight_tests {
+    use crate::solr_query::{SolrQuery, SubQuery};
+    use std::error::Error;
+    use urlencoding::decode;
+
+    #[test]
+    fn should_keep_highlighting_when_merging() -> Result<(), Box<dyn Error>> {
+        let first_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=a:1")?
+            .highlight_field("title")
+            .highlight_field("abstract")
+            .highlight_snippets(3);
+        let second_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=b:1")?
+            .highlight_field("title")
+            .highlight_fragsize(200);
+
+        let url_string = first_query.inner_join(&second_query)?.url.to_string();
+        let result = decode(&url_string)?;
+
+        assert_eq!(
+            result,
+            "http://localhost:8983/solr/collection/select?q=(a:1)+AND+(b:1)&hl=true&hl.fl=title&hl.fragsize=200&hl.fl=abstract&hl.snippets=3"
+        );
+
+        Ok(())
+    }
+}
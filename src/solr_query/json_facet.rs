@@ -0,0 +1,252 @@
+use crate::solr_query::{QueryParam, SolrQuery};
+
+use serde_json::{json, Map, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+/// A facet of the JSON Facet API
+pub enum JsonFacet {
+    /// Buckets of the values of a field
+    Terms {
+        field: String,
+        options: Map<String, Value>,
+        facets: Vec<(String, JsonFacet)>,
+    },
+    /// Buckets of consecutive ranges of a numeric or date field
+    Range {
+        field: String,
+        start: Value,
+        end: Value,
+        gap: Value,
+        options: Map<String, Value>,
+        facets: Vec<(String, JsonFacet)>,
+    },
+    /// A single bucket of the documents matched by a query
+    Query {
+        q: String,
+        facets: Vec<(String, JsonFacet)>,
+    },
+    /// An aggregation such as `avg(price)` or `unique(author)`
+    Stat(String),
+}
+
+impl JsonFacet {
+    pub fn terms(field: &str) -> JsonFacet {
+        JsonFacet::Terms {
+            field: field.to_string(),
+            options: Map::new(),
+            facets: Vec::new(),
+        }
+    }
+
+    pub fn range<V: Into<Value>>(field: &str, start: V, end: V, gap: V) -> JsonFacet {
+        JsonFacet::Range {
+            field: field.to_string(),
+            start: start.into(),
+            end: end.into(),
+            gap: gap.into(),
+            options: Map::new(),
+            facets: Vec::new(),
+        }
+    }
+
+    pub fn query(q: &str) -> JsonFacet {
+        JsonFacet::Query {
+            q: q.to_string(),
+            facets: Vec::new(),
+        }
+    }
+
+    pub fn stat(aggregation: &str) -> JsonFacet {
+        JsonFacet::Stat(aggregation.to_string())
+    }
+
+    /// Sets the maximum number of buckets of a terms facet
+    pub fn limit(self, limit: i64) -> JsonFacet {
+        self.option("limit", limit.into())
+    }
+
+    /// Sets the minimum count of the returned buckets
+    pub fn mincount(self, mincount: u64) -> JsonFacet {
+        self.option("mincount", mincount.into())
+    }
+
+    /// Sets the order of the buckets of a terms facet, e.g. `count desc`
+    pub fn sort(self, sort: &str) -> JsonFacet {
+        self.option("sort", sort.into())
+    }
+
+    /// Sets any other option of a terms or range facet, ignored by the other facets
+    pub fn option(mut self, name: &str, value: Value) -> JsonFacet {
+        if let JsonFacet::Terms { options, .. } | JsonFacet::Range { options, .. } = &mut self {
+            options.insert(name.to_string(), value);
+        }
+        self
+    }
+
+    /// Computes a facet in each bucket, ignored by stat facets
+    pub fn subfacet(mut self, name: &str, facet: JsonFacet) -> JsonFacet {
+        if let JsonFacet::Terms { facets, .. }
+        | JsonFacet::Range { facets, .. }
+        | JsonFacet::Query { facets, .. } = &mut self
+        {
+            facets.push((name.to_string(), facet));
+        }
+        self
+    }
+
+    /// Renders the facet as JSON
+    pub fn to_json(&self) -> Value {
+        let (mut facet, options, facets) = match self {
+            JsonFacet::Stat(aggregation) => return Value::String(aggregation.to_string()),
+            JsonFacet::Terms {
+                field,
+                options,
+                facets,
+            } => (
+                json!({"type": "terms", "field": field}),
+                Some(options),
+                facets,
+            ),
+            JsonFacet::Range {
+                field,
+                start,
+                end,
+                gap,
+                options,
+                facets,
+            } => (
+                json!({"type": "range", "field": field, "start": start, "end": end, "gap": gap}),
+                Some(options),
+                facets,
+            ),
+            JsonFacet::Query { q, facets } => (json!({"type": "query", "q": q}), None, facets),
+        };
+
+        if let Some(object) = facet.as_object_mut() {
+            object.extend(
+                options
+                    .into_iter()
+                    .flatten()
+                    .map(|(k, v)| (k.clone(), v.clone())),
+            );
+            if !facets.is_empty() {
+                object.insert("facet".to_string(), to_object(facets));
+            }
+        }
+
+        facet
+    }
+}
+
+fn to_object(facets: &[(String, JsonFacet)]) -> Value {
+    Value::Object(
+        facets
+            .iter()
+            .map(|(name, facet)| (name.clone(), facet.to_json()))
+            .collect(),
+    )
+}
+
+impl SolrQuery {
+    /// Adds a named facet to the `json.facet` parameter
+    pub fn json_facet(self, name: &str, facet: JsonFacet) -> SolrQuery {
+        let mut facets = self
+            .url
+            .params("json.facet")
+            .first()
+            .and_then(|f| serde_json::from_str::<Map<String, Value>>(f).ok())
+            .unwrap_or_default();
+        facets.insert(name.to_string(), facet.to_json());
+
+        self.with_param("json.facet", &Value::Object(facets).to_string())
+    }
+}
+
+#[cfg(test)]
+mod json_facet_tests {
+    use super::*;
+    use crate::errors::SolrSubqueryError;
+    use crate::solr_query::SubQuery;
+    use std::error::Error;
+
+    #[test]
+    fn should_serialize_json_facets() -> Result<(), Box<dyn Error>> {
+        let query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=*:*")?
+            .json_facet(
+                "languages",
+                JsonFacet::terms("lang")
+                    .limit(10)
+                    .sort("count desc")
+                    .subfacet("avg_price", JsonFacet::stat("avg(price)")),
+            )
+            .json_facet(
+                "years",
+                JsonFacet::range("year", 2000, 2020, 5)
+                    .subfacet("recent", JsonFacet::query("date:[NOW-1YEAR TO NOW]")),
+            );
+
+        let json_facet: Value = serde_json::from_str(&query.url.params("json.facet")[0])?;
+
+        assert_eq!(
+            json_facet,
+            json!({
+                "languages": {
+                    "type": "terms",
+                    "field": "lang",
+                    "limit": 10,
+                    "sort": "count desc",
+                    "facet": { "avg_price": "avg(price)" },
+                },
+                "years": {
+                    "type": "range",
+                    "field": "year",
+                    "start": 2000,
+                    "end": 2020,
+                    "gap": 5,
+                    "facet": { "recent": { "type": "query", "q": "date:[NOW-1YEAR TO NOW]" } },
+                },
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_merge_json_facets() -> Result<(), Box<dyn Error>> {
+        let query = |facet: JsonFacet| -> Result<SolrQuery, SolrSubqueryError> {
+            Ok(
+                SolrQuery::new("http://localhost:8983/solr/collection/select?q=*:*")?
+                    .json_facet("languages", facet),
+            )
+        };
+
+        let left =
+            query(JsonFacet::terms("lang").subfacet("avg_price", JsonFacet::stat("avg(price)")))?
+                .json_facet("years", JsonFacet::terms("year"));
+        let right =
+            query(JsonFacet::terms("lang").subfacet("max_price", JsonFacet::stat("max(price)")))?;
+
+        let merged = left.inner_join(&right)?;
+        let json_facet: Value = serde_json::from_str(&merged.url.params("json.facet")[0])?;
+        assert_eq!(merged.url.params("json.facet").len(), 1);
+        assert_eq!(
+            json_facet,
+            json!({
+                "languages": {
+                    "type": "terms",
+                    "field": "lang",
+                    "facet": { "avg_price": "avg(price)", "max_price": "max(price)" },
+                },
+                "years": { "type": "terms", "field": "year" },
+            })
+        );
+
+        let conflicting = query(JsonFacet::terms("language"))?;
+        assert!(matches!(
+            left.inner_join(&conflicting),
+            Err(SolrSubqueryError::ConflictingJsonFacets { path, .. }) if path == "languages.field"
+        ));
+
+        Ok(())
+    }
+}
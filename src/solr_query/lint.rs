@@ -0,0 +1,132 @@
+use crate::errors::SolrSubqueryError;
+use crate::query_ast::QueryAst;
+use crate::solr_query::{clause_terms, QueryParam, SolrQuery};
+
+/// Number of rows above which a query should be paged instead
+pub const MAX_ROWS: u64 = 10000;
+
+#[derive(Debug, Clone, PartialEq)]
+/// A potential problem of a query, which doesn't prevent sending it
+pub enum LintWarning {
+    /// A term starts with a wildcard, making Solr scan the whole term dictionary
+    LeadingWildcard(String),
+    /// A range is open on both sides, or on one side for a high-cardinality field
+    UnboundedRange(String),
+    /// More rows than `MAX_ROWS` are requested at once
+    TooManyRows(u64),
+    /// The query matches all documents but the negated clauses
+    NegatedMatchAll,
+}
+
+impl std::fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LintWarning::LeadingWildcard(term) => write!(f, "Leading wildcard in `{}`", term),
+            LintWarning::UnboundedRange(term) => write!(f, "Unbounded range `{}`", term),
+            LintWarning::TooManyRows(rows) => {
+                write!(f, "{} rows requested, more than {}", rows, MAX_ROWS)
+            }
+            LintWarning::NegatedMatchAll => {
+                write!(f, "Query matches all documents but the negated clauses")
+            }
+        }
+    }
+}
+
+impl SolrQuery {
+    /// Finds the potential problems of the query
+    pub fn lint(&self) -> Result<Vec<LintWarning>, SolrSubqueryError> {
+        self.lint_with(&[])
+    }
+
+    /// Finds the potential problems of the query, also warning about ranges open on one side on
+    /// `high_cardinality_fields`
+    pub fn lint_with(
+        &self,
+        high_cardinality_fields: &[&str],
+    ) -> Result<Vec<LintWarning>, SolrSubqueryError> {
+        let q = self.q_param()?;
+        let mut warnings = Vec::new();
+
+        for term in clause_terms(&q) {
+            let (field, value) = term.split_once(':').unwrap_or(("", &term));
+
+            if value.starts_with(['*', '?']) && value != "*" {
+                warnings.push(LintWarning::LeadingWildcard(term.clone()));
+            } else if let Some((from, to)) = range_bounds(value) {
+                let open_sides = [from, to].iter().filter(|b| **b == "*").count();
+                if open_sides == 2 || (open_sides == 1 && high_cardinality_fields.contains(&field))
+                {
+                    warnings.push(LintWarning::UnboundedRange(term.clone()));
+                }
+            }
+        }
+
+        if let Some(rows) = self.url.params("rows").first() {
+            match rows.parse::<u64>() {
+                Ok(rows) if rows > MAX_ROWS => warnings.push(LintWarning::TooManyRows(rows)),
+                _ => {}
+            }
+        }
+
+        match QueryAst::parse(&q).normalize() {
+            QueryAst::Not(_) => warnings.push(LintWarning::NegatedMatchAll),
+            QueryAst::And(children) if children.iter().all(|c| matches!(c, QueryAst::Not(_))) => {
+                warnings.push(LintWarning::NegatedMatchAll)
+            }
+            _ => {}
+        }
+
+        Ok(warnings)
+    }
+}
+
+/// Gets the bounds of a range such as `[2020 TO *]`
+fn range_bounds(value: &str) -> Option<(&str, &str)> {
+    let range = value.strip_prefix(['[', '{'])?.strip_suffix([']', '}'])?;
+    let (from, to) = range.split_once(" TO ")?;
+    Some((from.trim(), to.trim()))
+}
+
+#[cfg(test)]
+mod lint_tests {
+    use crate::solr_query::{LintWarning, SolrQuery};
+    use std::error::Error;
+
+    #[test]
+    fn should_lint_queries() -> Result<(), Box<dyn Error>> {
+        let query = SolrQuery::new(
+            "http://localhost:8983/solr/collection/select?q=title:*rust AND year:[* TO *] AND id:[100 TO *] AND lang:en*&rows=50000",
+        )?;
+
+        assert_eq!(
+            query.lint()?,
+            vec![
+                LintWarning::LeadingWildcard("title:*rust".into()),
+                LintWarning::UnboundedRange("year:[* TO *]".into()),
+                LintWarning::TooManyRows(50000),
+            ]
+        );
+        assert_eq!(
+            query.lint_with(&["id"])?[2],
+            LintWarning::UnboundedRange("id:[100 TO *]".into())
+        );
+
+        let negated = SolrQuery::new("http://localhost:8983/solr/collection/select?q=lang:en")?;
+        assert_eq!(negated.lint()?, vec![]);
+        assert_eq!(
+            negated.inverse().lint()?,
+            vec![LintWarning::NegatedMatchAll]
+        );
+        assert_eq!(
+            LintWarning::NegatedMatchAll.to_string(),
+            "Query matches all documents but the negated clauses"
+        );
+
+        let existence =
+            SolrQuery::new("http://localhost:8983/solr/collection/select?q=*:* AND lang:*")?;
+        assert_eq!(existence.lint()?, vec![]);
+
+        Ok(())
+    }
+}
@@ -1,8 +1,35 @@
+mod boost;
+pub use boost::*;
+mod endpoint;
+pub use endpoint::*;
+mod export;
+mod facet;
+mod grouping;
+pub use grouping::*;
+mod highlight;
+mod json_facet;
+pub use json_facet::*;
+mod lint;
+pub use lint::*;
+mod params;
+pub use params::MERGED_PARAM_FAMILIES;
+mod spellcheck;
+pub use spellcheck::*;
+mod sql;
+mod stats;
+mod terms;
+pub use terms::*;
+pub(crate) mod timeout;
+
 use crate::errors::*;
+use crate::query_ast::{explicit_operators, wildcard_to_range, QueryAst, WildcardField};
 
+use std::collections::BTreeSet;
+use std::ops::{BitAnd, BitOr, Not};
+use unicode_normalization::UnicodeNormalization;
 use url::Url;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 /// A Solr boolean operator
 pub enum Operator {
     And,
@@ -20,11 +47,90 @@ impl std::fmt::Display for Operator {
     }
 }
 
+impl std::str::FromStr for Operator {
+    type Err = SolrSubqueryError;
+
+    fn from_str(s: &str) -> Result<Operator, SolrSubqueryError> {
+        match s.to_uppercase().as_str() {
+            "AND" => Ok(Operator::And),
+            "OR" => Ok(Operator::Or),
+            "NOT" => Ok(Operator::Not),
+            _ => Err(SolrSubqueryError::InvalidOperator(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+/// How to merge queries using the dismax or edismax parser with different parameters
+pub enum EdismaxStrategy {
+    /// Keeps the parameters of the last query
+    #[default]
+    KeepLast,
+    /// Rejects the merge
+    Reject,
+    /// Rewrites each side as a `_query_` clause carrying its own local params
+    Nest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+/// Which parts of the URLs must match for two queries to be merged
+///
+/// The schemes must match whatever the policy.
+pub enum JoinPolicy {
+    /// Same host, port and path
+    #[default]
+    Strict,
+    /// Same host and path, e.g. behind load balancers listening on several ports
+    IgnorePort,
+    /// Same port and path, e.g. behind several load balancer hostnames
+    IgnoreHost,
+    /// Same host and port, e.g. to merge queries sent to different request handlers
+    IgnorePath,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+/// How to compare the characters of q values
+pub enum UnicodeComparison {
+    /// Compares code points as they are
+    #[default]
+    Exact,
+    /// Compares the NFC forms, so composed and decomposed accents are the same
+    Nfc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+/// How to compute the inverse of a query
+pub enum InverseMode {
+    /// Flips the last merge operator: `(A AND B) AND C` becomes `(A AND B) NOT C`
+    #[default]
+    LastOperator,
+    /// Complements the whole query: `(A AND B) AND C` becomes `*:* NOT ((A AND B) AND C)`
+    Complement,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+/// How to handle URLs with several q parameters
+pub enum MultiQ {
+    /// Fails with `MultipleQQueryParameters`
+    #[default]
+    Reject,
+    /// Combines the q parameters into one, each as a group: `(a) AND (b)`
+    JoinWith(Operator),
+}
+
+/// Parameters interpreted by the dismax and edismax query parsers
+pub const DISMAX_PARAMS: [&str; 14] = [
+    "qf", "mm", "pf", "pf2", "pf3", "ps", "ps2", "ps3", "qs", "tie", "bq", "bf", "boost", "uf",
+];
+
 #[derive(Debug, Clone, PartialEq)]
 /// A Solr query
 pub struct SolrQuery {
     pub url: Url,
     negation: Url,
+    join_policy: JoinPolicy,
+    clause_budget: Option<usize>,
+    endpoint: SolrEndpoint,
 }
 
 pub trait QueryParam {
@@ -58,50 +164,625 @@ impl QueryParam for Url {
     }
 }
 
+/// Rewrites a q value in a canonical form to compare queries
+///
+/// Whitespace outside of phrases is collapsed, `&&` and `||` are spelled `AND`
+/// and `OR`, and parentheses wrapping the whole value are removed.
+fn canonical_q(q: &str) -> String {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let (mut in_quotes, mut escaped) = (false, false);
+
+    for c in q.chars() {
+        match c {
+            _ if escaped => {
+                escaped = false;
+                token.push(c);
+            }
+            '\\' => {
+                escaped = true;
+                token.push(c);
+            }
+            '"' => {
+                in_quotes = !in_quotes;
+                token.push(c);
+            }
+            c if c.is_whitespace() && !in_quotes => tokens.push(std::mem::take(&mut token)),
+            _ => token.push(c),
+        }
+    }
+    tokens.push(token);
+
+    let mut canonical = tokens
+        .into_iter()
+        .filter(|t| !t.is_empty())
+        .map(|t| match t.as_str() {
+            "&&" => "AND".to_string(),
+            "||" => "OR".to_string(),
+            _ => t,
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    while canonical.starts_with('(') && closing_paren(&canonical) == Some(canonical.len() - 1) {
+        canonical = canonical[1..canonical.len() - 1].trim().to_string();
+    }
+
+    canonical
+}
+
+/// Index of the parenthesis closing the group opened at the start of `q`
+pub(crate) fn closing_paren(q: &str) -> Option<usize> {
+    let (mut depth, mut in_quotes, mut escaped) = (0, false, false);
+
+    for (i, c) in q.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => in_quotes = !in_quotes,
+            '(' if !in_quotes => depth += 1,
+            ')' if !in_quotes => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Hashes bytes with FNV-1a, which is stable across runs and platforms
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Collects the fields prefixing the clauses of a q or fq value
+///
+/// Phrases, ranges and local params are skipped, as well as `*:*` and `_query_`.
+/// Parsers taking a literal value, such as `{!term f=id}`, only use their `f` field.
+fn referenced_fields(q: &str, fields: &mut BTreeSet<String>) {
+    if let Some(local_params) = q
+        .trim_start()
+        .strip_prefix("{!")
+        .and_then(|q| q.split_once('}'))
+        .map(|(local_params, _)| local_params.split_whitespace().collect::<Vec<_>>())
+    {
+        if matches!(
+            local_params.first(),
+            Some(&"term") | Some(&"raw") | Some(&"field") | Some(&"prefix")
+        ) {
+            fields.extend(
+                local_params
+                    .iter()
+                    .filter_map(|p| p.strip_prefix("f="))
+                    .map(|f| f.to_string()),
+            );
+            return;
+        }
+    }
+
+    let mut name = String::new();
+    let (mut in_quotes, mut in_braces, mut escaped) = (false, 0, false);
+
+    for c in q.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => in_quotes = !in_quotes,
+            _ if in_quotes => {}
+            '[' | '{' => in_braces += 1,
+            ']' | '}' => in_braces -= 1,
+            _ if in_braces > 0 => {}
+            ':' if !name.is_empty() && name != "_query_" => {
+                fields.insert(name.clone());
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '.' => {
+                name.push(c);
+                continue;
+            }
+            '-' if !name.is_empty() => {
+                name.push(c);
+                continue;
+            }
+            _ => {}
+        }
+
+        name.clear();
+    }
+}
+
+/// Gets the endpoint and the sorted parameters of an URL with a canonical q value
+fn canonical_form(url: &Url, unicode: UnicodeComparison) -> (Url, Vec<(String, String)>) {
+    let mut endpoint = url.clone();
+    endpoint.set_query(None);
+    endpoint.set_fragment(None);
+
+    let mut params = url
+        .query_pairs()
+        .map(|(k, v)| match k.as_ref() {
+            "q" if unicode == UnicodeComparison::Nfc => {
+                (k.to_string(), canonical_q(&v.nfc().collect::<String>()))
+            }
+            "q" => (k.to_string(), canonical_q(&v)),
+            _ => (k.to_string(), v.to_string()),
+        })
+        .collect::<Vec<_>>();
+    params.sort();
+
+    (endpoint, params)
+}
+
+/// Hashes the canonical form of an URL, see `SolrQuery::canonical_hash`
+pub(crate) fn canonical_url_hash(url: &Url) -> u64 {
+    let (endpoint, params) = canonical_form(url, UnicodeComparison::Nfc);
+    let mut canonical = endpoint.to_string();
+    for (key, value) in params {
+        canonical.push_str(&format!("\n{}={}", key, value));
+    }
+
+    fnv1a(canonical.as_bytes())
+}
+
+/// Gets the same URL on another origin (scheme, host and port of `base`)
+pub(crate) fn with_origin(url: &Url, base: &Url) -> Result<Url, SolrSubqueryError> {
+    let invalid = || SolrSubqueryError::invalid_url(redact(base));
+    let mut rebased = url.clone();
+    rebased.set_scheme(base.scheme()).map_err(|_| invalid())?;
+    rebased
+        .set_host(base.host_str())
+        .map_err(SolrSubqueryError::from)?;
+    rebased.set_port(base.port()).map_err(|_| invalid())?;
+    Ok(rebased)
+}
+
+/// Estimates the number of clauses of a q value
+///
+/// Terms, phrases and ranges count as one clause each, boolean operators don't.
+pub(crate) fn count_clauses(q: &str) -> usize {
+    clause_terms(q).len()
+}
+
+/// Splits a q value into its terms, phrases and ranges, dropping boolean operators
+pub(crate) fn clause_terms(q: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let (mut in_quotes, mut in_range, mut escaped) = (false, 0_usize, false);
+
+    for c in q.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => in_quotes = !in_quotes,
+            '[' | '{' if !in_quotes => in_range += 1,
+            // An unbalanced closing bracket must not keep the rest of q in a range
+            ']' | '}' if !in_quotes => in_range = in_range.saturating_sub(1),
+            '(' | ')' if !in_quotes && in_range == 0 => {
+                tokens.push(std::mem::take(&mut token));
+                continue;
+            }
+            c if c.is_whitespace() && !in_quotes && in_range == 0 => {
+                tokens.push(std::mem::take(&mut token));
+                continue;
+            }
+            _ => {}
+        }
+        token.push(c);
+    }
+    tokens.push(token);
+
+    tokens
+        .iter()
+        .map(|t| t.trim_start_matches(['+', '-', '!']))
+        .filter(|t| !t.is_empty() && !t.ends_with(':'))
+        .filter(|t| !matches!(*t, "AND" | "OR" | "NOT" | "&&" | "||"))
+        .map(|t| t.to_string())
+        .collect()
+}
+
 impl SolrQuery {
     /// Creates a new SolrQuery from an URL or a string
     pub fn new<U: TryInto<Url>>(url: U) -> Result<SolrQuery, SolrSubqueryError>
     where
         <U as TryInto<Url>>::Error: std::error::Error + 'static,
     {
-        let url: Url = url
-            .try_into()
-            .map_err(|e| SolrSubqueryError::InvalidUrl(e.to_string()))?;
+        let url: Url = url.try_into().map_err(SolrSubqueryError::url_conversion)?;
 
         let mut negation_url = url.clone();
         let q_params = negation_url.params("q");
 
         match q_params.len() {
-            0 => Err(SolrSubqueryError::MissingQQueryParameter),
+            0 => Err(SolrSubqueryError::MissingQQueryParameter { url: redact(&url) }),
             1 => {
                 let q = format!("{} ({})", Operator::Not, q_params[0]);
                 negation_url.set_param(("q", &q));
 
                 Ok(SolrQuery {
+                    endpoint: SolrEndpoint::from_url(&url),
                     url,
                     negation: negation_url,
+                    join_policy: JoinPolicy::default(),
+                    clause_budget: None,
                 })
             }
-            _ => Err(SolrSubqueryError::MultipleQQueryParameters),
+            _ => Err(SolrSubqueryError::MultipleQQueryParameters {
+                url: redact(&url),
+                q: q_params,
+            }),
         }
     }
 
+    /// Creates a new SolrQuery from an URL or a string, handling several q parameters as requested
+    ///
+    /// The combined q takes the place of the first one.
+    pub fn new_with_options<U: TryInto<Url>>(
+        url: U,
+        multi_q: MultiQ,
+    ) -> Result<SolrQuery, SolrSubqueryError>
+    where
+        <U as TryInto<Url>>::Error: std::error::Error + 'static,
+    {
+        let mut url: Url = url.try_into().map_err(SolrSubqueryError::url_conversion)?;
+        let q_params = url.params("q");
+
+        if let (MultiQ::JoinWith(operator), true) = (multi_q, q_params.len() > 1) {
+            let q = q_params
+                .iter()
+                .map(|q| format!("({})", q))
+                .collect::<Vec<_>>()
+                .join(&format!(" {} ", operator));
+
+            let mut q = Some(q);
+            let parameters = url
+                .query_pairs()
+                .filter_map(|(key, value)| match key == "q" {
+                    true => q.take().map(|q| (key.to_string(), q)),
+                    false => Some((key.to_string(), value.to_string())),
+                })
+                .collect::<Vec<_>>();
+            url.query_pairs_mut().clear().extend_pairs(parameters);
+        }
+
+        SolrQuery::new(url)
+    }
+
+    /// Creates a new SolrQuery from its parts, e.g. `http://localhost:8983/solr`, `books`,
+    /// `select` and `title:rust`
+    ///
+    /// The core name and the q value are percent-encoded, the handler may have several segments.
+    pub fn from_parts(
+        base: &str,
+        core: &str,
+        handler: &str,
+        q: &str,
+    ) -> Result<SolrQuery, SolrSubqueryError> {
+        let mut url = Url::parse(base)?;
+        url.set_query(None);
+        let invalid = SolrSubqueryError::invalid_url(redact(&url));
+        url.path_segments_mut()
+            .map_err(|_| invalid)?
+            .pop_if_empty()
+            .push(core)
+            .extend(handler.trim_matches('/').split('/'));
+        url.query_pairs_mut().append_pair("q", q);
+
+        let handler_segments = handler.trim_matches('/').split('/').count();
+        Ok(SolrQuery::new(url)?.with_handler_segments(handler_segments))
+    }
+
     /// Gets the left join if null query
     pub fn inverse(&self) -> SolrQuery {
         SolrQuery {
             url: self.negation.clone(),
             negation: self.url.clone(),
+            join_policy: self.join_policy,
+            clause_budget: self.clause_budget,
+            endpoint: self.endpoint.clone(),
         }
     }
 
-    fn q_param(&self) -> Result<String, SolrSubqueryError> {
+    /// Gets the inverse query according to the given mode
+    pub fn inverse_with(&self, mode: InverseMode) -> SolrQuery {
+        match mode {
+            InverseMode::LastOperator => self.inverse(),
+            InverseMode::Complement => {
+                let mut complement = self.url.clone();
+                let q = format!(
+                    "*:* {} ({})",
+                    Operator::Not,
+                    self.q_param().unwrap_or_default()
+                );
+                complement.set_param(("q", &q));
+
+                SolrQuery {
+                    url: complement,
+                    negation: self.url.clone(),
+                    join_policy: self.join_policy,
+                    clause_budget: self.clause_budget,
+                    endpoint: self.endpoint.clone(),
+                }
+            }
+        }
+    }
+
+    /// Sets which parts of the URLs must match when merging this query with another one
+    pub fn with_join_policy(mut self, join_policy: JoinPolicy) -> SolrQuery {
+        self.join_policy = join_policy;
+        self
+    }
+
+    pub fn join_policy(&self) -> JoinPolicy {
+        self.join_policy
+    }
+
+    /// Sets the maximum number of clauses of the queries merged from this one
+    ///
+    /// Solr rejects queries with more clauses than its `maxBooleanClauses`
+    /// (`MAX_BOOLEAN_CLAUSES` by default), merges exceeding the budget fail early.
+    pub fn with_clause_budget(mut self, clause_budget: usize) -> SolrQuery {
+        self.clause_budget = Some(clause_budget);
+        self
+    }
+
+    pub fn clause_budget(&self) -> Option<usize> {
+        self.clause_budget
+    }
+
+    /// Gets the fields used by the `q` and `fq` parameters
+    pub fn fields(&self) -> Result<BTreeSet<String>, SolrSubqueryError> {
+        let mut fields = BTreeSet::new();
+
+        referenced_fields(&self.q_param()?, &mut fields);
+        for fq in self.url.params("fq") {
+            referenced_fields(&fq, &mut fields);
+        }
+
+        Ok(fields)
+    }
+
+    /// Estimates the number of clauses of the q parameter
+    pub fn clause_count(&self) -> Result<usize, SolrSubqueryError> {
+        Ok(count_clauses(&self.q_param()?))
+    }
+
+    /// Copies the join policy, the clause budget and the handler split of another query
+    pub(crate) fn with_settings_of(mut self, other: &SolrQuery) -> SolrQuery {
+        self.join_policy = other.join_policy;
+        self.clause_budget = other.clause_budget;
+        self.with_handler_segments(other.handler_segments())
+    }
+
+    /// Checks that another query can be merged with this one according to its join policy
+    pub(crate) fn check_can_join(&self, other: &SolrQuery) -> Result<(), SolrSubqueryError> {
+        self.check_has_same_scheme(other)?;
+        if self.join_policy != JoinPolicy::IgnoreHost {
+            self.check_has_same_host(other)?;
+        }
+        if self.join_policy != JoinPolicy::IgnorePort {
+            self.check_has_same_port(other)?;
+        }
+        if self.join_policy != JoinPolicy::IgnorePath {
+            self.check_has_same_path(other)?;
+        }
+        Ok(())
+    }
+
+    /// Gets the URL of the inverse query
+    pub fn negation_url(&self) -> &Url {
+        &self.negation
+    }
+
+    /// Gets the same query sent to another origin (scheme, host and port of `base`)
+    pub fn with_origin(&self, base: &Url) -> Result<SolrQuery, SolrSubqueryError> {
+        Ok(SolrQuery {
+            url: with_origin(&self.url, base)?,
+            negation: with_origin(&self.negation, base)?,
+            join_policy: self.join_policy,
+            clause_budget: self.clause_budget,
+            endpoint: self.endpoint.with_origin(base),
+        })
+    }
+
+    /// Restricts the query to the given collections with the `collection` param
+    ///
+    /// The inverse is left as is, documents of the other collections don't match the query either.
+    /// An empty list leaves the query unchanged rather than sending an empty `collection` param.
+    pub fn with_collections(&self, collections: &[String]) -> SolrQuery {
+        if collections.is_empty() {
+            return self.clone();
+        }
+
+        let mut url = self.url.clone();
+        let parameters = self
+            .url
+            .query_pairs()
+            .filter(|(k, _)| k != "collection")
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<Vec<_>>();
+
+        url.query_pairs_mut()
+            .clear()
+            .extend_pairs(parameters)
+            .append_pair("collection", &collections.join(","));
+
+        SolrQuery {
+            url,
+            ..self.clone()
+        }
+    }
+
+    /// Creates a SolrQuery from an URL and its already computed negation
+    pub(crate) fn with_negation(url: Url, negation: Url) -> Result<SolrQuery, SolrSubqueryError> {
+        let query = SolrQuery::new(url)?;
+        SolrQuery::new(negation.clone())?;
+
+        Ok(SolrQuery { negation, ..query })
+    }
+
+    /// Simplifies the boolean structure of the query and of its inverse
+    ///
+    /// See `QueryAst::normalize`.
+    pub fn normalize(&self) -> Result<SolrQuery, SolrSubqueryError> {
+        self.rewrite_q(|ast| ast.normalize())
+    }
+
+    /// Keeps a single copy of the clauses repeated in a group, e.g. when the same
+    /// subquery was merged twice
+    ///
+    /// The query is normalized first, see `QueryAst::deduplicate`.
+    pub fn deduplicate(&self) -> Result<SolrQuery, SolrSubqueryError> {
+        self.rewrite_q(|ast| ast.normalize().deduplicate())
+    }
+
+    /// Rewrites prefix wildcards on numeric and date fields as ranges, which Solr
+    /// evaluates much faster: `year:19*` becomes `year:[1900 TO 1999]`
+    ///
+    /// Solr doesn't know the type of the fields at parse time, they must be given.
+    pub fn rewrite_wildcards(
+        &self,
+        fields: &[(&str, WildcardField)],
+    ) -> Result<SolrQuery, SolrSubqueryError> {
+        self.rewrite_q(|ast| ast.map_clauses(&|clause| wildcard_to_range(clause, fields)))
+    }
+
+    /// Rewrites the q values of the query and of its inverse through their AST
+    pub(crate) fn rewrite_q<F>(&self, rewrite: F) -> Result<SolrQuery, SolrSubqueryError>
+    where
+        F: Fn(QueryAst) -> QueryAst,
+    {
+        let rewrite_url = |url: &Url| -> Result<Url, SolrSubqueryError> {
+            let q = url.params("q");
+            let q = q
+                .first()
+                .ok_or_else(|| SolrSubqueryError::MissingQQueryParameter { url: redact(url) })?;
+
+            let mut rewritten = url.clone();
+            rewritten.set_param(("q", &rewrite(QueryAst::parse(q)).to_string()));
+            Ok(rewritten)
+        };
+
+        Ok(
+            SolrQuery::with_negation(rewrite_url(&self.url)?, rewrite_url(&self.negation)?)?
+                .with_settings_of(self),
+        )
+    }
+
+    /// Checks if two queries are the same once decoded and normalized
+    ///
+    /// Endpoints must be equal, parameters may appear in any order and `q` values
+    /// are compared in their canonical form, so `(a  &&  b)` is equivalent to `a AND b`.
+    pub fn is_equivalent(&self, other: &SolrQuery) -> bool {
+        self.is_equivalent_with(other, UnicodeComparison::default())
+    }
+
+    /// Checks if two queries are equivalent, comparing q values according to the given mode
+    pub fn is_equivalent_with(&self, other: &SolrQuery, unicode: UnicodeComparison) -> bool {
+        self.canonical_form(unicode) == other.canonical_form(unicode)
+    }
+
+    /// Hashes the canonical form of the query, equivalent queries have the same hash
+    ///
+    /// The hash is stable across runs and platforms, so it can key persistent caches.
+    pub fn canonical_hash(&self) -> u64 {
+        canonical_url_hash(&self.url)
+    }
+
+    /// Gets the endpoint and the sorted parameters with a canonical q value
+    fn canonical_form(&self, unicode: UnicodeComparison) -> (Url, Vec<(String, String)>) {
+        canonical_form(&self.url, unicode)
+    }
+
+    pub(crate) fn q_param(&self) -> Result<String, SolrSubqueryError> {
         let q_params = self.url.params("q");
 
         match q_params.len() {
-            0 => Err(SolrSubqueryError::MissingQQueryParameter),
+            0 => Err(SolrSubqueryError::MissingQQueryParameter {
+                url: redact(&self.url),
+            }),
             1 => Ok(q_params[0].clone()),
-            _ => Err(SolrSubqueryError::MultipleQQueryParameters),
+            _ => Err(SolrSubqueryError::MultipleQQueryParameters {
+                url: redact(&self.url),
+                q: q_params,
+            }),
+        }
+    }
+
+    fn def_type(&self) -> Option<String> {
+        self.url.params("defType").pop()
+    }
+
+    fn uses_dismax(&self) -> bool {
+        matches!(self.def_type().as_deref(), Some("dismax") | Some("edismax"))
+    }
+
+    fn has_dismax_params(&self) -> bool {
+        DISMAX_PARAMS
+            .iter()
+            .any(|name| !self.url.params(name).is_empty())
+    }
+
+    /// Gets the first parser parameter differing between two queries when one of them uses (e)dismax
+    fn conflicting_dismax_param(&self, other: &SolrQuery) -> Option<String> {
+        if !self.uses_dismax() && !other.uses_dismax() {
+            return None;
         }
+
+        std::iter::once("defType")
+            .chain(DISMAX_PARAMS)
+            .filter(|name| !BOOST_PARAMS.contains(name))
+            .find(|name| self.url.params(name) != other.url.params(name))
+            .map(|name| name.to_string())
+    }
+
+    /// Gets the `q.op` of the query, Solr defaults to `OR`
+    fn default_operator(&self) -> Operator {
+        match self.url.params("q.op").pop() {
+            Some(op) if op.eq_ignore_ascii_case("AND") => Operator::And,
+            _ => Operator::Or,
+        }
+    }
+
+    /// Gets the q param, with explicit operators if the other query has a different `q.op`
+    fn explicit_q_param(&self, other: &SolrQuery) -> Result<String, SolrSubqueryError> {
+        let q = self.q_param()?;
+        if self.default_operator() == other.default_operator() {
+            return Ok(q);
+        }
+
+        explicit_operators(&q, self.default_operator()).ok_or_else(|| {
+            SolrSubqueryError::ConflictingDefaultOperators {
+                q,
+                left_url: redact(&self.url),
+                right_url: redact(&other.url),
+            }
+        })
+    }
+
+    /// Gets the q param wrapped in a `_query_` clause holding its parser and dismax params
+    fn nested_q_param(&self) -> Result<String, SolrSubqueryError> {
+        if self.def_type().is_none() && !self.has_dismax_params() {
+            return self.q_param();
+        }
+
+        let mut local_params = vec![self.def_type().unwrap_or_else(|| "lucene".into())];
+
+        for name in std::iter::once("q.op").chain(DISMAX_PARAMS) {
+            for value in self.url.params(name) {
+                let value = value.replace('\\', "\\\\").replace('\'', "\\'");
+                local_params.push(format!("{}='{}'", name, value));
+            }
+        }
+
+        let nested = format!("{{!{}}}{}", local_params.join(" "), self.q_param()?);
+
+        Ok(format!(
+            "_query_:\"{}\"",
+            nested.replace('\\', "\\\\").replace('"', "\\\"")
+        ))
     }
 }
 
@@ -111,8 +792,27 @@ pub trait SubQuery {
         query: &SolrQuery,
         operator: Operator,
     ) -> Result<SolrQuery, SolrSubqueryError>;
+    fn merge_queries_with(
+        &self,
+        query: &SolrQuery,
+        operator: Operator,
+        strategy: EdismaxStrategy,
+    ) -> Result<SolrQuery, SolrSubqueryError>;
+    fn merge_queries_with_policy(
+        &self,
+        other: &SolrQuery,
+        operator: Operator,
+        join_policy: JoinPolicy,
+    ) -> Result<SolrQuery, SolrSubqueryError>;
     fn inner_join(&self, other: &SolrQuery) -> Result<SolrQuery, SolrSubqueryError>;
+    fn inner_join_with(
+        &self,
+        other: &SolrQuery,
+        strategy: EdismaxStrategy,
+    ) -> Result<SolrQuery, SolrSubqueryError>;
+    fn exclude(&self, other: &SolrQuery) -> Result<SolrQuery, SolrSubqueryError>;
     fn check_has_same_path(&self, other: &SolrQuery) -> Result<(), SolrSubqueryError>;
+    fn check_has_same_scheme(&self, other: &SolrQuery) -> Result<(), SolrSubqueryError>;
     fn check_has_same_host(&self, other: &SolrQuery) -> Result<(), SolrSubqueryError>;
     fn check_has_same_port(&self, other: &SolrQuery) -> Result<(), SolrSubqueryError>;
 }
@@ -123,12 +823,42 @@ impl SubQuery for SolrQuery {
         other: &SolrQuery,
         operator: Operator,
     ) -> Result<SolrQuery, SolrSubqueryError> {
-        self.check_has_same_host(other)?;
-        self.check_has_same_port(other)?;
-        self.check_has_same_path(other)?;
+        self.merge_queries_with(other, operator, EdismaxStrategy::default())
+    }
 
-        let self_q = self.q_param()?;
-        let other_q = other.q_param()?;
+    fn merge_queries_with(
+        &self,
+        other: &SolrQuery,
+        operator: Operator,
+        strategy: EdismaxStrategy,
+    ) -> Result<SolrQuery, SolrSubqueryError> {
+        #[cfg(feature = "tracing")]
+        let (_span, started) = (
+            tracing::debug_span!("merge", %operator).entered(),
+            std::time::Instant::now(),
+        );
+
+        self.check_can_join(other)?;
+
+        let (self_q, other_q, nested) = match (strategy, self.conflicting_dismax_param(other)) {
+            (EdismaxStrategy::Reject, Some(param)) => {
+                return Err(SolrSubqueryError::ConflictingEdismaxParams {
+                    param,
+                    left_url: redact(&self.url),
+                    right_url: redact(&other.url),
+                })
+            }
+            (EdismaxStrategy::Nest, Some(_)) => {
+                (self.nested_q_param()?, other.nested_q_param()?, true)
+            }
+            _ => (
+                self.explicit_q_param(other)?,
+                other.explicit_q_param(self)?,
+                false,
+            ),
+        };
+
+        let json_facet = params::merged_json_facet(&self.url, &other.url)?;
 
         let mut new_url = other.url.clone();
         let mut new_url_query_pairs = new_url.query_pairs_mut();
@@ -137,59 +867,250 @@ impl SubQuery for SolrQuery {
         let new_q_param = format!("({}) {} ({})", self_q, operator, other_q);
 
         for (key, value) in other.url.query_pairs() {
-            if key != "q" {
-                new_url_query_pairs.append_pair(&key, &value);
-            } else {
-                new_url_query_pairs.append_pair("q", &new_q_param);
+            if nested && (key == "defType" || DISMAX_PARAMS.contains(&&*key)) {
+                continue;
             }
+
+            match (key.as_ref(), &json_facet) {
+                ("q", _) => new_url_query_pairs.append_pair("q", &new_q_param),
+                ("json.facet", Some(merged)) => new_url_query_pairs.append_pair(&key, merged),
+                _ => new_url_query_pairs.append_pair(&key, &value),
+            };
         }
 
+        new_url_query_pairs.extend_pairs(
+            params::missing_merged_params(&self.url, &other.url)
+                .into_iter()
+                .filter(|(key, _)| !nested || !BOOST_PARAMS.contains(&key.as_str())),
+        );
         drop(new_url_query_pairs);
 
-        SolrQuery::new(new_url)
+        let mut merged = SolrQuery::new(new_url)?.with_settings_of(self);
+        merged.clause_budget = match (self.clause_budget, other.clause_budget) {
+            (Some(budget), Some(other_budget)) => Some(budget.min(other_budget)),
+            (budget, other_budget) => budget.or(other_budget),
+        };
+
+        // The merged query must honour the tightest time limit of both sides
+        let timeout = match (self.timeout(), other.timeout()) {
+            (Some(timeout), Some(other_timeout)) => Some(timeout.min(other_timeout)),
+            (timeout, other_timeout) => timeout.or(other_timeout),
+        };
+        if let Some(timeout) = timeout {
+            merged = merged.with_timeout(timeout);
+        }
+
+        if let Some(budget) = merged.clause_budget {
+            let clause_count = merged.clause_count()?;
+            if clause_count > budget {
+                return Err(SolrSubqueryError::TooManyClauses {
+                    count: clause_count,
+                    budget,
+                    q: merged.q_param()?,
+                });
+            }
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            q = %merged.q_param()?,
+            elapsed_us = started.elapsed().as_micros() as u64,
+            "merged queries"
+        );
+
+        Ok(merged)
+    }
+
+    fn merge_queries_with_policy(
+        &self,
+        other: &SolrQuery,
+        operator: Operator,
+        join_policy: JoinPolicy,
+    ) -> Result<SolrQuery, SolrSubqueryError> {
+        self.clone()
+            .with_join_policy(join_policy)
+            .merge_queries(other, operator)
     }
 
     fn inner_join(&self, other: &SolrQuery) -> Result<SolrQuery, SolrSubqueryError> {
-        let positive = self.merge_queries(other, Operator::And)?;
-        let negative = self.merge_queries(other, Operator::Not)?;
+        self.inner_join_with(other, EdismaxStrategy::default())
+    }
+
+    fn inner_join_with(
+        &self,
+        other: &SolrQuery,
+        strategy: EdismaxStrategy,
+    ) -> Result<SolrQuery, SolrSubqueryError> {
+        let positive = self.merge_queries_with(other, Operator::And, strategy)?;
+        let negative = self.merge_queries_with(other, Operator::Not, strategy)?;
 
         Ok(SolrQuery {
             url: positive.url,
             negation: negative.url,
+            join_policy: self.join_policy,
+            clause_budget: self.clause_budget,
+            endpoint: positive.endpoint,
         })
     }
 
+    /// Excludes the documents matched by `other` with a negative filter query
+    ///
+    /// Unlike `merge_queries`, `q` and therefore scoring are left untouched and the
+    /// exclusion benefits from the filter cache. Only the `q` of `other` is used.
+    fn exclude(&self, other: &SolrQuery) -> Result<SolrQuery, SolrSubqueryError> {
+        self.check_can_join(other)?;
+
+        let self_q = self.q_param()?;
+        let other_q = other.q_param()?;
+
+        // A purely negative clause matches nothing once grouped, it needs a positive guard
+        let guarded_q = if other_q
+            .trim_start()
+            .starts_with(&format!("{} ", Operator::Not))
+            || other_q.trim_start().starts_with('-')
+        {
+            format!("*:* {}", other_q)
+        } else {
+            other_q.clone()
+        };
+
+        // The filter is purely negative, it needs a positive clause on its left
+        let mut url = self.url.clone();
+        url.query_pairs_mut()
+            .append_pair("fq", &format!("*:* -({})", guarded_q));
+
+        let mut negation = self.url.clone();
+        let negation_q = format!(
+            "{} (({}) {} ({}))",
+            Operator::Not,
+            self_q,
+            Operator::Not,
+            guarded_q
+        );
+        negation.set_param(("q", &negation_q));
+
+        Ok(SolrQuery {
+            url,
+            negation,
+            join_policy: self.join_policy,
+            clause_budget: self.clause_budget,
+            endpoint: self.endpoint.clone(),
+        })
+    }
+
+    fn check_has_same_scheme(&self, other: &SolrQuery) -> Result<(), SolrSubqueryError> {
+        if self.endpoint.scheme == other.endpoint.scheme {
+            Ok(())
+        } else {
+            Err(SolrSubqueryError::DifferentSchemes {
+                left: self.endpoint.scheme.clone(),
+                right: other.endpoint.scheme.clone(),
+                left_url: redact(&self.url),
+                right_url: redact(&other.url),
+            })
+        }
+    }
+
     fn check_has_same_host(&self, other: &SolrQuery) -> Result<(), SolrSubqueryError> {
-        if self.url.host() == other.url.host() {
+        if self.endpoint.host == other.endpoint.host {
             Ok(())
         } else {
-            Err(SolrSubqueryError::DifferentsHosts(
-                self.url.host_str().map(|h| h.to_string()),
-                other.url.host_str().map(|h| h.to_string()),
-            ))
+            Err(SolrSubqueryError::DifferentsHosts {
+                left: self.url.host_str().map(|h| h.to_string()),
+                right: other.url.host_str().map(|h| h.to_string()),
+                left_url: redact(&self.url),
+                right_url: redact(&other.url),
+            })
         }
     }
 
     fn check_has_same_path(&self, other: &SolrQuery) -> Result<(), SolrSubqueryError> {
-        if self.url.path() == other.url.path() {
-            Ok(())
+        // The origins are checked separately, according to the join policy
+        let (left, right) = (&self.endpoint, other.endpoint.with_origin(&self.url));
+
+        if !left.same_core(&right) {
+            Err(SolrSubqueryError::DifferentsPaths {
+                left_url: redact(&self.url),
+                right_url: redact(&other.url),
+            })
+        } else if *left != right {
+            Err(SolrSubqueryError::DifferentHandlers {
+                collection: left.core.clone(),
+                left: left.handler.clone(),
+                right: right.handler,
+                left_url: redact(&self.url),
+                right_url: redact(&other.url),
+            })
         } else {
-            Err(SolrSubqueryError::DifferentsPaths)
+            Ok(())
         }
     }
 
     fn check_has_same_port(&self, other: &SolrQuery) -> Result<(), SolrSubqueryError> {
-        if self.url.port() == other.url.port() {
+        if self.endpoint.port == other.endpoint.port {
             Ok(())
         } else {
-            Err(SolrSubqueryError::DifferentsPorts(
-                self.url.port(),
-                other.url.port(),
-            ))
+            Err(SolrSubqueryError::DifferentsPorts {
+                left: self.url.port(),
+                right: other.url.port(),
+                left_url: redact(&self.url),
+                right_url: redact(&other.url),
+            })
         }
     }
 }
 
+impl BitAnd for &SolrQuery {
+    type Output = Result<SolrQuery, SolrSubqueryError>;
+
+    /// Inner joins two queries
+    fn bitand(self, other: &SolrQuery) -> Self::Output {
+        self.inner_join(other)
+    }
+}
+
+impl BitAnd for SolrQuery {
+    type Output = Result<SolrQuery, SolrSubqueryError>;
+
+    fn bitand(self, other: SolrQuery) -> Self::Output {
+        &self & &other
+    }
+}
+
+impl BitOr for &SolrQuery {
+    type Output = Result<SolrQuery, SolrSubqueryError>;
+
+    /// Merges two queries with the `OR` operator
+    fn bitor(self, other: &SolrQuery) -> Self::Output {
+        self.merge_queries(other, Operator::Or)
+    }
+}
+
+impl BitOr for SolrQuery {
+    type Output = Result<SolrQuery, SolrSubqueryError>;
+
+    fn bitor(self, other: SolrQuery) -> Self::Output {
+        &self | &other
+    }
+}
+
+impl Not for &SolrQuery {
+    type Output = SolrQuery;
+
+    /// Gets the inverse query
+    fn not(self) -> Self::Output {
+        self.inverse()
+    }
+}
+
+impl Not for SolrQuery {
+    type Output = SolrQuery;
+
+    fn not(self) -> Self::Output {
+        self.inverse()
+    }
+}
+
 #[cfg(test)]
 mod solr_query_tests {
     use super::*;
@@ -227,7 +1148,7 @@ mod solr_query_tests {
         let negation_result = decode(&negation_url)?;
 
         let negation_expected = "http://localhost:8983/solr/collection1/select?q=NOT+(1:*)";
-        
+
         assert_eq!(negation_result, negation_expected);
 
         let inner_join = first_query.inner_join(&second_query)?;
@@ -289,10 +1210,12 @@ mod solr_query_tests {
 
         assert_eq!(
             inner_join_query,
-            Err(SolrSubqueryError::DifferentsHosts(
-                Some("localhost1".into()),
-                Some("localhost2".into())
-            ))
+            Err(SolrSubqueryError::DifferentsHosts {
+                left: Some("localhost1".into()),
+                right: Some("localhost2".into()),
+                left_url: "http://localhost1:8983/solr/collection1/select?q=*:*".into(),
+                right_url: "http://localhost2:8983/solr/collection1/select?q=*:*".into(),
+            })
         );
         Ok(())
     }
@@ -306,7 +1229,12 @@ mod solr_query_tests {
 
         assert_eq!(
             inner_join_query,
-            Err(SolrSubqueryError::DifferentsPorts(Some(8983), Some(8984)))
+            Err(SolrSubqueryError::DifferentsPorts {
+                left: Some(8983),
+                right: Some(8984),
+                left_url: "http://localhost:8983/solr/collection1/select?q=*:*".into(),
+                right_url: "http://localhost:8984/solr/collection1/select?q=*:*".into(),
+            })
         );
         Ok(())
     }
@@ -318,14 +1246,29 @@ mod solr_query_tests {
 
         let inner_join_query = first_query.inner_join(&second_query);
 
-        assert_eq!(inner_join_query, Err(SolrSubqueryError::DifferentsPaths));
+        assert_eq!(
+            inner_join_query,
+            Err(SolrSubqueryError::DifferentsPaths {
+                left_url: "http://localhost:8983/solr/collection1/select?q=*:*".into(),
+                right_url: "http://localhost:8983/solr/collection2/select?q=*:*".into(),
+            })
+        );
+        assert_eq!(
+            inner_join_query.unwrap_err().to_string(),
+            "Requests have different paths: http://localhost:8983/solr/collection1/select?q=*:* and http://localhost:8983/solr/collection2/select?q=*:*"
+        );
         Ok(())
     }
 
     #[test]
     fn should_not_inner_join_queries_without_q_param() -> Result<(), Box<dyn Error>> {
         let first_query = SolrQuery::new("http://localhost:8983/solr/collection/select");
-        assert_eq!(first_query, Err(SolrSubqueryError::MissingQQueryParameter));
+        assert_eq!(
+            first_query,
+            Err(SolrSubqueryError::MissingQQueryParameter {
+                url: "http://localhost:8983/solr/collection/select".into()
+            })
+        );
         Ok(())
     }
 
@@ -334,8 +1277,401 @@ mod solr_query_tests {
         let first_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=1&q=2");
         assert_eq!(
             first_query,
-            Err(SolrSubqueryError::MultipleQQueryParameters)
+            Err(SolrSubqueryError::MultipleQQueryParameters {
+                url: "http://localhost:8983/solr/collection/select?q=1&q=2".into(),
+                q: vec!["1".into(), "2".into()],
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn should_not_merge_conflicting_edismax_queries() -> Result<(), Box<dyn Error>> {
+        let first_query = SolrQuery::new(
+            "http://localhost:8983/solr/collection/select?q=rust&defType=edismax&qf=title",
+        )?;
+        let second_query = SolrQuery::new(
+            "http://localhost:8983/solr/collection/select?q=solr&defType=edismax&qf=body",
+        )?;
+
+        let merged =
+            first_query.merge_queries_with(&second_query, Operator::And, EdismaxStrategy::Reject);
+        assert_eq!(
+            merged,
+            Err(SolrSubqueryError::ConflictingEdismaxParams {
+                param: "qf".into(),
+                left_url: redact(&first_query.url),
+                right_url: redact(&second_query.url),
+            })
         );
+
+        let same_params = SolrQuery::new(
+            "http://localhost:8983/solr/collection/select?q=lang&defType=edismax&qf=title",
+        )?;
+        assert!(first_query
+            .merge_queries_with(&same_params, Operator::And, EdismaxStrategy::Reject)
+            .is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_build_query_from_parts() -> Result<(), Box<dyn Error>> {
+        let query = SolrQuery::from_parts(
+            "http://localhost:8983/solr/",
+            "books 2020",
+            "select",
+            "title:\"rust & solr\"",
+        )?;
+        assert_eq!(
+            query.url.as_str(),
+            "http://localhost:8983/solr/books%202020/select?q=title%3A%22rust+%26+solr%22"
+        );
+        assert_eq!(query.q_param()?, "title:\"rust & solr\"");
+
+        let query =
+            SolrQuery::from_parts("https://solr.example.org", "books", "/admin/ping", "*:*")?;
+        assert_eq!(
+            query.url.as_str(),
+            "https://solr.example.org/books/admin/ping?q=*%3A*"
+        );
+
+        assert!(matches!(
+            SolrQuery::from_parts("mailto:solr@example.org", "books", "select", "*:*"),
+            Err(SolrSubqueryError::InvalidUrl { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_join_multiple_q_parameters() -> Result<(), Box<dyn Error>> {
+        let url = "http://localhost:8983/solr/collection/select?rows=10&q=title:rust&fq=lang:en&q=year:2020";
+
+        assert!(matches!(
+            SolrQuery::new_with_options(url, MultiQ::Reject),
+            Err(SolrSubqueryError::MultipleQQueryParameters { .. })
+        ));
+
+        let query = SolrQuery::new_with_options(url, MultiQ::JoinWith(Operator::And))?;
+        assert_eq!(
+            decode(query.url.as_str())?,
+            "http://localhost:8983/solr/collection/select?rows=10&q=(title:rust)+AND+(year:2020)&fq=lang:en"
+        );
+        assert_eq!(
+            query.inverse().url.params("q"),
+            vec!["NOT ((title:rust) AND (year:2020))"]
+        );
+
+        let single = SolrQuery::new_with_options(
+            "http://localhost:8983/solr/collection/select?q=title:rust",
+            MultiQ::JoinWith(Operator::Or),
+        )?;
+        assert_eq!(single.url.params("q"), vec!["title:rust"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_make_operators_explicit_on_q_op_conflict() -> Result<(), Box<dyn Error>> {
+        let and_query =
+            SolrQuery::new("http://localhost:8983/solr/collection/select?q=rust lang&q.op=AND")?;
+        let or_query =
+            SolrQuery::new("http://localhost:8983/solr/collection/select?q=en fr&q.op=OR")?;
+
+        let merged = and_query.merge_queries(&or_query, Operator::And)?;
+        assert_eq!(
+            merged.url.params("q"),
+            vec!["(rust AND lang) AND (en OR fr)"]
+        );
+
+        let default_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=en fr")?;
+        assert_eq!(
+            and_query
+                .merge_queries(&default_query, Operator::Or)?
+                .url
+                .params("q"),
+            vec!["(rust AND lang) OR (en OR fr)"]
+        );
+
+        let ambiguous =
+            SolrQuery::new("http://localhost:8983/solr/collection/select?q=en fr AND de")?;
+        assert!(matches!(
+            and_query.merge_queries(&ambiguous, Operator::And),
+            Err(SolrSubqueryError::ConflictingDefaultOperators { q, .. }) if q == "en fr AND de"
+        ));
+
+        for q in ["{!term f=lang}en fr", "{!lucene q.op=OR}en fr"] {
+            let local_params = SolrQuery::new(
+                format!("http://localhost:8983/solr/collection/select?q={}", q).as_str(),
+            )?;
+            assert!(matches!(
+                and_query.merge_queries(&local_params, Operator::And),
+                Err(SolrSubqueryError::ConflictingDefaultOperators { q: conflicting, .. }) if conflicting == q
+            ));
+        }
+
+        let same_default =
+            SolrQuery::new("http://localhost:8983/solr/collection/select?q=en fr&q.op=and")?;
+        assert_eq!(
+            and_query
+                .merge_queries(&same_default, Operator::And)?
+                .url
+                .params("q"),
+            vec!["(rust lang) AND (en fr)"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_nest_conflicting_edismax_queries() -> Result<(), Box<dyn Error>> {
+        let first_query = SolrQuery::new(
+            "http://localhost:8983/solr/collection/select?q=rust&defType=edismax&qf=title+body&mm=2",
+        )?;
+        let second_query =
+            SolrQuery::new("http://localhost:8983/solr/collection/select?q=lang:en&rows=10")?;
+
+        let inner_join = first_query.inner_join_with(&second_query, EdismaxStrategy::Nest)?;
+        let url_string = inner_join.url.to_string();
+        let result = decode(&url_string)?;
+        let expected = "http://localhost:8983/solr/collection/select?q=(_query_:\"{!edismax+qf='title+body'+mm='2'}rust\")+AND+(lang:en)&rows=10";
+
+        assert_eq!(result, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_compose_queries_with_operators() -> Result<(), Box<dyn Error>> {
+        let first_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=1:*")?;
+        let second_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=2:*")?;
+        let third_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=3:*")?;
+
+        let composed = ((&first_query & &second_query)? | third_query)?;
+        let url_string = composed.url.to_string();
+        let result = decode(&url_string)?;
+        let expected = "http://localhost:8983/solr/collection/select?q=((1:*)+AND+(2:*))+OR+(3:*)";
+
+        assert_eq!(result, expected);
+        assert_eq!(!&first_query, first_query.inverse());
+        assert_eq!(!!first_query.clone(), first_query);
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_complement_multi_way_merge() -> Result<(), Box<dyn Error>> {
+        let first_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=1:*")?;
+        let second_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=2:*")?;
+        let third_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=3:*")?;
+
+        let merged = first_query
+            .inner_join(&second_query)?
+            .inner_join(&third_query)?;
+
+        let complement = merged.inverse_with(InverseMode::Complement);
+        let url_string = complement.url.to_string();
+        let result = decode(&url_string)?;
+        let expected =
+            "http://localhost:8983/solr/collection/select?q=*:*+NOT+(((1:*)+AND+(2:*))+AND+(3:*))";
+
+        assert_eq!(result, expected);
+        assert_eq!(complement.inverse().url, merged.url);
+        assert_eq!(
+            merged.inverse_with(InverseMode::LastOperator),
+            merged.inverse()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_exclude_with_filter_query() -> Result<(), Box<dyn Error>> {
+        let first_query =
+            SolrQuery::new("http://localhost:8983/solr/collection/select?q=title:rust")?;
+        let second_query =
+            SolrQuery::new("http://localhost:8983/solr/collection/select?q=lang:fr")?;
+        let negative_query =
+            SolrQuery::new("http://localhost:8983/solr/collection/select?q=NOT+lang:en")?;
+
+        let excluded = first_query.exclude(&second_query)?;
+        let url_string = excluded.url.to_string();
+        let result = decode(&url_string)?;
+        let expected =
+            "http://localhost:8983/solr/collection/select?q=title:rust&fq=*:*+-(lang:fr)";
+
+        assert_eq!(result, expected);
+
+        let inverse_url_string = excluded.inverse().url.to_string();
+        let inverse_result = decode(&inverse_url_string)?;
+        let inverse_expected =
+            "http://localhost:8983/solr/collection/select?q=NOT+((title:rust)+NOT+(lang:fr))";
+
+        assert_eq!(inverse_result, inverse_expected);
+
+        let guarded = first_query.exclude(&negative_query)?;
+        let guarded_url_string = guarded.url.to_string();
+        let guarded_result = decode(&guarded_url_string)?;
+        let guarded_expected =
+            "http://localhost:8983/solr/collection/select?q=title:rust&fq=*:*+-(*:*+NOT+lang:en)";
+
+        assert_eq!(guarded_result, guarded_expected);
+
+        let guarded_inverse_url_string = guarded.inverse().url.to_string();
+        assert_eq!(
+            decode(&guarded_inverse_url_string)?,
+            "http://localhost:8983/solr/collection/select?q=NOT+((title:rust)+NOT+(*:*+NOT+lang:en))"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_merge_according_to_join_policy() -> Result<(), Box<dyn Error>> {
+        let first_query = SolrQuery::new("http://lb-1:8983/solr/collection/select?q=1:*")?;
+        let second_query = SolrQuery::new("http://lb-2:8983/solr/collection/select?q=2:*")?;
+
+        assert!(matches!(
+            first_query.inner_join(&second_query),
+            Err(SolrSubqueryError::DifferentsHosts { .. })
+        ));
+
+        let lenient_query = first_query.clone().with_join_policy(JoinPolicy::IgnoreHost);
+        let joined = lenient_query.inner_join(&second_query)?;
+        assert_eq!(joined.join_policy(), JoinPolicy::IgnoreHost);
+
+        let url_string = joined.url.to_string();
+        let result = decode(&url_string)?;
+        assert_eq!(
+            result,
+            "http://lb-2:8983/solr/collection/select?q=(1:*)+AND+(2:*)"
+        );
+
+        assert!(first_query
+            .merge_queries_with_policy(&second_query, Operator::Or, JoinPolicy::IgnoreHost)
+            .is_ok());
+        assert!(matches!(
+            first_query.merge_queries_with_policy(
+                &second_query,
+                Operator::Or,
+                JoinPolicy::IgnorePort
+            ),
+            Err(SolrSubqueryError::DifferentsHosts { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_compare_equivalent_queries() -> Result<(), Box<dyn Error>> {
+        let query = SolrQuery::new(
+            "http://localhost:8983/solr/collection/select?q=(title:rust)%20AND%20(lang:en)&rows=10",
+        )?;
+        let equivalent = SolrQuery::new(
+            "http://localhost:8983/solr/collection/select?rows=10&q=((title:rust)++%26%26+(lang:en))",
+        )?;
+        let phrase = SolrQuery::new(
+            "http://localhost:8983/solr/collection/select?q=title:\"a  b\"&rows=10",
+        )?;
+        let other_phrase =
+            SolrQuery::new("http://localhost:8983/solr/collection/select?q=title:\"a b\"&rows=10")?;
+
+        assert!(query.is_equivalent(&equivalent));
+        assert!(!query.is_equivalent(&query.inverse()));
+        assert!(!phrase.is_equivalent(&other_phrase));
+
+        let composed =
+            SolrQuery::new("http://localhost:8983/solr/collection/select?q=title:caf%C3%A9")?;
+        let decomposed =
+            SolrQuery::new("http://localhost:8983/solr/collection/select?q=title:cafe%CC%81")?;
+
+        assert!(!composed.is_equivalent(&decomposed));
+        assert!(composed.is_equivalent_with(&decomposed, UnicodeComparison::Nfc));
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_normalize_query() -> Result<(), Box<dyn Error>> {
+        let first_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=a:1")?;
+        let second_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=b:1")?;
+
+        let complement = first_query
+            .inner_join(&second_query)?
+            .inverse_with(InverseMode::Complement);
+        let normalized = complement
+            .inverse_with(InverseMode::Complement)
+            .normalize()?;
+
+        let url_string = normalized.url.to_string();
+        let result = decode(&url_string)?;
+        assert_eq!(
+            result,
+            "http://localhost:8983/solr/collection/select?q=a:1+AND+b:1"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_rewrite_wildcards() -> Result<(), Box<dyn Error>> {
+        let query = SolrQuery::new(
+            "http://localhost:8983/solr/collection/select?q=(year:19*)%20AND%20(title:rust*)",
+        )?;
+
+        let rewritten =
+            query.rewrite_wildcards(&[("year", WildcardField::Integer { digits: 4 })])?;
+        let url_string = rewritten.url.to_string();
+        let result = decode(&url_string)?;
+
+        assert_eq!(
+            result,
+            "http://localhost:8983/solr/collection/select?q=year:[1900+TO+1999]+AND+title:rust*"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_enforce_clause_budget() -> Result<(), Box<dyn Error>> {
+        let first_query =
+            SolrQuery::new("http://localhost:8983/solr/collection/select?q=a:1 OR b:1")?;
+        let second_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=c:1")?;
+
+        assert_eq!(first_query.clause_count()?, 2);
+
+        let merged = first_query
+            .clone()
+            .with_clause_budget(3)
+            .inner_join(&second_query)?;
+        assert_eq!(merged.clause_budget(), Some(3));
+
+        assert_eq!(
+            second_query
+                .with_clause_budget(2)
+                .merge_queries(&first_query, Operator::Or),
+            Err(SolrSubqueryError::TooManyClauses {
+                count: 3,
+                budget: 2,
+                q: "(c:1) OR (a:1 OR b:1)".into(),
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_list_referenced_fields() -> Result<(), Box<dyn Error>> {
+        let query = SolrQuery::new(
+            "http://localhost:8983/solr/collection/select?q=(title:\"a:b\" OR -body_en:rust) AND *:* AND date:[NOW-1DAY TO NOW]&fq={!term f=id}doc:1&fq=lang:(en OR fr)",
+        )?;
+
+        assert_eq!(
+            query.fields()?.into_iter().collect::<Vec<_>>(),
+            vec!["body_en", "date", "id", "lang", "title"]
+        );
+
         Ok(())
     }
 }
@@ -0,0 +1,155 @@
+use crate::solr_query::{QueryParam, SolrQuery};
+
+use crate::errors::{redact, SolrSubqueryError};
+use serde_json::Value;
+use url::Url;
+
+/// Parameter families kept from both sides when merging queries
+///
+/// Other parameters are taken from the right-hand query only. `json.facet` objects of
+/// both sides are merged, see `merged_json_facet`.
+pub const MERGED_PARAM_FAMILIES: &[&str] = &[
+    "facet",
+    "json.facet",
+    "hl",
+    "group",
+    "stats",
+    "spellcheck",
+    "bq",
+    "bf",
+    "boost",
+];
+
+/// Parameters of the merged families that can be repeated
+const MULTI_VALUED_PARAMS: [&str; 11] = [
+    "facet.field",
+    "facet.query",
+    "facet.pivot",
+    "facet.range",
+    "facet.interval",
+    "hl.fl",
+    "group.field",
+    "stats.field",
+    "bq",
+    "bf",
+    "boost",
+];
+
+/// Checks if a parameter belongs to a family merged from both sides, per field
+/// overrides such as `f.lang.facet.limit` included
+pub(crate) fn is_merged_param(key: &str) -> bool {
+    let key = match key.strip_prefix("f.") {
+        Some(per_field) => per_field.split_once('.').map_or(key, |(_, k)| k),
+        None => key,
+    };
+
+    MERGED_PARAM_FAMILIES
+        .iter()
+        .any(|family| key == *family || key.starts_with(&format!("{}.", family)))
+}
+
+/// Gets the parameters of the merged families of `left` missing from `right`
+///
+/// Repeatable parameters are unioned, single valued ones keep the value of `right`.
+pub(crate) fn missing_merged_params(left: &Url, right: &Url) -> Vec<(String, String)> {
+    left.query_pairs()
+        .filter(|(key, _)| is_merged_param(key))
+        .filter(
+            |(key, value)| match MULTI_VALUED_PARAMS.contains(&key.as_ref()) {
+                true => !right.params(key).contains(&value.to_string()),
+                false => right.params(key).is_empty(),
+            },
+        )
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+/// Gets the `json.facet` of two queries having one, with the facets of both
+///
+/// Facets defined on both sides must have the same definition, nested objects being
+/// compared key by key.
+pub(crate) fn merged_json_facet(
+    left: &Url,
+    right: &Url,
+) -> Result<Option<String>, SolrSubqueryError> {
+    let (left_facet, right_facet) = match (
+        left.params("json.facet").first(),
+        right.params("json.facet").first(),
+    ) {
+        (Some(left_facet), Some(right_facet)) => (left_facet.clone(), right_facet.clone()),
+        _ => return Ok(None),
+    };
+    let conflict = |path: &str| SolrSubqueryError::ConflictingJsonFacets {
+        path: path.to_string(),
+        left_url: redact(left),
+        right_url: redact(right),
+    };
+
+    if left_facet == right_facet {
+        return Ok(Some(right_facet));
+    }
+
+    match (
+        serde_json::from_str::<Value>(&left_facet),
+        serde_json::from_str::<Value>(&right_facet),
+    ) {
+        (Ok(left_facet), Ok(mut merged)) => {
+            deep_merge(&left_facet, &mut merged, "").map_err(|path| conflict(&path))?;
+            Ok(Some(merged.to_string()))
+        }
+        // Solr also reads a relaxed JSON syntax, which can't be merged
+        _ => Err(conflict("")),
+    }
+}
+
+/// Adds the keys of `left` missing from `right`, failing with the path of a conflicting value
+fn deep_merge(left: &Value, right: &mut Value, path: &str) -> Result<(), String> {
+    match (left, right) {
+        (Value::Object(left), Value::Object(right)) => {
+            for (key, value) in left {
+                let path = match path.is_empty() {
+                    true => key.clone(),
+                    false => format!("{}.{}", path, key),
+                };
+                match right.get_mut(key) {
+                    Some(right_value) => deep_merge(value, right_value, &path)?,
+                    None => {
+                        right.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+            Ok(())
+        }
+        (left, right) if left == right => Ok(()),
+        _ => Err(path.to_string()),
+    }
+}
+
+impl SolrQuery {
+    /// Sets a parameter of the query and of its inverse, replacing its values
+    pub(crate) fn with_param(mut self, name: &str, value: &str) -> SolrQuery {
+        for url in [&mut self.url, &mut self.negation] {
+            let parameters = url
+                .query_pairs()
+                .filter(|(k, _)| k != name)
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<Vec<_>>();
+
+            url.query_pairs_mut()
+                .clear()
+                .extend_pairs(parameters)
+                .append_pair(name, value);
+        }
+        self
+    }
+
+    /// Adds a value to a repeatable parameter of the query and of its inverse
+    pub(crate) fn add_param(mut self, name: &str, value: &str) -> SolrQuery {
+        for url in [&mut self.url, &mut self.negation] {
+            if !url.params(name).contains(&value.to_string()) {
+                url.query_pairs_mut().append_pair(name, value);
+            }
+        }
+        self
+    }
+}
@@ -0,0 +1,90 @@
+use crate::solr_query::SolrQuery;
+
+use serde_json::Value;
+
+impl SolrQuery {
+    /// Enables the spellcheck component
+    pub fn spellcheck(self) -> SolrQuery {
+        self.with_param("spellcheck", "true")
+    }
+
+    /// Sets the query to spellcheck instead of `q`, enabling the spellcheck component
+    pub fn spellcheck_q(self, q: &str) -> SolrQuery {
+        self.spellcheck().with_param("spellcheck.q", q)
+    }
+
+    /// Asks for collations, queries rewritten with the best suggestions, enabling the spellcheck component
+    pub fn spellcheck_collate(self) -> SolrQuery {
+        self.spellcheck().with_param("spellcheck.collate", "true")
+    }
+}
+
+/// Reads the collations of a spellcheck response, best first
+///
+/// Both the default flat list (`["collation", "..."]`) and the `json.nl=map`
+/// object are supported, as well as the extended results returned with
+/// `spellcheck.collateExtendedResults=true`.
+pub fn spellcheck_collations(response: &Value) -> Vec<String> {
+    let collations: Vec<&Value> = match &response["spellcheck"]["collations"] {
+        Value::Array(list) => list
+            .chunks(2)
+            .filter(|pair| pair[0] == "collation")
+            .filter_map(|pair| pair.get(1))
+            .collect(),
+        Value::Object(map) => map
+            .iter()
+            .filter(|(k, _)| *k == "collation")
+            .map(|(_, v)| v)
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    collations
+        .into_iter()
+        .filter_map(|collation| match collation {
+            Value::String(q) => Some(q.to_string()),
+            extended => extended["collationQuery"].as_str().map(|q| q.to_string()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod spellcheck_tests {
+    use super::*;
+    use serde_json::json;
+    use std::error::Error;
+    use urlencoding::decode;
+
+    #[test]
+    fn should_build_spellcheck_params() -> Result<(), Box<dyn Error>> {
+        let query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=title:hest")?
+            .spellcheck_q("hest")
+            .spellcheck_collate();
+
+        let url_string = query.url.to_string();
+        let result = decode(&url_string)?;
+
+        assert_eq!(
+            result,
+            "http://localhost:8983/solr/collection/select?q=title:hest&spellcheck.q=hest&spellcheck=true&spellcheck.collate=true"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_parse_collations() {
+        let flat = json!({"spellcheck": {"collations": [
+            "collation", "title:heat",
+            "collation", {"collationQuery": "title:best", "hits": 3},
+        ]}});
+        let map = json!({"spellcheck": {"collations": {"collation": "title:heat"}}});
+
+        assert_eq!(
+            spellcheck_collations(&flat),
+            vec!["title:heat", "title:best"]
+        );
+        assert_eq!(spellcheck_collations(&map), vec!["title:heat"]);
+        assert!(spellcheck_collations(&json!({})).is_empty());
+    }
+}
@@ -0,0 +1,129 @@
+use crate::errors::SolrSubqueryError;
+use crate::query_ast::QueryAst;
+use crate::solr_query::{QueryParam, SolrQuery};
+
+impl SolrQuery {
+    /// Translates the query to a statement for the `/sql` handler
+    ///
+    /// `fl` gives the selected columns and `rows` the limit, `q` and the `fq`
+    /// filters are combined in the WHERE clause. Clauses without a field and
+    /// groups using implicit operators can't be translated.
+    pub fn to_sql(&self) -> Result<String, SolrSubqueryError> {
+        let unsupported = |reason: String| SolrSubqueryError::UnsupportedSql(reason);
+
+        let collection = self.collection()?;
+        let columns = self.fl_fields();
+        if columns.is_empty() {
+            return Err(unsupported("missing fl".to_string()));
+        }
+
+        let filters = std::iter::once(self.q_param()?)
+            .chain(self.url.params("fq"))
+            .map(|q| QueryAst::parse(&q))
+            .collect();
+
+        let mut statement = format!("SELECT {} FROM {}", columns.join(", "), collection);
+        match QueryAst::And(filters).normalize() {
+            QueryAst::Clause(clause) if clause == "*:*" => {}
+            ast => {
+                statement.push_str(" WHERE ");
+                statement.push_str(&sql_expression(&ast, false)?);
+            }
+        }
+        if let Some(rows) = self.url.params("rows").first() {
+            let limit = rows
+                .trim()
+                .parse::<u64>()
+                .map_err(|_| unsupported(format!("rows={}", rows)))?;
+            statement.push_str(&format!(" LIMIT {}", limit));
+        }
+
+        Ok(statement)
+    }
+}
+
+/// Translates a parsed query to a SQL boolean expression, `nested` operands are parenthesized
+fn sql_expression(ast: &QueryAst, nested: bool) -> Result<String, SolrSubqueryError> {
+    let join = |children: &[QueryAst], operator: &str| -> Result<String, SolrSubqueryError> {
+        let expression = children
+            .iter()
+            .map(|c| sql_expression(c, true))
+            .collect::<Result<Vec<_>, _>>()?
+            .join(operator);
+
+        Ok(match nested {
+            true => format!("({})", expression),
+            false => expression,
+        })
+    };
+
+    match ast {
+        QueryAst::Clause(clause) => match clause.strip_prefix('-') {
+            Some(negated) => Ok(format!("NOT {}", sql_comparison(negated)?)),
+            None => sql_comparison(clause),
+        },
+        QueryAst::Verbatim(group) => Err(SolrSubqueryError::UnsupportedSql(group.to_string())),
+        QueryAst::And(children) => join(children, " AND "),
+        QueryAst::Or(children) => join(children, " OR "),
+        QueryAst::Not(child) => Ok(format!("NOT {}", sql_expression(child, true)?)),
+    }
+}
+
+/// Translates `field:value` to `field = 'value'`, the value keeping the Lucene syntax
+fn sql_comparison(clause: &str) -> Result<String, SolrSubqueryError> {
+    let (field, value) = clause
+        .strip_prefix('+')
+        .unwrap_or(clause)
+        .split_once(':')
+        .filter(|(field, value)| {
+            !field.is_empty()
+                && !value.is_empty()
+                && field
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '_' || c == '.')
+        })
+        .ok_or_else(|| SolrSubqueryError::UnsupportedSql(clause.to_string()))?;
+
+    Ok(format!("{} = '{}'", field, value.replace('\'', "''")))
+}
+
+#[cfg(test)]
+mod sql_tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn should_translate_query_to_sql() -> Result<(), Box<dyn Error>> {
+        let query = SolrQuery::new(
+            "http://localhost:8983/solr/books/select?q=(lang:en OR lang:fr) AND NOT author:o'brien&fq=year:[2000 TO *]&fl=id,title&rows=10",
+        )?;
+
+        assert_eq!(
+            query.to_sql()?,
+            "SELECT id, title FROM books WHERE (lang = 'en' OR lang = 'fr') AND NOT author = 'o''brien' AND year = '[2000 TO *]' LIMIT 10"
+        );
+
+        let query = SolrQuery::new("http://localhost:8983/solr/books/select?q=*:*&fl=id")?;
+        assert_eq!(query.to_sql()?, "SELECT id FROM books");
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_reject_untranslatable_queries() -> Result<(), Box<dyn Error>> {
+        for url in [
+            "http://localhost:8983/solr/books/select?q=lang:en",
+            "http://localhost:8983/solr/books/select?q=\"a phrase\"&fl=id",
+            "http://localhost:8983/solr/books/select?q=lang:en lang:fr&fl=id",
+            "http://localhost:8983/solr/books/select?q=lang:en&fl=id&rows=1; DROP TABLE books",
+            "http://localhost:8983/solr/books/select?q=lang:en&fl=id&rows=-1",
+        ] {
+            assert!(matches!(
+                SolrQuery::new(url)?.to_sql(),
+                Err(SolrSubqueryError::UnsupportedSql(_))
+            ));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+use crate::solr_query::SolrQuery;
+
+impl SolrQuery {
+    /// Enables the stats component
+    pub fn stats(self) -> SolrQuery {
+        self.with_param("stats", "true")
+    }
+
+    /// Adds a field to compute min, max, sum, count and the other stats of, enabling the stats component
+    pub fn stats_field(self, field: &str) -> SolrQuery {
+        self.stats().add_param("stats.field", field)
+    }
+}
+
+#[cfg(test)]
+mod stats_tests {
+    use crate::solr_query::{SolrQuery, SubQuery};
+    use std::error::Error;
+    use urlencoding::decode;
+
+    #[test]
+    fn should_keep_stats_when_merging() -> Result<(), Box<dyn Error>> {
+        let first_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=a:1")?
+            .stats_field("price");
+        let second_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=b:1")?
+            .stats_field("pages")
+            .stats_field("price");
+
+        let url_string = first_query.inner_join(&second_query)?.url.to_string();
+        let result = decode(&url_string)?;
+
+        assert_eq!(
+            result,
+            "http://localhost:8983/solr/collection/select?q=(a:1)+AND+(b:1)&stats.field=pages&stats=true&stats.field=price"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+use crate::solr_query::SolrQuery;
+
+use serde_json::Value;
+use url::Url;
+
+impl SolrQuery {
+    /// Builds a request to the `/terms` handler of the collection listing the indexed values of a field
+    ///
+    /// The request handler of the query is replaced, its parameters are dropped.
+    pub fn terms_for_field(&self, field: &str) -> Url {
+        let mut url = self.core_handler_url("terms");
+        url.query_pairs_mut()
+            .append_pair("terms", "true")
+            .append_pair("terms.fl", field)
+            .append_pair("terms.limit", "-1")
+            .append_pair("wt", "json");
+        url
+    }
+}
+
+/// Reads the indexed values of a field and their document counts from a terms response
+///
+/// Both the default flat list (`["math", 10, ...]`) and the `json.nl=map` object are supported.
+pub fn terms_values(response: &Value, field: &str) -> Vec<(String, u64)> {
+    match &response["terms"][field] {
+        Value::Array(list) => list
+            .chunks(2)
+            .filter_map(|pair| Some((pair[0].as_str()?.to_string(), pair.get(1)?.as_u64()?)))
+            .collect(),
+        Value::Object(map) => map
+            .iter()
+            .filter_map(|(term, count)| Some((term.to_string(), count.as_u64()?)))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod terms_tests {
+    use super::*;
+    use serde_json::json;
+    use std::error::Error;
+
+    #[test]
+    fn should_derive_terms_request() -> Result<(), Box<dyn Error>> {
+        let query =
+            SolrQuery::new("http://localhost:8983/solr/collection/select?q=lang:en&rows=0")?;
+
+        assert_eq!(
+            query.terms_for_field("subject").as_str(),
+            "http://localhost:8983/solr/collection/terms?terms=true&terms.fl=subject&terms.limit=-1&wt=json"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_parse_terms() {
+        let flat = json!({"terms": {"subject": ["math", 10, "physics", 5]}});
+        let map = json!({"terms": {"subject": {"math": 10}}});
+
+        assert_eq!(
+            terms_values(&flat, "subject"),
+            vec![("math".to_string(), 10), ("physics".to_string(), 5)]
+        );
+        assert_eq!(
+            terms_values(&map, "subject"),
+            vec![("math".to_string(), 10)]
+        );
+        assert!(terms_values(&flat, "title").is_empty());
+    }
+}
@@ -0,0 +1,65 @@
+use crate::solr_query::{QueryParam, SolrQuery};
+
+use std::time::Duration;
+use url::Url;
+
+impl SolrQuery {
+    /// Limits the time Solr spends searching, with the `timeAllowed` parameter
+    ///
+    /// The client also uses it as the timeout of the requests sending the query, overriding
+    /// its own.
+    pub fn with_timeout(self, timeout: Duration) -> SolrQuery {
+        self.with_param("timeAllowed", &timeout.as_millis().to_string())
+    }
+
+    /// Gets the time Solr may spend searching, if limited
+    pub fn timeout(&self) -> Option<Duration> {
+        time_allowed(&self.url)
+    }
+}
+
+/// Reads the `timeAllowed` parameter of an URL
+pub(crate) fn time_allowed(url: &Url) -> Option<Duration> {
+    url.params("timeAllowed")
+        .first()
+        .and_then(|ms| ms.parse().ok())
+        .map(Duration::from_millis)
+}
+
+#[cfg(test)]
+mod timeout_tests {
+    use crate::solr_query::{SolrQuery, SubQuery};
+    use std::error::Error;
+    use std::time::Duration;
+
+    #[test]
+    fn should_map_timeout_to_time_allowed() -> Result<(), Box<dyn Error>> {
+        let slow_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=a:1")?
+            .with_timeout(Duration::from_secs(30));
+        let fast_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=b:1")?
+            .with_timeout(Duration::from_millis(500));
+
+        assert_eq!(slow_query.url.query(), Some("q=a%3A1&timeAllowed=30000"));
+        assert_eq!(slow_query.timeout(), Some(Duration::from_secs(30)));
+        assert_eq!(
+            slow_query.inner_join(&fast_query)?.timeout(),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(
+            fast_query.inner_join(&slow_query)?.timeout(),
+            Some(Duration::from_millis(500))
+        );
+
+        let unlimited_query = SolrQuery::new("http://localhost:8983/solr/collection/select?q=c:1")?;
+        assert_eq!(
+            slow_query.inner_join(&unlimited_query)?.timeout(),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            slow_query.inverse().timeout(),
+            Some(Duration::from_secs(30))
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+use crate::errors::SolrSubqueryError;
+use crate::solr_query::{QueryParam, SolrQuery};
+
+use url::Url;
+
+#[derive(Debug, Clone, PartialEq)]
+/// A Solr streaming expression run by the `/stream` handler
+pub enum StreamingExpression {
+    /// `search(collection, q=..., fq=..., fl=..., sort=..., qt="/export")`
+    Search {
+        collection: String,
+        q: String,
+        fq: Vec<String>,
+        fl: String,
+        sort: String,
+    },
+    /// `innerJoin(left, right, on=...)`, both streams being sorted on the join field
+    InnerJoin {
+        left: Box<StreamingExpression>,
+        right: Box<StreamingExpression>,
+        on: String,
+    },
+}
+
+impl StreamingExpression {
+    /// Builds a search over the whole result set of a query, sorted on `sort_field`
+    ///
+    /// The `fl` of the query is kept, `sort_field` is added to it if missing.
+    pub fn search(
+        query: &SolrQuery,
+        sort_field: &str,
+    ) -> Result<StreamingExpression, SolrSubqueryError> {
+        let collection = query.collection()?;
+        let mut fl = query.fl_fields();
+        if !fl.iter().any(|f| f == sort_field) {
+            fl.insert(0, sort_field.to_string());
+        }
+
+        Ok(StreamingExpression::Search {
+            collection: collection.to_string(),
+            q: query.q_param()?,
+            fq: query.url.params("fq"),
+            fl: fl.join(","),
+            sort: format!("{} asc", sort_field),
+        })
+    }
+
+    /// Joins the results of two queries on a field, both sides being streamed sorted on it
+    pub fn inner_join(
+        left: &SolrQuery,
+        right: &SolrQuery,
+        on: &str,
+    ) -> Result<StreamingExpression, SolrSubqueryError> {
+        Ok(StreamingExpression::InnerJoin {
+            left: Box::new(StreamingExpression::search(left, on)?),
+            right: Box::new(StreamingExpression::search(right, on)?),
+            on: on.to_string(),
+        })
+    }
+
+    /// Builds the request running the expression on the `/stream` handler of a query's collection
+    pub fn stream_url(&self, query: &SolrQuery) -> Url {
+        let mut url = query.core_handler_url("stream");
+        url.query_pairs_mut().append_pair("expr", &self.to_string());
+        url
+    }
+}
+
+/// Quotes a streaming expression parameter value
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl std::fmt::Display for StreamingExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            StreamingExpression::Search {
+                collection,
+                q,
+                fq,
+                fl,
+                sort,
+            } => {
+                write!(f, "search({}, q={}", collection, quote(q))?;
+                for fq in fq {
+                    write!(f, ", fq={}", quote(fq))?;
+                }
+                write!(
+                    f,
+                    ", fl={}, sort={}, qt=\"/export\")",
+                    quote(fl),
+                    quote(sort)
+                )
+            }
+            StreamingExpression::InnerJoin { left, right, on } => {
+                write!(f, "innerJoin({}, {}, on={})", left, right, quote(on))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod streaming_tests {
+    use super::*;
+    use std::error::Error;
+    use urlencoding::decode;
+
+    #[test]
+    fn should_build_inner_join_expression() -> Result<(), Box<dyn Error>> {
+        let left = SolrQuery::new(
+            "http://localhost:8983/solr/books/select?q=title:\"rust lang\"&fq=lang:en&fl=title",
+        )?;
+        let right = SolrQuery::new("http://localhost:8983/solr/books/select?q=year:2020")?;
+
+        let expression = StreamingExpression::inner_join(&left, &right, "id")?;
+
+        assert_eq!(
+            expression.to_string(),
+            "innerJoin(search(books, q=\"title:\\\"rust lang\\\"\", fq=\"lang:en\", fl=\"id,title\", sort=\"id asc\", qt=\"/export\"), search(books, q=\"year:2020\", fl=\"id\", sort=\"id asc\", qt=\"/export\"), on=\"id\")"
+        );
+
+        let url_string = expression.stream_url(&left).to_string();
+        assert!(decode(&url_string)?
+            .starts_with("http://localhost:8983/solr/books/stream?expr=innerJoin(search(books,"));
+
+        Ok(())
+    }
+}
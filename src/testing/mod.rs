@@ -0,0 +1,211 @@
+use crate::errors::SolrSubqueryError;
+use crate::solr_query::QueryParam;
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use url::Url;
+
+#[derive(Debug, Default)]
+struct MockState {
+    /// Canned responses by decoded q
+    responses: HashMap<String, Value>,
+    /// Schema of every collection, returned by the Schema API
+    schema: Option<Value>,
+    /// URLs of the received requests
+    requests: Vec<Url>,
+}
+
+#[derive(Debug)]
+/// A stub of Solr answering `/select` and `/export` requests with canned responses keyed by
+/// their decoded q
+///
+/// Requests whose q has no response get a 404, pings of any collection succeed.
+/// The Schema API answers with the schema set by `respond_schema`, if any.
+/// The server stops when dropped.
+pub struct MockSolr {
+    address: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl MockSolr {
+    /// Starts the server on a free local port
+    pub fn start() -> Result<MockSolr, SolrSubqueryError> {
+        let listener =
+            TcpListener::bind("127.0.0.1:0").map_err(|e| SolrSubqueryError::Http(e.to_string()))?;
+        let address = listener
+            .local_addr()
+            .map_err(|e| SolrSubqueryError::Http(e.to_string()))?;
+
+        let state = Arc::new(Mutex::new(MockState::default()));
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let (thread_state, thread_stopped) = (state.clone(), stopped.clone());
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                if thread_stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                if let Ok(stream) = stream {
+                    let _ = answer(stream, &thread_state);
+                }
+            }
+        });
+
+        Ok(MockSolr {
+            address,
+            state,
+            stopped,
+        })
+    }
+
+    /// Gets the URL of a collection on the server, e.g. `http://127.0.0.1:1234/solr/books/`
+    pub fn collection_url(&self, collection: &str) -> Url {
+        Url::parse(&format!("http://{}/solr/{}/", self.address, collection))
+            .expect("valid collection URL")
+    }
+
+    /// Gets the URL of a search of a collection on the server
+    pub fn select_url(&self, collection: &str, q: &str) -> Url {
+        let mut url = self
+            .collection_url(collection)
+            .join("select")
+            .expect("valid select URL");
+        url.query_pairs_mut().append_pair("q", q);
+        url
+    }
+
+    /// Answers the searches with the given q with a response
+    pub fn respond(&self, q: &str, response: Value) {
+        self.lock().responses.insert(q.to_string(), response);
+    }
+
+    /// Answers the searches with the given q with the given documents
+    pub fn respond_docs(&self, q: &str, docs: Vec<Value>) {
+        self.respond(
+            q,
+            json!({"response": {"numFound": docs.len(), "start": 0, "docs": docs}}),
+        );
+    }
+
+    /// Answers the Schema API requests of every collection with a schema
+    pub fn respond_schema(&self, schema: Value) {
+        self.lock().schema = Some(schema);
+    }
+
+    /// Gets the URLs of the requests received so far
+    pub fn requests(&self) -> Vec<Url> {
+        self.lock().requests.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for MockSolr {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // Wakes the server up so it sees the stop flag
+        let _ = TcpStream::connect(self.address);
+    }
+}
+
+/// Reads a request and writes the canned response of its q
+fn answer(stream: TcpStream, state: &Mutex<MockState>) -> std::io::Result<()> {
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let url = request_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|target| Url::parse(&format!("http://localhost{}", target)).ok());
+
+    let (status, body) = match url {
+        Some(url) => {
+            let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+            state.requests.push(url.clone());
+
+            match url.path() {
+                path if path.ends_with("/select") || path.ends_with("/export") => url
+                    .params("q")
+                    .first()
+                    .and_then(|q| state.responses.get(q))
+                    .map(|response| ("200 OK", response.clone())),
+                path if path.ends_with("/admin/ping") => Some(("200 OK", json!({"status": "OK"}))),
+                path if path.ends_with("/schema") => state
+                    .schema
+                    .as_ref()
+                    .map(|schema| ("200 OK", json!({ "schema": schema }))),
+                _ => None,
+            }
+            .unwrap_or_else(|| {
+                (
+                    "404 Not Found",
+                    json!({"error": {"msg": "no canned response"}}),
+                )
+            })
+        }
+        None => (
+            "400 Bad Request",
+            json!({"error": {"msg": "invalid request"}}),
+        ),
+    };
+
+    let body = body.to_string();
+    write!(
+        &stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+#[cfg(test)]
+mod testing_tests {
+    use super::*;
+    use std::error::Error;
+    use std::io::Read;
+
+    #[test]
+    fn should_answer_canned_responses() -> Result<(), Box<dyn Error>> {
+        let solr = MockSolr::start()?;
+        solr.respond_docs("title:\"rust lang\"", vec![json!({"id": "1"})]);
+
+        let get = |url: &Url| -> Result<String, Box<dyn Error>> {
+            let mut stream = TcpStream::connect(solr.address)?;
+            write!(
+                stream,
+                "GET {}?{} HTTP/1.1\r\nHost: localhost\r\n\r\n",
+                url.path(),
+                url.query().unwrap_or_default()
+            )?;
+            let mut response = String::new();
+            stream.read_to_string(&mut response)?;
+            Ok(response)
+        };
+
+        let found = get(&solr.select_url("books", "title:\"rust lang\""))?;
+        assert!(found.starts_with("HTTP/1.1 200 OK"));
+        assert!(found.ends_with(r#"{"response":{"docs":[{"id":"1"}],"numFound":1,"start":0}}"#));
+
+        let missing = get(&solr.select_url("books", "title:solr"))?;
+        assert!(missing.starts_with("HTTP/1.1 404 Not Found"));
+
+        assert_eq!(solr.requests().len(), 2);
+        assert_eq!(solr.requests()[0].path(), "/solr/books/select");
+
+        Ok(())
+    }
+}
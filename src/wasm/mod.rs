@@ -0,0 +1,92 @@
+use crate::query_chain::QueryChain as Chain;
+use crate::solr_query::{Operator, SubQuery};
+use crate::SolrQuery as Query;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolrQuery {
+    query: Query,
+}
+
+#[wasm_bindgen]
+impl SolrQuery {
+    #[wasm_bindgen(constructor)]
+    pub fn new(url: &str) -> Result<SolrQuery, JsError> {
+        Ok(Query::new(url)?.into())
+    }
+
+    #[wasm_bindgen(js_name = innerJoin)]
+    pub fn inner_join(&self, query: &SolrQuery) -> Result<SolrQuery, JsError> {
+        Ok(self.query.inner_join(&query.query)?.into())
+    }
+
+    /// Merges the queries with an `AND`, `OR` or `NOT` operator
+    pub fn merge(&self, query: &SolrQuery, operator: &str) -> Result<SolrQuery, JsError> {
+        let operator = operator.parse::<Operator>()?;
+        Ok(self.query.merge_queries(&query.query, operator)?.into())
+    }
+
+    pub fn inverse(&self) -> SolrQuery {
+        self.query.inverse().into()
+    }
+
+    pub fn url(&self) -> String {
+        self.query.url.to_string()
+    }
+
+    #[wasm_bindgen(js_name = inverseUrl)]
+    pub fn inverse_url(&self) -> String {
+        self.query.negation_url().to_string()
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolrQueryChain {
+    chain: Chain,
+}
+
+impl Default for SolrQueryChain {
+    fn default() -> SolrQueryChain {
+        SolrQueryChain::new()
+    }
+}
+
+#[wasm_bindgen]
+impl SolrQueryChain {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> SolrQueryChain {
+        SolrQueryChain {
+            chain: Chain::new(vec![]),
+        }
+    }
+
+    /// Adds a subquery merged with the previous step using an `AND`, `OR` or `NOT` operator
+    #[wasm_bindgen(js_name = addSubquery)]
+    pub fn add_subquery(&mut self, url: &str, operator: &str) -> Result<(), JsError> {
+        let operator = operator.parse::<Operator>()?;
+        self.chain.add_subquery_with_operator(url, operator)?;
+        Ok(())
+    }
+
+    /// Gets the URLs of every step of the chain
+    pub fn steps(&self) -> Result<Vec<String>, JsError> {
+        Ok(self
+            .chain
+            .iter()
+            .map(|step| Ok(step?.url.to_string()))
+            .collect::<Result<Vec<_>, crate::SolrSubqueryError>>()?)
+    }
+
+    /// Merges all the subqueries into the final query
+    pub fn collapse(&self) -> Result<SolrQuery, JsError> {
+        Ok(self.chain.clone().collapse()?.into())
+    }
+}
+
+impl From<Query> for SolrQuery {
+    fn from(query: Query) -> SolrQuery {
+        SolrQuery { query }
+    }
+}
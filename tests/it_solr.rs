@@ -0,0 +1,97 @@
+//! Golden semantics tests: checks that merged and inverted queries select
+//! exactly the expected documents on a real Solr instance.
+//!
+//! Requires Docker, run with `cargo test --features it-solr`.
+#![cfg(feature = "it-solr")]
+
+use std::collections::BTreeSet;
+use std::error::Error;
+
+use serde_json::{json, Value};
+use solr_subquery::{QueryChain, SolrQuery, SubQuery};
+use testcontainers::clients::Cli;
+use testcontainers::core::WaitFor;
+use testcontainers::{GenericImage, RunnableImage};
+
+const CORE: &str = "fixtures";
+
+fn solr_image() -> RunnableImage<GenericImage> {
+    let image = GenericImage::new("solr", "9")
+        .with_exposed_port(8983)
+        .with_wait_for(WaitFor::message_on_stdout("o.e.j.s.Server Started"));
+
+    RunnableImage::from((image, vec!["solr-precreate".to_string(), CORE.to_string()]))
+}
+
+fn index_fixtures(port: u16) -> Result<(), Box<dyn Error>> {
+    let docs = json!([
+        {"id": "1", "lang_s": "en", "year_i": 2019},
+        {"id": "2", "lang_s": "en", "year_i": 2021},
+        {"id": "3", "lang_s": "fr", "year_i": 2021},
+        {"id": "4", "lang_s": "fr", "year_i": 2019},
+    ]);
+
+    ureq::post(&format!(
+        "http://localhost:{}/solr/{}/update?commit=true",
+        port, CORE
+    ))
+    .send_json(docs)?;
+
+    Ok(())
+}
+
+fn query(port: u16, q: &str) -> Result<SolrQuery, Box<dyn Error>> {
+    let mut url = url::Url::parse(&format!("http://localhost:{}/solr/{}/select", port, CORE))?;
+    url.query_pairs_mut().append_pair("q", q);
+
+    Ok(SolrQuery::new(url)?)
+}
+
+fn matching_ids(query: &SolrQuery) -> Result<BTreeSet<String>, Box<dyn Error>> {
+    let mut url = query.url.clone();
+    url.query_pairs_mut()
+        .append_pair("fl", "id")
+        .append_pair("rows", "100")
+        .append_pair("wt", "json");
+
+    let response: Value = ureq::get(url.as_str()).call()?.into_json()?;
+
+    Ok(response["response"]["docs"]
+        .as_array()
+        .ok_or("Solr response has no docs")?
+        .iter()
+        .filter_map(|doc| doc["id"].as_str().map(|id| id.to_string()))
+        .collect())
+}
+
+fn ids(ids: &[&str]) -> BTreeSet<String> {
+    ids.iter().map(|id| id.to_string()).collect()
+}
+
+#[test]
+fn should_select_expected_documents() -> Result<(), Box<dyn Error>> {
+    let docker = Cli::default();
+    let solr = docker.run(solr_image());
+    let port = solr.get_host_port_ipv4(8983);
+
+    index_fixtures(port)?;
+
+    let english = query(port, "lang_s:en")?;
+    let recent = query(port, "year_i:[2020 TO *]")?;
+
+    assert_eq!(matching_ids(&english)?, ids(&["1", "2"]));
+    assert_eq!(matching_ids(&english.inverse())?, ids(&["3", "4"]));
+
+    let inner_join = english.inner_join(&recent)?;
+    assert_eq!(matching_ids(&inner_join)?, ids(&["2"]));
+    assert_eq!(matching_ids(&inner_join.inverse())?, ids(&["1"]));
+
+    let chain = QueryChain::new(vec![english, recent, query(port, "id:2 OR id:3")?]);
+    let steps = chain
+        .map(|step| matching_ids(&step))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    assert_eq!(steps, vec![ids(&["1", "2"]), ids(&["2"]), ids(&["2"])]);
+
+    Ok(())
+}
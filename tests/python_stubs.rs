@@ -0,0 +1,158 @@
+//! Checks that the hand-written type stubs of the Python module declare
+//! exactly the classes, methods and exceptions exposed by `src/bindings`.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+const STUBS: &str = include_str!("../solr_subquery.pyi");
+const BINDINGS: [&str; 3] = [
+    include_str!("../src/bindings/mod.rs"),
+    include_str!("../src/bindings/docs.rs"),
+    include_str!("../src/bindings/exceptions.rs"),
+];
+
+/// Methods inherited from `object` or only used by pickle, not declared in the stubs
+const UNDECLARED: [&str; 5] = [
+    "__str__",
+    "__repr__",
+    "__getnewargs__",
+    "__getstate__",
+    "__setstate__",
+];
+
+type Members = BTreeMap<String, BTreeSet<String>>;
+
+/// Gets the members of the classes declared in the stubs, and the base of each class
+fn stub_members() -> (Members, BTreeMap<String, String>) {
+    let mut members = Members::new();
+    let mut bases = BTreeMap::new();
+    let mut class = None;
+
+    for line in STUBS.lines() {
+        if let Some(declaration) = line.strip_prefix("class ") {
+            let name_end = declaration.find(['(', ':']).unwrap_or(declaration.len());
+            let name = declaration[..name_end].to_string();
+
+            if let Some(base) = declaration[name_end..].strip_prefix('(') {
+                let base = &base[..base.rfind(')').unwrap_or(base.len())];
+                bases.insert(name.clone(), base.to_string());
+            }
+
+            members.insert(name.clone(), BTreeSet::new());
+            class = Some(name);
+        } else if let Some(member) = line.strip_prefix("    ") {
+            let member = member.trim();
+            let name = if let Some(def) = member.strip_prefix("def ") {
+                def.split('(').next()
+            } else if member.starts_with('@') || member.starts_with('#') {
+                None
+            } else if member.contains("ClassVar[None]") {
+                // `__hash__: ClassVar[None]` marks an unhashable class
+                None
+            } else {
+                member.split(':').next()
+            };
+
+            if let (Some(class), Some(name)) = (&class, name) {
+                members
+                    .get_mut(class)
+                    .expect("member of an undeclared class")
+                    .insert(name.trim().to_string());
+            }
+        } else if !line.is_empty() {
+            class = None;
+        }
+    }
+
+    (members, bases)
+}
+
+/// Gets the members of the classes and exceptions defined in the bindings, and the base of each exception
+fn binding_members() -> (Members, BTreeMap<String, String>) {
+    let mut members = Members::new();
+    let mut bases = BTreeMap::new();
+
+    for source in BINDINGS {
+        let mut lines = source.lines();
+
+        while let Some(line) = lines.next() {
+            let line = line.trim();
+
+            if let Some(arguments) = line.strip_prefix("create_exception!(") {
+                let arguments = arguments.trim_end_matches(");").split(", ");
+                let names = arguments.skip(1).collect::<Vec<_>>();
+                let base = names[1].strip_prefix("Py").unwrap_or(names[1]);
+
+                members.insert(names[0].to_string(), BTreeSet::new());
+                bases.insert(names[0].to_string(), base.to_string());
+            } else if line == "#[pyclass]" {
+                let declaration = lines.next().expect("declaration after #[pyclass]");
+                let name = declaration.split_whitespace().nth(2).expect("pyclass name");
+                let class = members.entry(name.to_string()).or_default();
+
+                if declaration.starts_with("pub enum") {
+                    for variant in lines.by_ref().map(str::trim) {
+                        if variant == "}" {
+                            break;
+                        }
+                        class.insert(variant.trim_end_matches(',').to_string());
+                    }
+                }
+            } else if line == "#[pymethods]" {
+                let declaration = lines.next().expect("impl after #[pymethods]");
+                let name = declaration
+                    .split_whitespace()
+                    .nth(1)
+                    .expect("pymethods type");
+                let class = members.entry(name.to_string()).or_default();
+                let mut constructor = false;
+
+                for method in lines.by_ref() {
+                    if method == "}" {
+                        break;
+                    }
+
+                    let method = method.trim();
+                    if method == "#[new]" {
+                        constructor = true;
+                    } else if let Some(signature) = method.strip_prefix("fn ") {
+                        let name = signature.split(['(', '<']).next().expect("method name");
+
+                        match name {
+                            _ if constructor => {
+                                class.insert("__init__".to_string());
+                            }
+                            "__richcmp__" => {
+                                class.insert("__eq__".to_string());
+                                class.insert("__ne__".to_string());
+                            }
+                            _ if UNDECLARED.contains(&name) => {}
+                            _ => {
+                                class.insert(name.to_string());
+                            }
+                        }
+                        constructor = false;
+                    }
+                }
+            }
+        }
+    }
+
+    (members, bases)
+}
+
+#[test]
+fn should_declare_every_binding_in_the_stubs() {
+    let (stubs, stub_bases) = stub_members();
+    let (bindings, exception_bases) = binding_members();
+
+    assert_eq!(stubs, bindings);
+
+    for (exception, base) in exception_bases {
+        assert_eq!(
+            stub_bases.get(&exception),
+            Some(&base),
+            "base of {}",
+            exception
+        );
+    }
+}